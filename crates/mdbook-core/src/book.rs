@@ -1,7 +1,8 @@
 //! A tree structure representing a book.
 
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt::{self, Display, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
@@ -206,6 +207,149 @@ impl Chapter {
     }
 }
 
+/// Metadata attached to a chapter with a front matter block.
+///
+/// A chapter may start with a YAML block delimited by `---` lines, or a TOML
+/// block delimited by `+++` lines. The block is removed from the chapter's
+/// content when the book is loaded, and the metadata of each chapter is given
+/// to preprocessors and renderers in the `chapter_metadata` of their context.
+///
+/// ```markdown
+/// ---
+/// description: An introduction to the book.
+/// authors: [Alice, Bob]
+/// tags: [intro]
+/// ---
+///
+/// # Introduction
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ChapterMetadata {
+    /// A short description of the chapter.
+    ///
+    /// The HTML renderer uses this for the page's `<meta name="description">`
+    /// instead of the book's description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The authors of the chapter.
+    #[serde(
+        deserialize_with = "string_or_seq",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub authors: Vec<String>,
+    /// Tags or keywords for the chapter.
+    #[serde(
+        deserialize_with = "string_or_seq",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+    /// Whether or not the chapter is a work in progress.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    /// Any other keys in the front matter.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ChapterMetadata {
+    /// Returns `true` if no metadata has been set.
+    pub fn is_empty(&self) -> bool {
+        self == &ChapterMetadata::default()
+    }
+
+    /// Splits a front matter block off the start of `content`.
+    ///
+    /// Returns the parsed metadata and the remaining content. If `content`
+    /// does not start with a front matter block, the metadata is empty and
+    /// the content is returned unchanged.
+    ///
+    /// A YAML block starts with a `---` line and ends with a `---` or `...`
+    /// line. A TOML block starts and ends with a `+++` line. To avoid
+    /// confusing a leading thematic break with front matter, the line after
+    /// the opening delimiter must not be blank, and a YAML block must be a
+    /// mapping. Otherwise, such as for a thematic break followed by a setext
+    /// heading, the content is returned unchanged.
+    pub fn split_front_matter(content: &str) -> Result<(ChapterMetadata, &str)> {
+        let Some((kind, block, rest)) = find_front_matter(content) else {
+            return Ok((ChapterMetadata::default(), content));
+        };
+        let metadata = match kind {
+            FrontMatterKind::Yaml => {
+                // An empty YAML document is null, which is not a map.
+                if block.trim().is_empty() {
+                    ChapterMetadata::default()
+                } else {
+                    match serde_yaml_ng::from_str(block) {
+                        Ok(value @ serde_yaml_ng::Value::Mapping(_)) => {
+                            serde_yaml_ng::from_value(value)
+                                .context("failed to parse YAML front matter")?
+                        }
+                        _ => return Ok((ChapterMetadata::default(), content)),
+                    }
+                }
+            }
+            FrontMatterKind::Toml => {
+                toml::from_str(block).context("failed to parse TOML front matter")?
+            }
+        };
+        Ok((metadata, rest))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrontMatterKind {
+    Yaml,
+    Toml,
+}
+
+/// Finds the front matter at the start of `content`, returning its kind, the
+/// text between the delimiters, and the text after the closing delimiter.
+fn find_front_matter(content: &str) -> Option<(FrontMatterKind, &str, &str)> {
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    let (kind, closers): (_, &[&str]) = match first.trim_end() {
+        "---" => (FrontMatterKind::Yaml, &["---", "..."]),
+        "+++" => (FrontMatterKind::Toml, &["+++"]),
+        _ => return None,
+    };
+    if !first.ends_with('\n') {
+        return None;
+    }
+    let block_start = first.len();
+    let mut offset = block_start;
+    for (i, line) in lines.enumerate() {
+        if i == 0 && line.trim().is_empty() {
+            return None;
+        }
+        if closers.contains(&line.trim_end()) {
+            let block = &content[block_start..offset];
+            let rest = &content[offset + line.len()..];
+            return Some((kind, block, rest));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn string_or_seq<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrSeq {
+        String(String),
+        Seq(Vec<String>),
+    }
+
+    Ok(match StringOrSeq::deserialize(deserializer)? {
+        StringOrSeq::String(s) => vec![s],
+        StringOrSeq::Seq(v) => v,
+    })
+}
+
 impl Display for Chapter {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         if let Some(ref section_number) = self.number {
@@ -121,3 +121,46 @@ fn iterate_over_nested_book_items() {
 
     assert_eq!(chapter_names, should_be);
 }
+
+#[test]
+fn split_yaml_front_matter() {
+    let content = "---\ndescription: Hello\nauthors: Alice\ncustom: [1, 2]\n---\n\n# Chapter\n";
+    let (metadata, rest) = ChapterMetadata::split_front_matter(content).unwrap();
+    assert_eq!(rest, "\n# Chapter\n");
+    assert_eq!(metadata.description.as_deref(), Some("Hello"));
+    assert_eq!(metadata.authors, ["Alice"]);
+    assert_eq!(metadata.extra["custom"], serde_json::json!([1, 2]));
+}
+
+#[test]
+fn split_toml_front_matter() {
+    let content = "+++\r\ntags = [\"a\", \"b\"]\r\ndraft = true\r\n+++\r\n# Chapter\r\n";
+    let (metadata, rest) = ChapterMetadata::split_front_matter(content).unwrap();
+    assert_eq!(rest, "# Chapter\r\n");
+    assert_eq!(metadata.tags, ["a", "b"]);
+    assert!(metadata.draft);
+}
+
+#[test]
+fn split_front_matter_ignores_other_content() {
+    for content in [
+        "# Chapter\n",
+        "---\n\nA thematic break.\n\n---\n",
+        "---\ndescription: never closed\n",
+        // A thematic break and a setext heading.
+        "---\nA heading\n---\n\nText.\n",
+        "---\n- not a map\n---\n",
+        "---",
+        "",
+    ] {
+        let (metadata, rest) = ChapterMetadata::split_front_matter(content).unwrap();
+        assert!(metadata.is_empty());
+        assert_eq!(rest, content);
+    }
+}
+
+#[test]
+fn split_front_matter_invalid() {
+    let err = ChapterMetadata::split_front_matter("---\nauthors: {a: 1}\n---\n").unwrap_err();
+    assert_eq!(err.to_string(), "failed to parse YAML front matter");
+}
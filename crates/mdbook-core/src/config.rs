@@ -46,7 +46,9 @@
 use crate::static_regex;
 use crate::utils::{TomlExt, fs, log_backtrace};
 use anyhow::{Context, Error, Result, bail};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::{Path, PathBuf};
@@ -55,9 +57,13 @@ use toml::Value;
 use toml::value::Table;
 use tracing::{debug, trace};
 
+pub use self::schema::{ConfigSchema, SchemaRegistry, UnknownKey};
+
+mod schema;
+
 /// The overall configuration object for MDBook, essentially an in-memory
 /// representation of `book.toml`.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Config {
@@ -71,10 +77,19 @@ pub struct Config {
     pub rust: RustConfig,
     /// The renderer configurations.
     #[serde(skip_serializing_if = "toml_is_empty")]
+    #[schemars(schema_with = "output_schema")]
     output: Value,
     /// The preprocessor configurations.
     #[serde(skip_serializing_if = "toml_is_empty")]
+    #[schemars(schema_with = "preprocessor_schema")]
     preprocessor: Value,
+    /// Named profiles that can be overlaid on top of the rest of the config.
+    #[serde(skip_serializing_if = "toml_is_empty")]
+    #[schemars(schema_with = "profile_schema")]
+    profile: Value,
+    /// The languages of a multilingual book, by language code.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub language: BTreeMap<String, LanguageConfig>,
 }
 
 /// Helper for serde serialization.
@@ -87,6 +102,85 @@ fn toml_is_empty(table: &Value) -> bool {
     table.as_table().unwrap().is_empty()
 }
 
+/// The JSON Schema of the `output` table.
+fn output_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "properties": {
+            "html": generator.subschema_for::<HtmlConfig>(),
+            "epub": generator.subschema_for::<EpubConfig>(),
+        },
+        "additionalProperties": {
+            "type": "object",
+            "properties": {
+                "command": {
+                    "description": "The command to run the renderer.",
+                    "type": "string",
+                },
+                "optional": {
+                    "description": "Don't fail the build if the command is not found.",
+                    "type": "boolean",
+                    "default": false,
+                },
+                "known-keys": {
+                    "description": "The other keys of the table, which are checked for typos.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                },
+            },
+        },
+    })
+}
+
+/// The JSON Schema of the `preprocessor` table.
+fn preprocessor_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": {
+            "type": "object",
+            "properties": {
+                "command": {
+                    "description": "The command to run the preprocessor.",
+                    "type": "string",
+                },
+                "renderers": {
+                    "description": "The renderers that the preprocessor runs for.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                },
+                "before": {
+                    "description": "Preprocessors that must run after this one.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                },
+                "after": {
+                    "description": "Preprocessors that must run before this one.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                },
+                "optional": {
+                    "description": "Don't fail the build if the command is not found.",
+                    "type": "boolean",
+                    "default": false,
+                },
+                "known-keys": {
+                    "description": "The other keys of the table, which are checked for typos.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                },
+            },
+        },
+    })
+}
+
+/// The JSON Schema of the `profile` table.
+fn profile_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": {"$ref": "#"},
+    })
+}
+
 impl FromStr for Config {
     type Err = Error;
 
@@ -104,11 +198,44 @@ impl Default for Config {
             rust: RustConfig::default(),
             output: Value::Table(Table::default()),
             preprocessor: Value::Table(Table::default()),
+            profile: Value::Table(Table::default()),
+            language: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
+    /// Load a `Config` from some string, checking its keys against `schemas`.
+    ///
+    /// Unlike [`Config::from_str`], keys that are not part of a registered
+    /// schema are not an error. They are removed before the config is
+    /// deserialized, and returned along with it.
+    pub fn from_str_checked(
+        src: &str,
+        schemas: &SchemaRegistry,
+    ) -> Result<(Config, Vec<UnknownKey>)> {
+        let mut raw: Value = toml::from_str(src).with_context(|| "Invalid configuration file")?;
+        let unknown = schemas.check(&mut raw);
+        let config = if unknown.is_empty() {
+            // Deserialize from the source to keep the location in errors.
+            Config::from_str(src)?
+        } else {
+            raw.try_into()
+                .with_context(|| "Invalid configuration file")?
+        };
+        Ok((config, unknown))
+    }
+
+    /// Returns a [JSON Schema](https://json-schema.org/) of `book.toml`.
+    ///
+    /// The schema describes the built-in tables, including the
+    /// documentation and default value of each option.
+    pub fn json_schema() -> serde_json::Value {
+        let generator = schemars::generate::SchemaSettings::draft07().into_generator();
+        let schema = generator.into_root_schema_for::<Config>();
+        serde_json::to_value(schema).expect("schema should serialize")
+    }
+
     /// Load the configuration file from disk.
     pub fn from_disk<P: AsRef<Path>>(config_file: P) -> Result<Config> {
         let cfg = fs::read_to_string(config_file)?;
@@ -175,29 +302,193 @@ impl Config {
         Ok(())
     }
 
+    /// Overlays the profile with the given name on top of the configuration.
+    ///
+    /// A profile is a table under `profile.<name>` which can contain any of
+    /// the other top-level tables. Tables in the profile are merged with the
+    /// existing ones, while any other value (including arrays) replaces the
+    /// existing value. For example, with the following `book.toml`:
+    ///
+    /// ```toml
+    /// [output.html]
+    /// site-url = "/internal/"
+    ///
+    /// [profile.public.output.html]
+    /// site-url = "/"
+    /// ```
+    ///
+    /// applying the `public` profile changes `output.html.site-url` to `/`,
+    /// and leaves all other settings of the html renderer unchanged.
+    ///
+    /// Afterwards, `build.profile` is set to the name of the profile.
+    ///
+    /// # Errors
+    ///
+    /// This will fail if the profile does not exist, or if the resulting
+    /// configuration is not valid.
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        debug!("Applying the `{name}` profile");
+        let Some(overlay) = self.profile.get(name) else {
+            let available: Vec<_> = self
+                .profile
+                .as_table()
+                .into_iter()
+                .flat_map(|table| table.keys())
+                .map(|name| format!("`{name}`"))
+                .collect();
+            if available.is_empty() {
+                bail!("profile `{name}` not found, no profiles are defined in book.toml");
+            }
+            bail!(
+                "profile `{name}` not found, available profiles are: {}",
+                available.join(", ")
+            );
+        };
+        if !overlay.is_table() {
+            bail!("profile `{name}` must be a table");
+        }
+        if overlay.get("profile").is_some() {
+            bail!("profile `{name}` cannot contain other profiles");
+        }
+        let mut raw = Value::try_from(&*self).expect("unreachable");
+        raw.merge(overlay.clone());
+        let mut updated: Config = raw
+            .try_into()
+            .with_context(|| format!("failed to apply profile `{name}`"))?;
+        updated.build.profile = Some(name.to_string());
+        *self = updated;
+        Ok(())
+    }
+
+    /// Whether the book is written in more than one language, which is when
+    /// there is at least one `[language.<code>]` table.
+    pub fn is_multilingual(&self) -> bool {
+        !self.language.is_empty()
+    }
+
+    /// The default language of a multilingual book, which is the one with
+    /// `default = true` in its `[language.<code>]` table.
+    ///
+    /// # Errors
+    ///
+    /// This will fail if there isn't exactly one default language.
+    pub fn default_language(&self) -> Result<&str> {
+        let defaults: Vec<_> = self
+            .language
+            .iter()
+            .filter(|(_, language)| language.default)
+            .map(|(code, _)| code.as_str())
+            .collect();
+        match defaults[..] {
+            [code] => Ok(code),
+            [] => bail!(
+                "a multilingual book needs a default language, \
+                 set `default = true` in one of the `[language]` tables"
+            ),
+            _ => bail!(
+                "a multilingual book can only have one default language, found `{}`",
+                defaults.join("`, `")
+            ),
+        }
+    }
+
+    /// Returns the configuration for building a multilingual book in the
+    /// language `code`.
+    ///
+    /// The source of each language is in the `code` directory of `book.src`,
+    /// and it's built into the `code` directory of `build.build-dir`.
+    /// `book.language` is set to `code`, and the title, description, and text
+    /// direction in `[language.<code>]` replace the ones in `[book]`. The
+    /// `[language]` table is kept, so that renderers can link to the other
+    /// languages.
+    ///
+    /// # Errors
+    ///
+    /// This will fail if there is no `[language.<code>]` table.
+    pub fn for_language(&self, code: &str) -> Result<Config> {
+        let Some(language) = self.language.get(code) else {
+            bail!("language `{code}` not found, there is no `[language.{code}]` table");
+        };
+        let mut config = self.clone();
+        config.book.src = self.book.src.join(code);
+        config.build.build_dir = self.build.build_dir.join(code);
+        config.book.language = Some(code.to_string());
+        if let Some(title) = &language.title {
+            config.book.title = Some(title.clone());
+        }
+        if let Some(description) = &language.description {
+            config.book.description = Some(description.clone());
+        }
+        // The text direction in `[book]` is for the default language.
+        config.book.text_direction = match language.text_direction {
+            Some(direction) => Some(direction),
+            None if language.default => self.book.text_direction,
+            None => None,
+        };
+        Ok(config)
+    }
+
+    /// The source directory of the default language, which chapters that
+    /// haven't been translated are read from.
+    ///
+    /// This is `None` unless this is the configuration of a language other
+    /// than the default one, from [`Config::for_language`].
+    pub fn fallback_src(&self) -> Option<PathBuf> {
+        let code = self.book.language.as_deref()?;
+        let default = self.default_language().ok()?;
+        if !self.language.contains_key(code) || code == default {
+            return None;
+        }
+        // The source directories of the languages are next to each other.
+        Some(self.book.src.parent()?.join(default))
+    }
+
+    /// The path of the file of the chapter with the given
+    /// [`Chapter::source_path`], relative to `root`, the root of the book.
+    ///
+    /// This is in `book.src`, or in the [`Config::fallback_src`] if the
+    /// chapter hasn't been translated.
+    ///
+    /// [`Chapter::source_path`]: crate::book::Chapter::source_path
+    pub fn chapter_file(&self, root: &Path, source_path: &Path) -> PathBuf {
+        let path = self.book.src.join(source_path);
+        if let Some(fallback) = self.fallback_src()
+            && !root.join(&path).exists()
+        {
+            let fallback = fallback.join(source_path);
+            if root.join(&fallback).exists() {
+                return fallback;
+            }
+        }
+        path
+    }
+
     /// Get a value from the configuration.
     ///
     /// This fetches a value from the book configuration. The key can have
     /// dotted indices to access nested items (e.g. `output.html.playground`
     /// will fetch the "playground" out of the html output table).
     ///
-    /// This can only access the `output` and `preprocessor` tables.
+    /// The `book`, `build`, and `rust` tables include the default values of
+    /// the options that are not set.
     ///
     /// Returns `Ok(None)` if the field is not set.
     ///
-    /// Returns `Err` if it fails to deserialize.
+    /// Returns `Err` if it fails to deserialize, or the key is not in one of
+    /// the top-level tables.
     pub fn get<'de, T: Deserialize<'de>>(&self, name: &str) -> Result<Option<T>> {
-        let (key, table) = if let Some(key) = name.strip_prefix("output.") {
-            (key, &self.output)
-        } else if let Some(key) = name.strip_prefix("preprocessor.") {
-            (key, &self.preprocessor)
-        } else {
+        let (head, key) = split_key(name);
+        let Some(table) = self.table(head) else {
             bail!(
-                "unable to get `{name}`, only `output` and `preprocessor` table entries are allowed"
+                "unable to get `{name}`, expected a key in the `book`, `build`, `rust`, \
+                 `output`, `preprocessor`, `profile`, or `language` table"
             );
         };
-        table
-            .read(key)
+        let value = match key {
+            Some(key) => table.read(key),
+            None => Some(&*table),
+        };
+        value
             .map(|value| {
                 value
                     .clone()
@@ -212,16 +503,40 @@ impl Config {
     /// The key can have dotted indices to access nested items (e.g.
     /// `preprocessor.foo.bar` will check if that key is set in the config).
     ///
-    /// This can only access the `output` and `preprocessor` tables.
+    /// Like [`Config::get`], the `book`, `build`, and `rust` tables include
+    /// the options that are not set.
     pub fn contains_key(&self, name: &str) -> bool {
-        if let Some(key) = name.strip_prefix("output.") {
-            self.output.read(key)
-        } else if let Some(key) = name.strip_prefix("preprocessor.") {
-            self.preprocessor.read(key)
-        } else {
+        let (head, key) = split_key(name);
+        let Some(table) = self.table(head) else {
             panic!("invalid key `{name}`");
+        };
+        match key {
+            Some(key) => table.read(key).is_some(),
+            None => true,
         }
-        .is_some()
+    }
+
+    /// Returns the top-level table with the given name.
+    fn table(&self, name: &str) -> Option<Cow<'_, Value>> {
+        let value = match name {
+            "book" => {
+                let mut book = Value::try_from(&self.book).expect("unreachable");
+                // `src` is skipped when serializing if it is the default.
+                if book.get("src").is_none() {
+                    let src = Value::try_from(&self.book.src).expect("unreachable");
+                    book.insert("src", src);
+                }
+                book
+            }
+            "build" => Value::try_from(&self.build).expect("unreachable"),
+            "rust" => Value::try_from(&self.rust).expect("unreachable"),
+            "output" => return Some(Cow::Borrowed(&self.output)),
+            "preprocessor" => return Some(Cow::Borrowed(&self.preprocessor)),
+            "profile" => return Some(Cow::Borrowed(&self.profile)),
+            "language" => Value::try_from(&self.language).expect("unreachable"),
+            _ => return None,
+        };
+        Some(Cow::Owned(value))
     }
 
     /// Returns the configuration for all preprocessors.
@@ -287,6 +602,10 @@ impl Config {
             self.output = value;
         } else if index == "preprocessor" {
             self.preprocessor = value;
+        } else if index == "profile" {
+            self.profile = value;
+        } else if index == "language" {
+            self.language = value.try_into()?;
         } else if let Some(key) = index.strip_prefix("book.") {
             self.book.update_value(key, value)?;
         } else if let Some(key) = index.strip_prefix("build.") {
@@ -297,6 +616,10 @@ impl Config {
             self.output.update_value(key, value)?;
         } else if let Some(key) = index.strip_prefix("preprocessor.") {
             self.preprocessor.update_value(key, value)?;
+        } else if let Some(key) = index.strip_prefix("profile.") {
+            self.profile.update_value(key, value)?;
+        } else if let Some(key) = index.strip_prefix("language.") {
+            self.language.update_value(key, value)?;
         } else {
             bail!("invalid key `{index}`");
         }
@@ -305,6 +628,14 @@ impl Config {
     }
 }
 
+/// Splits a dotted key into the top-level table and the rest of the key.
+fn split_key(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('.') {
+        Some((head, key)) => (head, Some(key)),
+        None => (name, None),
+    }
+}
+
 fn parse_env(key: &str) -> Option<String> {
     key.strip_prefix("MDBOOK_")
         .map(|key| key.to_lowercase().replace("__", ".").replace('_', "-"))
@@ -312,7 +643,7 @@ fn parse_env(key: &str) -> Option<String> {
 
 /// Configuration options which are specific to the book and required for
 /// loading it from disk.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct BookConfig {
@@ -363,7 +694,7 @@ impl BookConfig {
 }
 
 /// Text direction to use for HTML output
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[non_exhaustive]
 pub enum TextDirection {
     /// Left to right.
@@ -387,8 +718,30 @@ impl TextDirection {
     }
 }
 
+/// Configuration for one of the languages of a multilingual book, in the
+/// `[language.<code>]` table.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LanguageConfig {
+    /// Whether this is the default language, which the chapters that haven't
+    /// been translated are taken from.
+    pub default: bool,
+    /// The name of the language shown to readers, such as `Deutsch`. Defaults
+    /// to the language code.
+    pub name: Option<String>,
+    /// The book's title in this language, instead of `book.title`.
+    pub title: Option<String>,
+    /// The book's description in this language, instead of
+    /// `book.description`.
+    pub description: Option<String>,
+    /// The direction of text in this language. When not specified, the text
+    /// direction is derived from the language code.
+    pub text_direction: Option<TextDirection>,
+}
+
 /// Configuration for the build procedure.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct BuildConfig {
@@ -402,6 +755,12 @@ pub struct BuildConfig {
     pub use_default_preprocessors: bool,
     /// Extra directories to trigger rebuild when watching/serving
     pub extra_watch_dirs: Vec<PathBuf>,
+    /// The profile to apply when none is given on the command line.
+    ///
+    /// After a profile has been applied, this is the name of that profile.
+    pub profile: Option<String>,
+    /// The order of the chapters added by a glob entry in `SUMMARY.md`.
+    pub summary_glob_order: GlobOrder,
 }
 
 impl Default for BuildConfig {
@@ -411,12 +770,27 @@ impl Default for BuildConfig {
             create_missing: true,
             use_default_preprocessors: true,
             extra_watch_dirs: Vec::new(),
+            profile: None,
+            summary_glob_order: GlobOrder::default(),
         }
     }
 }
 
+/// The order of the chapters that a glob entry in `SUMMARY.md` expands to.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum GlobOrder {
+    /// Sorted by file name, comparing runs of digits as numbers so that
+    /// `2-intro.md` comes before `10-usage.md`.
+    #[default]
+    Name,
+    /// Sorted by the title of the chapter.
+    Title,
+}
+
 /// Configuration for the Rust compiler(e.g., for playground)
-#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct RustConfig {
@@ -425,7 +799,7 @@ pub struct RustConfig {
 }
 
 /// Rust edition to use for the code.
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[non_exhaustive]
 pub enum RustEdition {
     /// The 2024 edition of Rust
@@ -443,7 +817,7 @@ pub enum RustEdition {
 }
 
 /// Configuration for the HTML renderer.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct HtmlConfig {
@@ -476,6 +850,8 @@ pub struct HtmlConfig {
     pub code: Code,
     /// Print settings.
     pub print: Print,
+    /// Settings for the "On this page" outline of each chapter.
+    pub page_toc: PageToc,
     /// Don't render section labels.
     pub no_section_label: bool,
     /// Search settings. If `None`, the default will be used.
@@ -506,6 +882,7 @@ pub struct HtmlConfig {
     ///
     /// This config item *should not be edited* by the end user.
     #[doc(hidden)]
+    #[schemars(skip)]
     pub live_reload_endpoint: Option<String>,
     /// The mapping from old pages to new pages/URLs to use when generating
     /// redirects.
@@ -518,6 +895,15 @@ pub struct HtmlConfig {
     /// If enabled, the sidebar includes navigation for headers on the current
     /// page. Default is `true`.
     pub sidebar_header_nav: bool,
+    /// If enabled, only chapters that have changed since the last build are
+    /// rendered again. Default is `false`.
+    pub incremental: bool,
+    /// How to report links to pages or anchors that don't exist. Default is
+    /// [`LinkCheckMode::Off`].
+    pub check_links: LinkCheckMode,
+    /// Strings of the user interface that replace the built-in ones, by
+    /// language code and then by the name of the string.
+    pub ui_strings: BTreeMap<String, BTreeMap<String, String>>,
 }
 
 impl Default for HtmlConfig {
@@ -536,6 +922,7 @@ impl Default for HtmlConfig {
             playground: Playground::default(),
             code: Code::default(),
             print: Print::default(),
+            page_toc: PageToc::default(),
             no_section_label: false,
             search: None,
             git_repository_url: None,
@@ -548,6 +935,9 @@ impl Default for HtmlConfig {
             redirect: HashMap::new(),
             hash_files: true,
             sidebar_header_nav: true,
+            incremental: false,
+            check_links: LinkCheckMode::Off,
+            ui_strings: BTreeMap::new(),
         }
     }
 }
@@ -571,8 +961,22 @@ impl HtmlConfig {
     }
 }
 
+/// How the HTML renderer handles broken internal links.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum LinkCheckMode {
+    /// Links are not checked.
+    #[default]
+    Off,
+    /// Broken links are reported as warnings.
+    Warn,
+    /// Broken links are reported as errors, and the build fails.
+    Error,
+}
+
 /// Configuration for how to render the print icon, print.html, and print.css.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Print {
@@ -591,8 +995,30 @@ impl Default for Print {
     }
 }
 
+/// Configuration for the "On this page" outline of the headings in each
+/// chapter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct PageToc {
+    /// Whether the outline is shown. Default: `false`.
+    pub enable: bool,
+    /// The number of heading levels to include, starting from `h2`.
+    /// Default: `2`.
+    pub depth: u8,
+}
+
+impl Default for PageToc {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            depth: 2,
+        }
+    }
+}
+
 /// Configuration for how to fold chapters of sidebar.
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Fold {
@@ -605,7 +1031,7 @@ pub struct Fold {
 }
 
 /// Configuration for tweaking how the HTML renderer handles the playground.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Playground {
@@ -635,16 +1061,30 @@ impl Default for Playground {
 }
 
 /// Configuration for tweaking how the HTML renderer handles code blocks.
-#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Code {
     /// A prefix string to hide lines per language (one or more chars).
     pub hidelines: HashMap<String, String>,
+    /// How code blocks are syntax highlighted. Default: `highlightjs`.
+    pub highlighter: Highlighter,
+}
+
+/// The syntax highlighter to use for code blocks.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Highlighter {
+    /// Code blocks are highlighted in the browser with highlight.js.
+    #[default]
+    Highlightjs,
+    /// Code blocks are highlighted when the book is built.
+    Builtin,
 }
 
 /// Configuration of the search functionality of the HTML renderer.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct Search {
@@ -675,6 +1115,20 @@ pub struct Search {
     /// Copy JavaScript files for the search functionality to the output directory?
     /// Default: `true`.
     pub copy_js: bool,
+    /// The size in bytes above which the search index is split into shards
+    /// that are only downloaded when a search needs them. Default: `5000000`.
+    pub shard_threshold: u64,
+    /// Exclude code blocks from the search index. Default: `false`.
+    pub exclude_code: bool,
+    /// Exclude the elements with any of these classes from the search index.
+    /// Default: empty.
+    pub exclude_classes: Vec<String>,
+    /// Exclude the admonitions of these kinds, such as `note`, from the search
+    /// index. Default: empty.
+    pub exclude_admonitions: Vec<String>,
+    /// Words that are searched as the same word as their synonyms, such as
+    /// `async = ["futures", "await"]`. Default: empty.
+    pub synonyms: BTreeMap<String, Vec<String>>,
     /// Specifies search settings for the given path.
     ///
     /// The path can be for a specific chapter, or a directory. This will
@@ -696,18 +1150,39 @@ impl Default for Search {
             expand: true,
             heading_split_level: 3,
             copy_js: true,
+            shard_threshold: 5_000_000,
+            exclude_code: false,
+            exclude_classes: Vec::new(),
+            exclude_admonitions: Vec::new(),
+            synonyms: BTreeMap::new(),
             chapter: HashMap::new(),
         }
     }
 }
 
 /// Search options for chapters (or paths).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
 #[non_exhaustive]
 pub struct SearchChapterSettings {
     /// Whether or not indexing is enabled, default `true`.
     pub enable: Option<bool>,
+    /// Boost factor for the search result score of the results in the
+    /// chapter, default `1`.
+    pub boost: Option<f64>,
+}
+
+/// Configuration for the EPUB renderer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct EpubConfig {
+    /// The unique identifier of the publication, such as a URN or ISBN.
+    ///
+    /// If not set, an identifier is derived from the book's title and authors.
+    pub identifier: Option<String>,
+    /// Additional CSS stylesheets to include in every chapter.
+    pub additional_css: Vec<PathBuf>,
 }
 
 /// Allows you to "update" any arbitrary field in a struct by round-tripping via
@@ -782,6 +1257,8 @@ mod tests {
             create_missing: false,
             use_default_preprocessors: true,
             extra_watch_dirs: Vec::new(),
+            profile: None,
+            summary_glob_order: GlobOrder::Name,
         };
         let rust_should_be = RustConfig { edition: None };
         let playground_should_be = Playground {
@@ -1206,4 +1683,221 @@ mod tests {
         assert!(!cfg.contains_key("output.bar"));
         assert!(!cfg.contains_key("output.foo.sub.z"));
     }
+
+    #[test]
+    fn apply_profile() {
+        let src = r#"
+        [book]
+        title = "Internal"
+
+        [output.html]
+        site-url = "/internal/"
+        git-repository-url = "https://example.com/"
+
+        [preprocessor.drafts]
+
+        [profile.public.book]
+        title = "Public"
+
+        [profile.public.output.html]
+        site-url = "/"
+
+        [profile.public.preprocessor.drafts]
+        remove = true
+        "#;
+        let mut cfg = Config::from_str(src).unwrap();
+        cfg.apply_profile("public").unwrap();
+        assert_eq!(cfg.book.title.as_deref(), Some("Public"));
+        assert_eq!(cfg.build.profile.as_deref(), Some("public"));
+        let html = cfg.html_config().unwrap();
+        assert_eq!(html.site_url.as_deref(), Some("/"));
+        assert_eq!(
+            html.git_repository_url.as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            cfg.get::<bool>("preprocessor.drafts.remove").unwrap(),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn apply_missing_profile() {
+        let src = r#"
+        [profile.public]
+        [profile.staff]
+        "#;
+        let mut cfg = Config::from_str(src).unwrap();
+        let err = cfg.apply_profile("other").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "profile `other` not found, available profiles are: `public`, `staff`"
+        );
+
+        let mut cfg = Config::default();
+        let err = cfg.apply_profile("other").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "profile `other` not found, no profiles are defined in book.toml"
+        );
+    }
+
+    #[test]
+    fn apply_invalid_profile() {
+        let src = r#"
+        [profile.public.build]
+        create-missing = "yes"
+        "#;
+        let mut cfg = Config::from_str(src).unwrap();
+        let err = cfg.apply_profile("public").unwrap_err();
+        assert_eq!(err.to_string(), "failed to apply profile `public`");
+    }
+
+    #[test]
+    fn from_str_checked_removes_unknown_keys() {
+        let src = r#"
+        [book]
+        title = "Example"
+        tilte = "Typo"
+
+        [output.html]
+        additonal-css = ["custom.css"]
+        "#;
+        let (cfg, unknown) = Config::from_str_checked(src, &SchemaRegistry::new()).unwrap();
+        assert_eq!(cfg.book.title.as_deref(), Some("Example"));
+        assert_eq!(cfg.html_config().unwrap(), HtmlConfig::default());
+        let keys: Vec<_> = unknown.iter().map(|key| key.key.as_str()).collect();
+        assert_eq!(keys, ["book.tilte", "output.html.additonal-css"]);
+    }
+
+    #[test]
+    fn json_schema() {
+        let schema = Config::json_schema();
+        let html = &schema["definitions"]["HtmlConfig"];
+        assert_eq!(html["additionalProperties"], false);
+        assert_eq!(
+            html["properties"]["additional-css"]["description"],
+            "Additional CSS stylesheets to include in the rendered page's `<head>`."
+        );
+        assert_eq!(html["properties"]["hash-files"]["default"], true);
+        assert!(html["properties"].get("live-reload-endpoint").is_none());
+        assert_eq!(
+            schema["properties"]["output"]["properties"]["html"]["$ref"],
+            "#/definitions/HtmlConfig"
+        );
+    }
+
+    #[test]
+    fn get_from_any_table() {
+        let src = r#"
+        [book]
+        title = "Example"
+
+        [build]
+        create-missing = false
+        "#;
+        let cfg = Config::from_str(src).unwrap();
+        assert_eq!(
+            cfg.get::<String>("book.title").unwrap().as_deref(),
+            Some("Example")
+        );
+        assert_eq!(
+            cfg.get::<PathBuf>("book.src").unwrap(),
+            Some(PathBuf::from("src"))
+        );
+        assert_eq!(
+            cfg.get::<bool>("build.create-missing").unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            cfg.get::<PathBuf>("build.build-dir").unwrap(),
+            Some(PathBuf::from("book"))
+        );
+        assert_eq!(cfg.get::<String>("rust.edition").unwrap(), None);
+        assert!(cfg.contains_key("book.title"));
+        assert!(!cfg.contains_key("book.description"));
+        let err = cfg.get::<Value>("other.key").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unable to get `other.key`, expected a key in the `book`, `build`, `rust`, \
+             `output`, `preprocessor`, `profile`, or `language` table"
+        );
+    }
+
+    #[test]
+    fn config_for_language() {
+        let src = r#"
+        [book]
+        title = "Example"
+        description = "An example"
+        text-direction = "ltr"
+
+        [language.en]
+        default = true
+        name = "English"
+
+        [language.ar]
+        name = "العربية"
+        title = "مثال"
+        "#;
+        let cfg = Config::from_str(src).unwrap();
+        assert!(cfg.is_multilingual());
+        assert_eq!(cfg.default_language().unwrap(), "en");
+        assert_eq!(
+            cfg.get::<String>("language.ar.name").unwrap().as_deref(),
+            Some("العربية")
+        );
+
+        let en = cfg.for_language("en").unwrap();
+        assert_eq!(en.book.src, PathBuf::from("src/en"));
+        assert_eq!(en.build.build_dir, PathBuf::from("book/en"));
+        assert_eq!(en.book.language.as_deref(), Some("en"));
+        assert_eq!(en.book.title.as_deref(), Some("Example"));
+        assert_eq!(en.book.text_direction, Some(TextDirection::LeftToRight));
+        assert_eq!(en.fallback_src(), None);
+
+        let ar = cfg.for_language("ar").unwrap();
+        assert_eq!(ar.book.src, PathBuf::from("src/ar"));
+        assert_eq!(ar.build.build_dir, PathBuf::from("book/ar"));
+        assert_eq!(ar.book.title.as_deref(), Some("مثال"));
+        assert_eq!(ar.book.description.as_deref(), Some("An example"));
+        assert_eq!(ar.book.text_direction, None);
+        assert_eq!(
+            ar.book.realized_text_direction(),
+            TextDirection::RightToLeft
+        );
+        assert_eq!(ar.language, cfg.language);
+        assert_eq!(ar.fallback_src(), Some(PathBuf::from("src/en")));
+        assert_eq!(cfg.fallback_src(), None);
+
+        let err = cfg.for_language("fr").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "language `fr` not found, there is no `[language.fr]` table"
+        );
+    }
+
+    #[test]
+    fn default_language_must_be_unique() {
+        let cfg = Config::from_str("[language.en]\n[language.fr]").unwrap();
+        let err = cfg.default_language().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a multilingual book needs a default language, \
+             set `default = true` in one of the `[language]` tables"
+        );
+
+        let src = r#"
+        [language.en]
+        default = true
+        [language.fr]
+        default = true
+        "#;
+        let cfg = Config::from_str(src).unwrap();
+        let err = cfg.default_language().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a multilingual book can only have one default language, found `en`, `fr`"
+        );
+    }
 }
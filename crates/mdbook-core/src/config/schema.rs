@@ -0,0 +1,586 @@
+//! Checking the keys of `book.toml` against the known configuration options.
+//!
+//! The keys of the built-in tables are discovered from the [`Deserialize`]
+//! implementations of the config structs, so the checks stay in sync with the
+//! structs as they change.
+
+use super::{BookConfig, BuildConfig, HtmlConfig, LanguageConfig, RustConfig};
+use crate::utils::suggest;
+use serde::de::value::{
+    BorrowedStrDeserializer, Error as DeError, MapDeserializer, SeqDeserializer,
+};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use toml::Value;
+
+/// The keys that are always allowed in a third-party `[preprocessor.*]` table.
+const PREPROCESSOR_KEYS: &[&str] = &[
+    "command",
+    "renderers",
+    "before",
+    "after",
+    "optional",
+    KNOWN_KEYS,
+];
+
+/// The keys that are always allowed in a third-party `[output.*]` table.
+const OUTPUT_KEYS: &[&str] = &["command", "optional", KNOWN_KEYS];
+
+/// The key of a third-party preprocessor or renderer table that lists the
+/// other keys allowed in the table.
+const KNOWN_KEYS: &str = "known-keys";
+
+/// Describes which keys are allowed in a part of the configuration.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ConfigSchema {
+    /// Any value is allowed, and its contents are not checked.
+    Any,
+    /// A table which only allows the given keys.
+    Table(BTreeMap<String, ConfigSchema>),
+}
+
+impl ConfigSchema {
+    /// Creates the schema of a table with the given keys.
+    pub fn table<K: Into<String>>(keys: impl IntoIterator<Item = (K, ConfigSchema)>) -> Self {
+        ConfigSchema::Table(keys.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// Creates the schema of a table from a list of keys, where a dotted key
+    /// such as `style.color` is a key of a nested table.
+    ///
+    /// A key without nested keys allows any value.
+    pub fn from_keys<'a>(keys: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut schema = ConfigSchema::Table(BTreeMap::new());
+        for key in keys {
+            let mut table = &mut schema;
+            for part in key.split('.') {
+                if *table == ConfigSchema::Any {
+                    *table = ConfigSchema::Table(BTreeMap::new());
+                }
+                let ConfigSchema::Table(keys) = table else {
+                    unreachable!()
+                };
+                table = keys.entry(part.to_string()).or_insert(ConfigSchema::Any);
+            }
+        }
+        schema
+    }
+
+    /// Discovers the schema of a type from its [`Deserialize`] implementation.
+    ///
+    /// Structs become tables with their fields (including aliases) as keys,
+    /// and everything else allows any value. This works for types that use
+    /// `#[derive(Deserialize)]` with `#[serde(default)]`. If the keys cannot
+    /// be discovered, the schema is [`ConfigSchema::Any`].
+    pub fn of<T: DeserializeOwned>() -> Self {
+        let state = RefCell::new(TraceState::default());
+        // Each failure is caused by an alias of a field that was already
+        // seen, which is skipped on the next attempt.
+        loop {
+            let mut schema = ConfigSchema::Any;
+            let result = T::deserialize(Tracer {
+                schema: &mut schema,
+                state: &state,
+            });
+            let mut state = state.borrow_mut();
+            match (result, state.pending.take()) {
+                (Ok(_), _) => return schema,
+                (Err(_), Some(pending)) if state.skip.insert(pending) => {}
+                (Err(_), _) => return ConfigSchema::Any,
+            }
+        }
+    }
+}
+
+/// A key in the configuration that is not recognized.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct UnknownKey {
+    /// The full dotted path of the key, such as `output.html.foo`.
+    pub key: String,
+    /// A similar key that is allowed in the same table, if there is one.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key `{}` in book.toml", self.key)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// The schemas of the tables in the configuration which are checked for
+/// unknown keys.
+///
+/// By default this contains the `book`, `build`, `rust`, and `output.html`
+/// tables.
+///
+/// Preprocessors and renderers can add the schema of their own table with
+/// [`SchemaRegistry::register`], or by listing the keys of the table in its
+/// `known-keys` key, such as
+/// `known-keys = ["level", "style.color"]` in `[preprocessor.example]`.
+/// Tables which aren't registered are not checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaRegistry {
+    /// Map of the dotted path of a table to its schema.
+    tables: BTreeMap<String, ConfigSchema>,
+}
+
+impl Default for SchemaRegistry {
+    fn default() -> SchemaRegistry {
+        let tables = [
+            ("book", ConfigSchema::of::<BookConfig>()),
+            ("build", ConfigSchema::of::<BuildConfig>()),
+            ("rust", ConfigSchema::of::<RustConfig>()),
+            ("output.html", ConfigSchema::of::<HtmlConfig>()),
+        ]
+        .into_iter()
+        .map(|(table, schema)| (table.to_string(), schema))
+        .collect();
+        SchemaRegistry { tables }
+    }
+}
+
+impl SchemaRegistry {
+    /// Creates a registry with the schemas of the built-in tables.
+    pub fn new() -> SchemaRegistry {
+        SchemaRegistry::default()
+    }
+
+    /// Registers the schema of a table, such as `preprocessor.my-preprocessor`.
+    ///
+    /// The keys that mdBook itself uses in every preprocessor or renderer
+    /// table, such as `command`, are allowed in addition to the given ones.
+    pub fn register(&mut self, table: impl Into<String>, mut schema: ConfigSchema) {
+        let table = table.into();
+        if let ConfigSchema::Table(keys) = &mut schema {
+            let common = if table.starts_with("preprocessor.") {
+                PREPROCESSOR_KEYS
+            } else if table.starts_with("output.") {
+                OUTPUT_KEYS
+            } else {
+                &[]
+            };
+            for key in common {
+                keys.entry(key.to_string()).or_insert(ConfigSchema::Any);
+            }
+        }
+        self.tables.insert(table, schema);
+    }
+
+    /// Checks the keys of a raw config against the registered schemas.
+    ///
+    /// Unknown keys are removed from `config` and returned. The tables in
+    /// each `[profile.*]` are checked the same way, and each
+    /// `[language.*]` table is checked against [`LanguageConfig`]. The
+    /// `known-keys` of the preprocessor and renderer tables that aren't
+    /// registered are used as their schema.
+    pub fn check(&self, config: &mut Value) -> Vec<UnknownKey> {
+        let mut registry = self.clone();
+        registry.register_known_keys(config);
+        let mut unknown = Vec::new();
+        registry.check_root(config, "", &mut unknown);
+        if let Some(profiles) = config.get_mut("profile").and_then(Value::as_table_mut) {
+            for (name, profile) in profiles {
+                registry.check_root(profile, &format!("profile.{name}."), &mut unknown);
+            }
+        }
+        if let Some(languages) = config.get_mut("language").and_then(Value::as_table_mut) {
+            let schema = ConfigSchema::of::<LanguageConfig>();
+            for (code, language) in languages {
+                check_value(language, &schema, &format!("language.{code}"), &mut unknown);
+            }
+        }
+        unknown
+    }
+
+    /// Registers the `known-keys` of the preprocessor and renderer tables.
+    fn register_known_keys(&mut self, config: &Value) {
+        for section in ["preprocessor", "output"] {
+            let Some(tables) = config.get(section).and_then(Value::as_table) else {
+                continue;
+            };
+            for (name, table) in tables {
+                let table_path = format!("{section}.{name}");
+                let Some(keys) = table.get(KNOWN_KEYS).and_then(Value::as_array) else {
+                    continue;
+                };
+                if self.tables.contains_key(&table_path) {
+                    continue;
+                }
+                let keys = keys.iter().filter_map(Value::as_str);
+                self.register(table_path, ConfigSchema::from_keys(keys));
+            }
+        }
+    }
+
+    fn check_root(&self, root: &mut Value, prefix: &str, unknown: &mut Vec<UnknownKey>) {
+        for (table, schema) in &self.tables {
+            let mut value = Some(&mut *root);
+            for part in table.split('.') {
+                value = value.and_then(|v| v.get_mut(part));
+            }
+            if let Some(value) = value {
+                check_value(value, schema, &format!("{prefix}{table}"), unknown);
+            }
+        }
+    }
+}
+
+/// Checks a value against a schema, removing the keys that aren't allowed.
+fn check_value(
+    value: &mut Value,
+    schema: &ConfigSchema,
+    path: &str,
+    unknown: &mut Vec<UnknownKey>,
+) {
+    let (ConfigSchema::Table(keys), Value::Table(table)) = (schema, value) else {
+        return;
+    };
+    table.retain(|key, value| match keys.get(key) {
+        Some(schema) => {
+            check_value(value, schema, &format!("{path}.{key}"), unknown);
+            true
+        }
+        None => {
+            unknown.push(UnknownKey {
+                key: format!("{path}.{key}"),
+                suggestion: suggest(key, keys.keys().map(String::as_str)).map(str::to_string),
+            });
+            false
+        }
+    });
+}
+
+/// State shared by all of the [`Tracer`]s of a type.
+#[derive(Default)]
+struct TraceState {
+    /// The struct name and field that was last returned as a key, if its
+    /// value hasn't been deserialized yet.
+    pending: Option<(&'static str, &'static str)>,
+    /// Fields that are not returned as keys.
+    skip: HashSet<(&'static str, &'static str)>,
+}
+
+/// A deserializer which records the fields of the structs it is asked to
+/// deserialize, and provides an empty or zero value for everything else.
+struct Tracer<'a> {
+    schema: &'a mut ConfigSchema,
+    state: &'a RefCell<TraceState>,
+}
+
+macro_rules! trace_value {
+    ($($method:ident => $visit:ident($($value:expr)?),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = DeError;
+
+    trace_value! {
+        deserialize_any => visit_unit(),
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char(' '),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_unit => visit_unit(),
+        deserialize_identifier => visit_str(""),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<()>()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_map(MapDeserializer::new(std::iter::empty::<((), ())>()))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let keys = fields
+            .iter()
+            .map(|field| (field.to_string(), ConfigSchema::Any))
+            .collect();
+        *self.schema = ConfigSchema::Table(keys);
+        let ConfigSchema::Table(keys) = self.schema else {
+            unreachable!()
+        };
+        visitor.visit_map(StructAccess {
+            name,
+            fields: fields.iter(),
+            current: None,
+            keys,
+            state: self.state,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let variant = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(variant.into_deserializer())
+    }
+}
+
+/// Provides the fields of a struct as the keys of a map.
+struct StructAccess<'a> {
+    name: &'static str,
+    fields: std::slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+    keys: &'a mut BTreeMap<String, ConfigSchema>,
+    state: &'a RefCell<TraceState>,
+}
+
+impl<'de> de::MapAccess<'de> for StructAccess<'_> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        let field = {
+            let state = self.state.borrow();
+            self.fields
+                .find(|field| !state.skip.contains(&(self.name, **field)))
+        };
+        let Some(field) = field else {
+            return Ok(None);
+        };
+        self.current = Some(field);
+        self.state.borrow_mut().pending = Some((self.name, field));
+        seed.deserialize(BorrowedStrDeserializer::new(field))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        self.state.borrow_mut().pending = None;
+        let field = self.current.take().expect("value without key");
+        seed.deserialize(Tracer {
+            schema: self.keys.get_mut(field).unwrap(),
+            state: self.state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Default, Deserialize)]
+    #[serde(default, rename_all = "kebab-case")]
+    #[allow(dead_code)]
+    struct Example {
+        name: String,
+        count: u32,
+        #[serde(alias = "old-nested")]
+        nested: Nested,
+        optional: Option<Nested>,
+        map: BTreeMap<String, String>,
+        list: Vec<String>,
+        edition: Option<crate::config::RustEdition>,
+    }
+
+    #[derive(Deserialize, Default)]
+    #[serde(default)]
+    #[allow(dead_code)]
+    struct Nested {
+        inner: bool,
+    }
+
+    #[test]
+    fn schema_of_struct() {
+        let nested = ConfigSchema::table([("inner", ConfigSchema::Any)]);
+        assert_eq!(
+            ConfigSchema::of::<Example>(),
+            ConfigSchema::table([
+                ("name", ConfigSchema::Any),
+                ("count", ConfigSchema::Any),
+                ("nested", nested.clone()),
+                ("old-nested", ConfigSchema::Any),
+                ("optional", nested),
+                ("map", ConfigSchema::Any),
+                ("list", ConfigSchema::Any),
+                ("edition", ConfigSchema::Any),
+            ])
+        );
+    }
+
+    #[test]
+    fn schema_from_keys() {
+        assert_eq!(
+            ConfigSchema::from_keys(["level", "style.color", "style", "style.font.size"]),
+            ConfigSchema::table([
+                ("level", ConfigSchema::Any),
+                (
+                    "style",
+                    ConfigSchema::table([
+                        ("color", ConfigSchema::Any),
+                        ("font", ConfigSchema::table([("size", ConfigSchema::Any)])),
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn builtin_schemas() {
+        let registry = SchemaRegistry::new();
+        let ConfigSchema::Table(html) = &registry.tables["output.html"] else {
+            panic!("expected a table");
+        };
+        assert!(html.contains_key("additional-css"));
+        assert!(html.contains_key("playpen"));
+        assert!(matches!(html["search"], ConfigSchema::Table(_)));
+        assert!(matches!(html["playground"], ConfigSchema::Table(_)));
+        assert_eq!(html["redirect"], ConfigSchema::Any);
+    }
+
+    #[test]
+    fn check_unknown_keys() {
+        let mut registry = SchemaRegistry::new();
+        registry.register(
+            "preprocessor.example",
+            ConfigSchema::table([("level", ConfigSchema::Any)]),
+        );
+        let mut config: Value = toml::from_str(
+            r#"
+            [book]
+            title = "Example"
+            tilte = "Typo"
+
+            [output.html]
+            additonal-css = ["custom.css"]
+
+            [output.html.search]
+            limit-results = 10
+            unrelated = true
+
+            [output.other]
+            anything = true
+
+            [preprocessor.example]
+            command = "mdbook-example"
+            levle = 2
+
+            [preprocessor.declared]
+            known-keys = ["level", "style.color"]
+            level = 2
+            style = { color = "red", colour = "blue" }
+
+            [output.declared]
+            known-keys = []
+            levle = 2
+
+            [profile.public.output.html]
+            site-ulr = "/"
+
+            [profile.public.preprocessor.declared]
+            lvel = 3
+
+            [profile.public.build]
+            build-dri = "public"
+
+            [language.en]
+            default = true
+            nmae = "English"
+            "#,
+        )
+        .unwrap();
+        let unknown = registry.check(&mut config);
+        let messages: Vec<_> = unknown.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown key `book.tilte` in book.toml, did you mean `title`?",
+                "unknown key `output.declared.levle` in book.toml",
+                "unknown key `output.html.additonal-css` in book.toml, \
+                 did you mean `additional-css`?",
+                "unknown key `output.html.search.unrelated` in book.toml",
+                "unknown key `preprocessor.declared.style.colour` in book.toml, \
+                 did you mean `color`?",
+                "unknown key `preprocessor.example.levle` in book.toml, did you mean `level`?",
+                "unknown key `profile.public.build.build-dri` in book.toml, \
+                 did you mean `build-dir`?",
+                "unknown key `profile.public.output.html.site-ulr` in book.toml, \
+                 did you mean `site-url`?",
+                "unknown key `profile.public.preprocessor.declared.lvel` in book.toml, \
+                 did you mean `level`?",
+                "unknown key `language.en.nmae` in book.toml, did you mean `name`?",
+            ]
+        );
+        assert!(config.get("book").unwrap().get("tilte").is_none());
+        assert!(config.get("book").unwrap().get("title").is_some());
+        let html = config.get("output").unwrap().get("html").unwrap();
+        assert!(html.get("additonal-css").is_none());
+        assert!(html.get("search").unwrap().get("limit-results").is_some());
+    }
+}
@@ -0,0 +1,270 @@
+//! Diagnostics about problems in the source files of a book.
+//!
+//! A [`Diagnostic`] points to a location in a file, such as a line in
+//! `SUMMARY.md` that can't be parsed. It can be carried in an
+//! [`anyhow::Error`], in which case [`log_backtrace`] displays it with a
+//! snippet of the source. With [`MessageFormat::Json`], diagnostics are
+//! printed to stdout as JSON instead, one per line, so that other tools such
+//! as editors can show them.
+//!
+//! [`log_backtrace`]: crate::utils::log_backtrace
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, warn};
+
+/// Whether diagnostics are printed as JSON.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// How diagnostics are shown.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MessageFormat {
+    /// Logged to stderr, with a snippet of the source.
+    #[default]
+    Human,
+    /// Printed to stdout as JSON, one diagnostic per line.
+    Json,
+}
+
+/// Sets how [`Diagnostic::emit`] shows diagnostics for the rest of the
+/// process.
+pub fn set_message_format(format: MessageFormat) {
+    JSON.store(format == MessageFormat::Json, Ordering::Relaxed);
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Severity {
+    /// A problem that causes the output to be wrong or the build to fail.
+    Error,
+    /// A problem that should be fixed, but doesn't stop the build.
+    Warning,
+}
+
+/// A problem at a location in one of the source files of a book.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// A short identifier for the kind of problem, such as `summary-parse`.
+    pub code: String,
+    /// A description of the problem.
+    pub message: String,
+    /// The file with the problem, relative to the root of the book.
+    pub path: PathBuf,
+    /// The line where the problem starts, starting from 1.
+    pub line: usize,
+    /// The column where the problem starts, in characters, starting from 1.
+    pub column: usize,
+    /// The line where the problem ends.
+    pub end_line: usize,
+    /// The column just after the end of the problem.
+    pub end_column: usize,
+    /// The text of the line where the problem starts.
+    pub source_line: String,
+    /// Additional details, such as the underlying cause.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic about the text at the byte range `span` of
+    /// `source`, which is the content of the file at `path`.
+    pub fn new(
+        severity: Severity,
+        code: impl Into<String>,
+        message: impl Into<String>,
+        path: impl Into<PathBuf>,
+        source: &str,
+        span: Range<usize>,
+    ) -> Diagnostic {
+        let start = floor_char_boundary(source, span.start);
+        let end = floor_char_boundary(source, span.end.max(span.start));
+        let (line, column) = line_column(source, start);
+        let (end_line, end_column) = line_column(source, end);
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        Diagnostic {
+            severity,
+            code: code.into(),
+            message: message.into(),
+            path: path.into(),
+            line,
+            column,
+            end_line,
+            end_column,
+            source_line: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Adds a note with more details about the problem.
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Formats the diagnostic for the terminal, similar to `rustc`:
+    ///
+    /// ```text
+    /// [summary-parse] Suffix chapters cannot be followed by a list
+    ///  --> src/SUMMARY.md:5:1
+    ///   |
+    /// 5 | - [Appendix](appendix.md)
+    ///   | ^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let chars = self.source_line.chars().count();
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = if self.end_line == self.line {
+            self.end_column.saturating_sub(self.column)
+        } else {
+            (chars + 1).saturating_sub(self.column)
+        };
+
+        let mut out = format!("[{}] {}\n", self.code, self.message);
+        let _ = writeln!(
+            out,
+            "{gutter}--> {}:{}:{}",
+            self.path.display(),
+            self.line,
+            self.column
+        );
+        let _ = writeln!(out, "{gutter} |");
+        let _ = writeln!(out, "{number} | {}", self.source_line);
+        let _ = write!(out, "{gutter} | {indent}{}", "^".repeat(width.max(1)));
+        for note in &self.notes {
+            let _ = write!(out, "\n{gutter} = note: {note}");
+        }
+        out
+    }
+
+    /// Shows the diagnostic in the current [`MessageFormat`].
+    pub fn emit(&self) {
+        if JSON.load(Ordering::Relaxed) {
+            println!("{}", serde_json::to_string(self).unwrap());
+            return;
+        }
+        match self.severity {
+            Severity::Error => error!("{}", self.render()),
+            Severity::Warning => warn!("{}", self.render()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// The line and column of the byte offset `offset` in `source`, both
+/// starting from 1.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// The largest char boundary in `source` that is at or before `index`.
+fn floor_char_boundary(source: &str, index: usize) -> usize {
+    let mut index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_of_span() {
+        let source = "# Title\n\nSome text {{#include missing.rs}} here.\n";
+        let start = source.find("{{").unwrap();
+        let end = source.find("}}").unwrap() + 2;
+        let diag = Diagnostic::new(
+            Severity::Error,
+            "include-read",
+            "could not read `missing.rs`",
+            "src/chapter.md",
+            source,
+            start..end,
+        );
+        assert_eq!((diag.line, diag.column), (3, 11));
+        assert_eq!((diag.end_line, diag.end_column), (3, 34));
+        assert_eq!(diag.source_line, "Some text {{#include missing.rs}} here.");
+        assert_eq!(
+            diag.to_string(),
+            "src/chapter.md:3:11: could not read `missing.rs`"
+        );
+    }
+
+    #[test]
+    fn render_snippet() {
+        let source = "- [A](a.md)\n\n[B](b.md)\n- [C](c.md)\n  - [D](d.md)\n";
+        let start = source.find("- [C]").unwrap();
+        let diag = Diagnostic::new(
+            Severity::Error,
+            "summary-parse",
+            "Suffix chapters cannot be followed by a list",
+            "src/SUMMARY.md",
+            source,
+            start..source.len(),
+        )
+        .with_note("move the chapter above the suffix chapters");
+        assert_eq!(
+            diag.render(),
+            "[summary-parse] Suffix chapters cannot be followed by a list\n \
+             --> src/SUMMARY.md:4:1\n  \
+             |\n\
+             4 | - [C](c.md)\n  \
+             | ^^^^^^^^^^^\n  \
+             = note: move the chapter above the suffix chapters"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let diag = Diagnostic::new(
+            Severity::Warning,
+            "code",
+            "message",
+            "src/a.md",
+            "héllo",
+            1..3,
+        );
+        assert_eq!(
+            serde_json::to_string(&diag).unwrap(),
+            r#"{"severity":"warning","code":"code","message":"message","path":"src/a.md","line":1,"column":2,"end_line":1,"end_column":3,"source_line":"héllo","notes":[]}"#
+        );
+    }
+}
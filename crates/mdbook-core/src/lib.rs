@@ -8,6 +8,7 @@ pub const MDBOOK_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub mod book;
 pub mod config;
+pub mod diagnostic;
 pub mod utils;
 
 /// The error types used in mdbook.
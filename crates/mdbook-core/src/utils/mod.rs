@@ -1,5 +1,6 @@
 //! Various helpers and utilities.
 
+use crate::diagnostic::Diagnostic;
 use anyhow::Error;
 use std::fmt::Write;
 use tracing::error;
@@ -27,6 +28,11 @@ macro_rules! static_regex {
 
 /// Prints a "backtrace" of some `Error`.
 pub fn log_backtrace(e: &Error) {
+    if let Some(diagnostic) = e.downcast_ref::<Diagnostic>() {
+        diagnostic.emit();
+        return;
+    }
+
     let mut message = format!("{e}");
 
     for cause in e.chain().skip(1) {
@@ -35,3 +41,14 @@ pub fn log_backtrace(e: &Error) {
 
     error!("{message}");
 }
+
+/// Finds the most similar of the `candidates` to `word`, for suggesting what
+/// was meant instead of a misspelled name.
+pub fn suggest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::jaro(word, candidate), candidate))
+        .filter(|(confidence, _)| *confidence > 0.7)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
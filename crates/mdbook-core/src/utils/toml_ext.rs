@@ -8,6 +8,10 @@ pub(crate) trait TomlExt {
     fn read(&self, key: &str) -> Option<&Value>;
     /// Insert with a dotted key.
     fn insert(&mut self, key: &str, value: Value);
+    /// Recursively merge another value on top of this one.
+    ///
+    /// Tables are merged key by key, any other value is replaced.
+    fn merge(&mut self, other: Value);
 }
 
 impl TomlExt for Value {
@@ -35,6 +39,22 @@ impl TomlExt for Value {
             table.insert(key.to_string(), value);
         }
     }
+
+    fn merge(&mut self, other: Value) {
+        match (self, other) {
+            (Value::Table(table), Value::Table(other)) => {
+                for (key, value) in other {
+                    match table.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            table.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
 }
 
 fn split(key: &str) -> Option<(&str, &str)> {
@@ -91,4 +111,18 @@ mod tests {
         let inserted = value.read("first.second").unwrap();
         assert_eq!(inserted, &item);
     }
+
+    #[test]
+    fn merge_nested_tables() {
+        let mut value: Value =
+            toml::from_str("[a]\nx = 1\nlist = [1, 2]\n[a.b]\ny = 2\n[c]\nz = 3").unwrap();
+        let other: Value = toml::from_str("[a]\nlist = [3]\n[a.b]\nw = 4\n[d]\nv = 5").unwrap();
+
+        value.merge(other);
+
+        let expected: Value =
+            toml::from_str("[a]\nx = 1\nlist = [3]\n[a.b]\ny = 2\nw = 4\n[c]\nz = 3\n[d]\nv = 5")
+                .unwrap();
+        assert_eq!(value, expected);
+    }
 }
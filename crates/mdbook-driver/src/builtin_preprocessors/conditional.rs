@@ -0,0 +1,307 @@
+use super::ChapterSource;
+use anyhow::{Context, Result, bail};
+use mdbook_core::book::Book;
+use mdbook_core::static_regex;
+use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
+use std::ops::Range;
+
+const ESCAPE_CHAR: char = '\\';
+
+/// A preprocessor for including parts of a chapter only for some renderers,
+/// profiles, or flags.
+///
+/// A block starts with `{{#if condition}}`, can have an `{{#else}}`, and ends
+/// with `{{/if}}`. The condition can compare `renderer` or `profile` to a
+/// string with `==` or `!=`, such as `renderer == "html"`, or test whether a
+/// flag from the `preprocessor.conditional.flags` list is set. Conditions can
+/// be negated with `!` and combined with `&&` and `||`.
+///
+/// Unlike the other built-in preprocessors, this only runs if there is a
+/// `[preprocessor.conditional]` table in `book.toml`, because `{{#if}}` is
+/// common in examples of Handlebars templates. It runs before the other
+/// built-in preprocessors, so that files are only included and variables only
+/// replaced in the parts that are kept.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct ConditionalPreprocessor;
+
+impl ConditionalPreprocessor {
+    /// Name of this preprocessor.
+    pub const NAME: &'static str = "conditional";
+
+    /// Create a new `ConditionalPreprocessor`.
+    pub fn new() -> Self {
+        ConditionalPreprocessor
+    }
+}
+
+impl Preprocessor for ConditionalPreprocessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
+        let flags = ctx
+            .config
+            .get::<Vec<String>>("preprocessor.conditional.flags")
+            .context("failed to read `preprocessor.conditional.flags`")?
+            .unwrap_or_default();
+        let env = Env {
+            renderer: &ctx.renderer,
+            profile: ctx.config.build.profile.as_deref(),
+            flags: &flags,
+        };
+
+        let mut errors = 0;
+        book.for_each_chapter_mut(|ch| {
+            let Some(source) = ChapterSource::new(ctx, ch) else {
+                return;
+            };
+            match select(&ch.content, &env) {
+                Ok(content) => ch.content = content,
+                Err((message, span)) => {
+                    source.error("conditional", message, span).emit();
+                    errors += 1;
+                }
+            }
+        });
+
+        match errors {
+            0 => Ok(book),
+            1 => bail!("1 chapter has invalid conditional blocks"),
+            n => bail!("{n} chapters have invalid conditional blocks"),
+        }
+    }
+}
+
+/// The values that conditions are evaluated against.
+struct Env<'a> {
+    /// The name of the renderer that the book is being built for.
+    renderer: &'a str,
+    /// The name of the profile that was applied, if any.
+    profile: Option<&'a str>,
+    /// The flags that are set.
+    flags: &'a [String],
+}
+
+/// An `{{#if}}` block that has been opened but not closed yet.
+struct Block {
+    /// Whether the text in the current branch of the block is kept.
+    keep: bool,
+    /// Whether the text around the block is kept.
+    outer: bool,
+    /// Whether the `{{#else}}` has been seen.
+    in_else: bool,
+    /// The location of the `{{#if}}`.
+    span: Range<usize>,
+}
+
+/// Removes the parts of `content` whose conditions are false, or returns an
+/// error message and its location if the blocks are invalid.
+fn select(content: &str, env: &Env<'_>) -> Result<String, (String, Range<usize>)> {
+    static_regex!(
+        TAG,
+        r"(?x)                              # insignificant whitespace mode
+        \\\{\{(?:\#if\s[^}]*|\#else|/if)\}\}  # match escaped tag
+        |                                   # or
+        \{\{\s*\#if\s+([^}]+?)\s*\}\}       # opening tag with its condition
+        |                                   # or
+        \{\{\s*(\#else|/if)\s*\}\}          # else or closing tag"
+    );
+
+    let mut result = String::with_capacity(content.len());
+    let mut stack: Vec<Block> = Vec::new();
+    let mut previous_end_index = 0;
+    let keep = |stack: &[Block]| stack.last().is_none_or(|block| block.keep);
+
+    for cap in TAG.captures_iter(content) {
+        let mat = cap.get(0).unwrap();
+        let span = if mat.as_str().starts_with(ESCAPE_CHAR) {
+            mat.range()
+        } else {
+            whole_line(content, mat.range())
+        };
+        if keep(&stack) {
+            result.push_str(&content[previous_end_index..span.start]);
+        }
+        previous_end_index = span.end;
+
+        if let Some(condition) = cap.get(1) {
+            let outer = keep(&stack);
+            let keep = evaluate(condition.as_str(), env).map_err(|e| (e, mat.range()))?;
+            stack.push(Block {
+                keep: outer && keep,
+                outer,
+                in_else: false,
+                span: mat.range(),
+            });
+        } else if let Some(tag) = cap.get(2) {
+            if tag.as_str() == "#else" {
+                let Some(block) = stack.last_mut().filter(|block| !block.in_else) else {
+                    return Err((
+                        "`{{#else}}` without a matching `{{#if}}`".into(),
+                        mat.range(),
+                    ));
+                };
+                block.in_else = true;
+                block.keep = block.outer && !block.keep;
+            } else if stack.pop().is_none() {
+                return Err(("`{{/if}}` without a matching `{{#if}}`".into(), mat.range()));
+            }
+        } else if keep(&stack) {
+            result.push_str(&mat.as_str()[ESCAPE_CHAR.len_utf8()..]);
+        }
+    }
+
+    if let Some(block) = stack.pop() {
+        return Err(("`{{#if}}` is not closed with `{{/if}}`".into(), block.span));
+    }
+    result.push_str(&content[previous_end_index..]);
+    Ok(result)
+}
+
+/// Extends `span` to its whole line, including the line ending, if nothing
+/// else is on the line, so that removing a tag doesn't leave an empty line.
+fn whole_line(content: &str, span: Range<usize>) -> Range<usize> {
+    let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[span.end..]
+        .find('\n')
+        .map_or(content.len(), |i| span.end + i + 1);
+    if content[line_start..span.start].trim().is_empty()
+        && content[span.end..line_end].trim().is_empty()
+    {
+        line_start..line_end
+    } else {
+        span
+    }
+}
+
+/// Evaluates a condition, or returns an error message if it is invalid.
+fn evaluate(condition: &str, env: &Env<'_>) -> Result<bool, String> {
+    let mut any = false;
+    for alternative in condition.split("||") {
+        let mut all = true;
+        for term in alternative.split("&&") {
+            all &= evaluate_term(term.trim(), env)?;
+        }
+        any |= all;
+    }
+    Ok(any)
+}
+
+/// Evaluates a condition without `&&` or `||`.
+fn evaluate_term(term: &str, env: &Env<'_>) -> Result<bool, String> {
+    static_regex!(COMPARISON, r#"^([a-zA-Z0-9_-]+)\s*(==|!=)\s*"([^"]*)"$"#);
+    static_regex!(FLAG, r"^[a-zA-Z0-9_-]+$");
+
+    if let Some(term) = term.strip_prefix('!') {
+        return evaluate_term(term.trim_start(), env).map(|value| !value);
+    }
+    if let Some(cap) = COMPARISON.captures(term) {
+        let value = match &cap[1] {
+            "renderer" => Some(env.renderer),
+            "profile" => env.profile,
+            other => {
+                return Err(format!(
+                    "unknown name `{other}` in condition, expected `renderer` or `profile`"
+                ));
+            }
+        };
+        let equal = value == Some(&cap[3]);
+        return Ok(if &cap[2] == "==" { equal } else { !equal });
+    }
+    if FLAG.is_match(term) {
+        return Ok(env.flags.iter().any(|flag| flag == term));
+    }
+    Err(format!(
+        "invalid condition `{term}`, expected a flag or a comparison such as \
+         `renderer == \"html\"`"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENV: Env<'static> = Env {
+        renderer: "html",
+        profile: Some("public"),
+        flags: &[],
+    };
+
+    #[test]
+    fn conditions() {
+        let flags = ["beta".to_string()];
+        let env = Env {
+            flags: &flags,
+            ..ENV
+        };
+        let eval = |condition| evaluate(condition, &env);
+        assert_eq!(eval(r#"renderer == "html""#), Ok(true));
+        assert_eq!(eval(r#"renderer != "html""#), Ok(false));
+        assert_eq!(eval(r#"profile == "public""#), Ok(true));
+        assert_eq!(eval("beta"), Ok(true));
+        assert_eq!(eval("!beta"), Ok(false));
+        assert_eq!(eval("internal"), Ok(false));
+        assert_eq!(eval(r#"renderer == "epub" || beta"#), Ok(true));
+        assert_eq!(eval(r#"renderer == "html" && !beta"#), Ok(false));
+        assert_eq!(eval(r#"!internal && profile != "draft""#), Ok(true));
+        assert_eq!(
+            eval(r#"output == "html""#),
+            Err("unknown name `output` in condition, expected `renderer` or `profile`".into())
+        );
+        assert!(eval("renderer = html").is_err());
+
+        let env = Env {
+            profile: None,
+            ..ENV
+        };
+        assert_eq!(evaluate(r#"profile == "public""#, &env), Ok(false));
+        assert_eq!(evaluate(r#"profile != "public""#, &env), Ok(true));
+    }
+
+    #[test]
+    fn select_blocks() {
+        let content = "\
+Always.
+{{#if renderer == \"html\"}}
+HTML only.
+{{#else}}
+Not HTML.
+{{/if}}
+Inline {{#if renderer == \"epub\"}}epub{{#else}}other{{/if}} text.
+{{#if renderer == \"html\"}}
+{{#if beta}}
+Beta.
+{{/if}}
+{{/if}}
+Escaped \\{{#if beta}}.
+";
+        assert_eq!(
+            select(content, &ENV).unwrap(),
+            "\
+Always.
+HTML only.
+Inline other text.
+Escaped {{#if beta}}.
+"
+        );
+    }
+
+    #[test]
+    fn invalid_blocks() {
+        let error = |content| select(content, &ENV).unwrap_err();
+        assert_eq!(
+            error("a {{#if beta}} b"),
+            ("`{{#if}}` is not closed with `{{/if}}`".into(), 2..14)
+        );
+        assert_eq!(
+            error("a {{/if}} b"),
+            ("`{{/if}}` without a matching `{{#if}}`".into(), 2..9)
+        );
+        assert_eq!(
+            error("{{#if beta}}{{#else}}{{#else}}{{/if}}"),
+            ("`{{#else}}` without a matching `{{#if}}`".into(), 21..30)
+        );
+    }
+}
@@ -1,16 +1,19 @@
 use self::take_lines::{
+    dedent, dedent_rustdoc, find_item_lines, find_pattern_lines, shift_headings,
     take_anchored_lines, take_lines, take_rustdoc_include_anchored_lines,
     take_rustdoc_include_lines,
 };
-use anyhow::{Context, Result};
+use super::{ChapterSource, vars};
+use anyhow::{Context, Result, bail};
 use mdbook_core::book::{Book, BookItem};
+use mdbook_core::config::Config;
 use mdbook_core::static_regex;
 use mdbook_core::utils::fs;
 use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
 use regex::{CaptureMatches, Captures};
 use std::ops::{Bound, Range, RangeBounds, RangeFrom, RangeFull, RangeTo};
 use std::path::{Path, PathBuf};
-use tracing::{error, warn};
+use tracing::warn;
 
 mod take_lines;
 
@@ -20,7 +23,8 @@ const MAX_LINK_NESTED_DEPTH: usize = 10;
 /// A preprocessor for expanding helpers in a chapter. Supported helpers are:
 ///
 /// - `{{# include}}` - Insert an external file of any type. Include the whole file, only particular
-///   lines, or only between the specified anchors.
+///   lines, only between the specified anchors, only a Rust item with a given name, or only the
+///   lines between two regular expressions.
 /// - `{{# rustdoc_include}}` - Insert an external Rust file, showing the particular lines
 ///   specified or the lines between specified anchors, and include the rest of the file behind `#`.
 ///   This hides the lines from initial display but shows them when the reader expands the code
@@ -47,21 +51,35 @@ impl Preprocessor for LinkPreprocessor {
     }
 
     fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
-        let src_dir = ctx.root.join(&ctx.config.book.src);
-
+        let mut vars = vars::is_enabled(ctx).then(|| IncludedVars {
+            config: &ctx.config,
+            errors: 0,
+        });
         book.for_each_mut(|section: &mut BookItem| {
             if let BookItem::Chapter(ref mut ch) = *section {
-                if let Some(ref chapter_path) = ch.path {
-                    let base = chapter_path
+                // Includes are relative to the chapter's file, which is in the
+                // default language if the chapter hasn't been translated.
+                if let Some(source) = ChapterSource::new(ctx, ch) {
+                    let base = source
+                        .file
                         .parent()
-                        .map(|dir| src_dir.join(dir))
-                        .expect("All book items have a parent");
+                        .expect("All book items have a parent")
+                        .to_path_buf();
 
                     let mut chapter_title = ch.name.clone();
-                    let content =
-                        replace_all(&ch.content, base, chapter_path, 0, &mut chapter_title);
+                    let content = replace_all(
+                        &ch.content,
+                        &base,
+                        &source,
+                        0,
+                        None,
+                        &mut chapter_title,
+                        vars.as_mut(),
+                    );
                     ch.content = content;
-                    if chapter_title != ch.name {
+                    if chapter_title != ch.name
+                        && let Some(chapter_path) = &ch.path
+                    {
                         ctx.chapter_titles
                             .borrow_mut()
                             .insert(chapter_path.clone(), chapter_title);
@@ -70,31 +88,68 @@ impl Preprocessor for LinkPreprocessor {
             }
         });
 
+        if let Some(vars) = vars {
+            vars::check_errors(vars.errors)?;
+        }
         Ok(book)
     }
 }
 
-fn replace_all<P1, P2>(
+/// Replaces the variables in included files, if the `vars` preprocessor
+/// runs, since it has already run before the files are included.
+struct IncludedVars<'a> {
+    config: &'a Config,
+    /// The number of variables that could not be replaced.
+    errors: usize,
+}
+
+impl IncludedVars<'_> {
+    /// Replaces the variables in `content`, the text included from `file` by
+    /// the link at `span` in the chapter.
+    fn replace(
+        &mut self,
+        content: &str,
+        file: &Path,
+        source: &ChapterSource<'_>,
+        span: Range<usize>,
+    ) -> String {
+        vars::replace_vars(self.config, content, |message, _| {
+            source
+                .error("var-undefined", message, span.clone())
+                .with_note(format!("in the included file `{}`", file.display()))
+                .emit();
+            self.errors += 1;
+        })
+    }
+}
+
+/// Expands the links in `s`, which is in the directory `path`.
+///
+/// `span` is the location in the chapter of the link that included `s`, if
+/// any, which is where errors are reported. With `vars`, the variables in the
+/// files included by `{{#include}}` are replaced, unless the link is in code.
+fn replace_all(
     s: &str,
-    path: P1,
-    source: P2,
+    path: &Path,
+    source: &ChapterSource<'_>,
     depth: usize,
+    span: Option<Range<usize>>,
     chapter_title: &mut String,
-) -> String
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
+    mut vars: Option<&mut IncludedVars<'_>>,
+) -> String {
     // When replacing one thing in a string by something with a different length,
     // the indices after that will not correspond,
     // we therefore have to store the difference to correct this
-    let path = path.as_ref();
-    let source = source.as_ref();
     let mut previous_end_index = 0;
     let mut replaced = String::new();
+    let code = match vars {
+        Some(_) => vars::code_ranges(s),
+        None => Vec::new(),
+    };
 
     for link in find_links(s) {
         replaced.push_str(&s[previous_end_index..link.start_index]);
+        let link_span = span.clone().unwrap_or(link.start_index..link.end_index);
 
         match link.render_with_path(path, chapter_title) {
             Ok(new_content) => {
@@ -104,12 +159,38 @@ where
                     // Otherwise includes starting a new line would be prefixed
                     // by the preceding line
                     let prefix = replaced.split('\n').last().unwrap_or("");
+                    let shift = match &link.link_type {
+                        LinkType::Include(_, _, options) => options.shift,
+                        _ => 0,
+                    };
+                    let in_code = code
+                        .iter()
+                        .any(|code| code.start < link.end_index && link.start_index < code.end);
+                    let new_content = match (&mut vars, &link.link_type) {
+                        (Some(vars), LinkType::Include(file, _, _)) if !in_code => {
+                            vars.replace(&new_content, file, source, link_span.clone())
+                        }
+                        _ => new_content,
+                    };
                     let raw_new_content = if let Some(rel_path) = link.link_type.relative_path(path)
                     {
-                        replace_all(&new_content, rel_path, source, depth + 1, chapter_title)
+                        replace_all(
+                            &new_content,
+                            &rel_path,
+                            source,
+                            depth + 1,
+                            Some(link_span),
+                            chapter_title,
+                            vars.as_deref_mut(),
+                        )
                     } else {
                         new_content
                     };
+                    // Headings of nested includes are shifted too.
+                    let raw_new_content = match shift {
+                        0 => raw_new_content,
+                        shift => shift_headings(&raw_new_content, shift),
+                    };
                     // use lines instead of split('\n') because we DO
                     // want the last \n to be removed
                     // Otherwise inlined includes would fail
@@ -119,18 +200,18 @@ where
                         .join(&format!("\n{prefix}"));
                     replaced.push_str(&prefixed_new_content);
                 } else {
-                    error!(
-                        "Stack depth exceeded in {}. Check for cyclic includes",
-                        source.display()
-                    );
+                    let message = "Stack depth exceeded. Check for cyclic includes".to_string();
+                    source.error("include-depth", message, link_span).emit();
                 }
                 previous_end_index = link.end_index;
             }
             Err(e) => {
-                error!("Error updating \"{}\", {}", link.link_text, e);
-                for cause in e.chain().skip(1) {
-                    warn!("Caused By: {}", cause);
-                }
+                let message = e.to_string();
+                let diagnostic = e.chain().skip(1).fold(
+                    source.error("include-read", message, link_span),
+                    |d, cause| d.with_note(cause.to_string()),
+                );
+                diagnostic.emit();
 
                 // This should make sure we include the raw `{{# ... }}` snippet
                 // in the page content if there are any errors.
@@ -146,16 +227,54 @@ where
 #[derive(PartialEq, Debug, Clone)]
 enum LinkType<'a> {
     Escaped,
-    Include(PathBuf, RangeOrAnchor),
+    Include(PathBuf, RangeOrAnchor, IncludeOptions),
     Playground(PathBuf, Vec<&'a str>),
-    RustdocInclude(PathBuf, RangeOrAnchor),
+    RustdocInclude(PathBuf, RangeOrAnchor, IncludeOptions),
     Title(&'a str),
+    /// An include that can't be parsed, with the reason.
+    Invalid(String),
+}
+
+/// Options that change the included text, given after the path of an
+/// include, such as `{{#include file.md shift=1 dedent}}`.
+#[derive(PartialEq, Debug, Clone, Default)]
+struct IncludeOptions {
+    /// The number of levels to move markdown headings down by, or up by if
+    /// it is negative.
+    shift: i32,
+    /// Whether to remove the indentation that all lines have in common.
+    dedent: bool,
+}
+
+impl IncludeOptions {
+    /// Parses the options of the include `link_text`.
+    ///
+    /// Unknown options are ignored with a warning.
+    fn parse(props: &[&str], link_text: &str) -> IncludeOptions {
+        let mut options = IncludeOptions::default();
+        for prop in props {
+            match prop.split_once('=') {
+                None if *prop == "dedent" => options.dedent = true,
+                Some(("shift", value)) => match value.parse() {
+                    Ok(shift) => options.shift = shift,
+                    Err(_) => warn!("invalid heading shift `{value}` in {link_text}"),
+                },
+                _ => warn!("unknown option `{prop}` in {link_text}"),
+            }
+        }
+        options
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 enum RangeOrAnchor {
     Range(LineRange),
     Anchor(String),
+    /// A Rust item of some kind with a name, such as `fn=parse_config`.
+    Item(String, String),
+    /// The lines from one matching the first regex to the next one matching
+    /// the second, such as `/start/,/end/`.
+    Pattern(String, Option<String>),
 }
 
 // A range of lines specified with some include directive.
@@ -212,14 +331,38 @@ impl From<RangeFull> for LineRange {
 }
 
 impl<'a> LinkType<'a> {
+    fn invalid(e: anyhow::Error) -> LinkType<'a> {
+        LinkType::Invalid(e.to_string())
+    }
+
+    /// Sets the options of an include from the properties after its path.
+    fn with_options(self, props: &[&str], link_text: &str) -> LinkType<'a> {
+        match self {
+            LinkType::Include(path, range_or_anchor, _) => {
+                let options = IncludeOptions::parse(props, link_text);
+                LinkType::Include(path, range_or_anchor, options)
+            }
+            LinkType::RustdocInclude(path, range_or_anchor, _) => {
+                let mut options = IncludeOptions::parse(props, link_text);
+                if options.shift != 0 {
+                    warn!("headings can't be shifted in Rust code, in {link_text}");
+                    options.shift = 0;
+                }
+                LinkType::RustdocInclude(path, range_or_anchor, options)
+            }
+            other => other,
+        }
+    }
+
     fn relative_path<P: AsRef<Path>>(self, base: P) -> Option<PathBuf> {
         let base = base.as_ref();
         match self {
             LinkType::Escaped => None,
-            LinkType::Include(p, _) => Some(return_relative_path(base, &p)),
+            LinkType::Include(p, ..) => Some(return_relative_path(base, &p)),
             LinkType::Playground(p, _) => Some(return_relative_path(base, &p)),
-            LinkType::RustdocInclude(p, _) => Some(return_relative_path(base, &p)),
+            LinkType::RustdocInclude(p, ..) => Some(return_relative_path(base, &p)),
             LinkType::Title(_) => None,
+            LinkType::Invalid(_) => None,
         }
     }
 }
@@ -231,8 +374,29 @@ fn return_relative_path<P: AsRef<Path>>(base: P, relative: P) -> PathBuf {
         .to_path_buf()
 }
 
-fn parse_range_or_anchor(parts: Option<&str>) -> RangeOrAnchor {
-    let mut parts = parts.unwrap_or("").splitn(3, ':').fuse();
+fn parse_range_or_anchor(parts: Option<&str>) -> Result<RangeOrAnchor> {
+    static_regex!(ITEM, r"^(\w+)=(\S+)$");
+
+    let parts = parts.unwrap_or("");
+    if let Some(patterns) = parts.strip_prefix('/') {
+        // The directive is split on whitespace, so a regex with a space loses
+        // its end.
+        let Some(patterns) = patterns.strip_suffix('/') else {
+            bail!(
+                "the regular expression `{parts}` has no closing `/`, \
+                 use `\\s` instead of spaces in it"
+            );
+        };
+        return Ok(match patterns.split_once("/,/") {
+            Some((start, end)) => RangeOrAnchor::Pattern(start.into(), Some(end.into())),
+            None => RangeOrAnchor::Pattern(patterns.into(), None),
+        });
+    }
+    if let Some(cap) = ITEM.captures(parts) {
+        return Ok(RangeOrAnchor::Item(cap[1].into(), cap[2].into()));
+    }
+
+    let mut parts = parts.splitn(3, ':').fuse();
 
     let next_element = parts.next();
     let start = if let Some(value) = next_element.and_then(|s| s.parse::<usize>().ok()) {
@@ -241,7 +405,7 @@ fn parse_range_or_anchor(parts: Option<&str>) -> RangeOrAnchor {
     } else if let Some("") = next_element {
         None
     } else if let Some(anchor) = next_element {
-        return RangeOrAnchor::Anchor(String::from(anchor));
+        return Ok(RangeOrAnchor::Anchor(String::from(anchor)));
     } else {
         None
     };
@@ -252,31 +416,40 @@ fn parse_range_or_anchor(parts: Option<&str>) -> RangeOrAnchor {
     // the single line specified by `start`.
     let end = end.map(|s| s.parse::<usize>());
 
-    match (start, end) {
-        (Some(start), Some(Ok(end))) => RangeOrAnchor::Range(LineRange::from(start..end)),
-        (Some(start), Some(Err(_))) => RangeOrAnchor::Range(LineRange::from(start..)),
-        (Some(start), None) => RangeOrAnchor::Range(LineRange::from(start..start + 1)),
-        (None, Some(Ok(end))) => RangeOrAnchor::Range(LineRange::from(..end)),
-        (None, None) | (None, Some(Err(_))) => RangeOrAnchor::Range(LineRange::from(RangeFull)),
-    }
+    let range = match (start, end) {
+        (Some(start), Some(Ok(end))) => LineRange::from(start..end),
+        (Some(start), Some(Err(_))) => LineRange::from(start..),
+        (Some(start), None) => LineRange::from(start..start + 1),
+        (None, Some(Ok(end))) => LineRange::from(..end),
+        (None, None) | (None, Some(Err(_))) => LineRange::from(RangeFull),
+    };
+    Ok(RangeOrAnchor::Range(range))
 }
 
-fn parse_include_path(path: &str) -> LinkType<'static> {
+fn parse_include_path(path: &str) -> Result<LinkType<'static>> {
     let mut parts = path.splitn(2, ':');
 
     let path = parts.next().unwrap().into();
-    let range_or_anchor = parse_range_or_anchor(parts.next());
+    let range_or_anchor = parse_range_or_anchor(parts.next())?;
 
-    LinkType::Include(path, range_or_anchor)
+    Ok(LinkType::Include(
+        path,
+        range_or_anchor,
+        IncludeOptions::default(),
+    ))
 }
 
-fn parse_rustdoc_include_path(path: &str) -> LinkType<'static> {
+fn parse_rustdoc_include_path(path: &str) -> Result<LinkType<'static>> {
     let mut parts = path.splitn(2, ':');
 
     let path = parts.next().unwrap().into();
-    let range_or_anchor = parse_range_or_anchor(parts.next());
+    let range_or_anchor = parse_range_or_anchor(parts.next())?;
 
-    LinkType::RustdocInclude(path, range_or_anchor)
+    Ok(LinkType::RustdocInclude(
+        path,
+        range_or_anchor,
+        IncludeOptions::default(),
+    ))
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -299,7 +472,10 @@ impl<'a> Link<'a> {
                 let props: Vec<&str> = path_props.collect();
 
                 match (typ.as_str(), file_arg) {
-                    ("include", Some(pth)) => Some(parse_include_path(pth)),
+                    ("include", Some(pth)) => Some(
+                        parse_include_path(pth)
+                            .map_or_else(LinkType::invalid, |l| l.with_options(&props, &cap[0])),
+                    ),
                     ("playground", Some(pth)) => Some(LinkType::Playground(pth.into(), props)),
                     ("playpen", Some(pth)) => {
                         warn!(
@@ -309,7 +485,10 @@ impl<'a> Link<'a> {
                         );
                         Some(LinkType::Playground(pth.into(), props))
                     }
-                    ("rustdoc_include", Some(pth)) => Some(parse_rustdoc_include_path(pth)),
+                    ("rustdoc_include", Some(pth)) => Some(
+                        parse_rustdoc_include_path(pth)
+                            .map_or_else(LinkType::invalid, |l| l.with_options(&props, &cap[0])),
+                    ),
                     _ => None,
                 }
             }
@@ -329,6 +508,30 @@ impl<'a> Link<'a> {
         })
     }
 
+    /// Reads the file at `pat` relative to `base`, returning its path and
+    /// contents.
+    fn read_target(&self, base: &Path, pat: &Path) -> Result<(PathBuf, String)> {
+        let target = base.join(pat);
+        let contents = fs::read_to_string(&target).with_context(|| {
+            format!(
+                "Could not read file for link {} ({})",
+                self.link_text,
+                target.display(),
+            )
+        })?;
+        Ok((target, contents))
+    }
+
+    /// The context of an error finding the lines of an item or a pattern in
+    /// the file `target`.
+    fn lines_context(&self, target: &Path) -> String {
+        format!(
+            "Could not find the lines for link {} ({})",
+            self.link_text,
+            target.display(),
+        )
+    }
+
     fn render_with_path<P: AsRef<Path>>(
         &self,
         base: P,
@@ -338,41 +541,47 @@ impl<'a> Link<'a> {
         match self.link_type {
             // omit the escape char
             LinkType::Escaped => Ok(self.link_text[1..].to_owned()),
-            LinkType::Include(ref pat, ref range_or_anchor) => {
-                let target = base.join(pat);
-
-                fs::read_to_string(&target)
-                    .map(|s| match range_or_anchor {
-                        RangeOrAnchor::Range(range) => take_lines(&s, range.clone()),
-                        RangeOrAnchor::Anchor(anchor) => take_anchored_lines(&s, anchor),
-                    })
-                    .with_context(|| {
-                        format!(
-                            "Could not read file for link {} ({})",
-                            self.link_text,
-                            target.display(),
-                        )
-                    })
+            LinkType::Include(ref pat, ref range_or_anchor, ref options) => {
+                let (target, s) = self.read_target(base, pat)?;
+                let s = match range_or_anchor {
+                    RangeOrAnchor::Range(range) => take_lines(&s, range.clone()),
+                    RangeOrAnchor::Anchor(anchor) => take_anchored_lines(&s, anchor),
+                    RangeOrAnchor::Item(kind, name) => {
+                        let lines = find_item_lines(&s, kind, name)
+                            .with_context(|| self.lines_context(&target))?;
+                        take_lines(&s, lines)
+                    }
+                    RangeOrAnchor::Pattern(start, end) => {
+                        let lines = find_pattern_lines(&s, start, end.as_deref())
+                            .with_context(|| self.lines_context(&target))?;
+                        take_lines(&s, lines)
+                    }
+                };
+                Ok(if options.dedent { dedent(&s) } else { s })
             }
-            LinkType::RustdocInclude(ref pat, ref range_or_anchor) => {
-                let target = base.join(pat);
-
-                fs::read_to_string(&target)
-                    .map(|s| match range_or_anchor {
-                        RangeOrAnchor::Range(range) => {
-                            take_rustdoc_include_lines(&s, range.clone())
-                        }
-                        RangeOrAnchor::Anchor(anchor) => {
-                            take_rustdoc_include_anchored_lines(&s, anchor)
-                        }
-                    })
-                    .with_context(|| {
-                        format!(
-                            "Could not read file for link {} ({})",
-                            self.link_text,
-                            target.display(),
-                        )
-                    })
+            LinkType::RustdocInclude(ref pat, ref range_or_anchor, ref options) => {
+                let (target, s) = self.read_target(base, pat)?;
+                let s = match range_or_anchor {
+                    RangeOrAnchor::Range(range) => take_rustdoc_include_lines(&s, range.clone()),
+                    RangeOrAnchor::Anchor(anchor) => {
+                        take_rustdoc_include_anchored_lines(&s, anchor)
+                    }
+                    RangeOrAnchor::Item(kind, name) => {
+                        let lines = find_item_lines(&s, kind, name)
+                            .with_context(|| self.lines_context(&target))?;
+                        take_rustdoc_include_lines(&s, lines)
+                    }
+                    RangeOrAnchor::Pattern(start, end) => {
+                        let lines = find_pattern_lines(&s, start, end.as_deref())
+                            .with_context(|| self.lines_context(&target))?;
+                        take_rustdoc_include_lines(&s, lines)
+                    }
+                };
+                Ok(if options.dedent {
+                    dedent_rustdoc(&s)
+                } else {
+                    s
+                })
             }
             LinkType::Playground(ref pat, ref attrs) => {
                 let target = base.join(pat);
@@ -399,6 +608,9 @@ impl<'a> Link<'a> {
                 *chapter_title = title.to_owned();
                 Ok(String::new())
             }
+            LinkType::Invalid(ref reason) => {
+                bail!("Could not parse link {}: {reason}", self.link_text)
+            }
         }
     }
 }
@@ -450,7 +662,23 @@ mod tests {
         {{#include file.rs}} << an escaped link!
         ```";
         let mut chapter_title = "test_replace_all_escaped".to_owned();
-        assert_eq!(replace_all(start, "", "", 0, &mut chapter_title), end);
+        let source = ChapterSource {
+            path: PathBuf::new(),
+            file: PathBuf::new(),
+            content: start,
+        };
+        assert_eq!(
+            replace_all(
+                start,
+                Path::new(""),
+                &source,
+                0,
+                None,
+                &mut chapter_title,
+                None
+            ),
+            end
+        );
     }
 
     #[test]
@@ -462,7 +690,23 @@ mod tests {
         # My Chapter
         ";
         let mut chapter_title = "test_set_chapter_title".to_owned();
-        assert_eq!(replace_all(start, "", "", 0, &mut chapter_title), end);
+        let source = ChapterSource {
+            path: PathBuf::new(),
+            file: PathBuf::new(),
+            content: start,
+        };
+        assert_eq!(
+            replace_all(
+                start,
+                Path::new(""),
+                &source,
+                0,
+                None,
+                &mut chapter_title,
+                None
+            ),
+            end
+        );
         assert_eq!(chapter_title, "My Title");
     }
 
@@ -550,7 +794,8 @@ mod tests {
                 end_index: 48,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(9..20))
+                    RangeOrAnchor::Range(LineRange::from(9..20)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs:10:20}}",
             }]
@@ -569,7 +814,8 @@ mod tests {
                 end_index: 45,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(9..10))
+                    RangeOrAnchor::Range(LineRange::from(9..10)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs:10}}",
             }]
@@ -588,7 +834,8 @@ mod tests {
                 end_index: 46,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(9..))
+                    RangeOrAnchor::Range(LineRange::from(9..)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs:10:}}",
             }]
@@ -607,7 +854,8 @@ mod tests {
                 end_index: 46,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(..20))
+                    RangeOrAnchor::Range(LineRange::from(..20)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs::20}}",
             }]
@@ -626,7 +874,8 @@ mod tests {
                 end_index: 44,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(..))
+                    RangeOrAnchor::Range(LineRange::from(..)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs::}}",
             }]
@@ -645,7 +894,8 @@ mod tests {
                 end_index: 42,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(..))
+                    RangeOrAnchor::Range(LineRange::from(..)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs}}",
             }]
@@ -664,7 +914,8 @@ mod tests {
                 end_index: 49,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Anchor(String::from("anchor"))
+                    RangeOrAnchor::Anchor(String::from("anchor")),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs:anchor}}",
             }]
@@ -718,6 +969,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_includes_with_options() {
+        let s = "{{#include intro.md shift=2 dedent}} {{#rustdoc_include main.rs:body dedent}}";
+
+        let res = find_links(s).collect::<Vec<_>>();
+        assert_eq!(
+            res.iter().map(|link| &link.link_type).collect::<Vec<_>>(),
+            [
+                &LinkType::Include(
+                    PathBuf::from("intro.md"),
+                    RangeOrAnchor::Range(LineRange::from(RangeFull)),
+                    IncludeOptions {
+                        shift: 2,
+                        dedent: true
+                    }
+                ),
+                &LinkType::RustdocInclude(
+                    PathBuf::from("main.rs"),
+                    RangeOrAnchor::Anchor(String::from("body")),
+                    IncludeOptions {
+                        shift: 0,
+                        dedent: true
+                    }
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_find_all_link_types() {
         let s = "Some random text with escaped playground {{#include file.rs}} and \\{{#contents are \
@@ -734,7 +1013,8 @@ mod tests {
                 end_index: 61,
                 link_type: LinkType::Include(
                     PathBuf::from("file.rs"),
-                    RangeOrAnchor::Range(LineRange::from(..))
+                    RangeOrAnchor::Range(LineRange::from(..)),
+                    IncludeOptions::default()
                 ),
                 link_text: "{{#include file.rs}}",
             }
@@ -764,180 +1044,253 @@ mod tests {
 
     #[test]
     fn parse_without_colon_includes_all() {
-        let link_type = parse_include_path("arbitrary");
+        let link_type = parse_include_path("arbitrary").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(RangeFull))
+                RangeOrAnchor::Range(LineRange::from(RangeFull)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_nothing_after_colon_includes_all() {
-        let link_type = parse_include_path("arbitrary:");
+        let link_type = parse_include_path("arbitrary:").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(RangeFull))
+                RangeOrAnchor::Range(LineRange::from(RangeFull)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_two_colons_includes_all() {
-        let link_type = parse_include_path("arbitrary::");
+        let link_type = parse_include_path("arbitrary::").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(RangeFull))
+                RangeOrAnchor::Range(LineRange::from(RangeFull)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_garbage_after_two_colons_includes_all() {
-        let link_type = parse_include_path("arbitrary::NaN");
+        let link_type = parse_include_path("arbitrary::NaN").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(RangeFull))
+                RangeOrAnchor::Range(LineRange::from(RangeFull)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_one_number_after_colon_only_that_line() {
-        let link_type = parse_include_path("arbitrary:5");
+        let link_type = parse_include_path("arbitrary:5").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(4..5))
+                RangeOrAnchor::Range(LineRange::from(4..5)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_one_based_start_becomes_zero_based() {
-        let link_type = parse_include_path("arbitrary:1");
+        let link_type = parse_include_path("arbitrary:1").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(0..1))
+                RangeOrAnchor::Range(LineRange::from(0..1)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_zero_based_start_stays_zero_based_but_is_probably_an_error() {
-        let link_type = parse_include_path("arbitrary:0");
+        let link_type = parse_include_path("arbitrary:0").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(0..1))
+                RangeOrAnchor::Range(LineRange::from(0..1)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_start_only_range() {
-        let link_type = parse_include_path("arbitrary:5:");
+        let link_type = parse_include_path("arbitrary:5:").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(4..))
+                RangeOrAnchor::Range(LineRange::from(4..)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_start_with_garbage_interpreted_as_start_only_range() {
-        let link_type = parse_include_path("arbitrary:5:NaN");
+        let link_type = parse_include_path("arbitrary:5:NaN").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(4..))
+                RangeOrAnchor::Range(LineRange::from(4..)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_end_only_range() {
-        let link_type = parse_include_path("arbitrary::5");
+        let link_type = parse_include_path("arbitrary::5").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(..5))
+                RangeOrAnchor::Range(LineRange::from(..5)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_start_and_end_range() {
-        let link_type = parse_include_path("arbitrary:5:10");
+        let link_type = parse_include_path("arbitrary:5:10").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(4..10))
+                RangeOrAnchor::Range(LineRange::from(4..10)),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_negative_interpreted_as_anchor() {
-        let link_type = parse_include_path("arbitrary:-5");
+        let link_type = parse_include_path("arbitrary:-5").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Anchor("-5".to_string())
+                RangeOrAnchor::Anchor("-5".to_string()),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_floating_point_interpreted_as_anchor() {
-        let link_type = parse_include_path("arbitrary:-5.7");
+        let link_type = parse_include_path("arbitrary:-5.7").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Anchor("-5.7".to_string())
+                RangeOrAnchor::Anchor("-5.7".to_string()),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_anchor_followed_by_colon() {
-        let link_type = parse_include_path("arbitrary:some-anchor:this-gets-ignored");
+        let link_type = parse_include_path("arbitrary:some-anchor:this-gets-ignored").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Anchor("some-anchor".to_string())
+                RangeOrAnchor::Anchor("some-anchor".to_string()),
+                IncludeOptions::default()
             )
         );
     }
 
     #[test]
     fn parse_with_more_than_three_colons_ignores_everything_after_third_colon() {
-        let link_type = parse_include_path("arbitrary:5:10:17:anything:");
+        let link_type = parse_include_path("arbitrary:5:10:17:anything:").unwrap();
         assert_eq!(
             link_type,
             LinkType::Include(
                 PathBuf::from("arbitrary"),
-                RangeOrAnchor::Range(LineRange::from(4..10))
+                RangeOrAnchor::Range(LineRange::from(4..10)),
+                IncludeOptions::default()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_with_item_selector() {
+        let link_type = parse_include_path("src/config.rs:fn=parse_config").unwrap();
+        assert_eq!(
+            link_type,
+            LinkType::Include(
+                PathBuf::from("src/config.rs"),
+                RangeOrAnchor::Item("fn".into(), "parse_config".into()),
+                IncludeOptions::default()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_with_pattern_selector() {
+        let link_type = parse_include_path(r"notes.txt:/^##\sStart/,/^##\sEnd:/").unwrap();
+        assert_eq!(
+            link_type,
+            LinkType::Include(
+                PathBuf::from("notes.txt"),
+                RangeOrAnchor::Pattern(r"^##\sStart".into(), Some(r"^##\sEnd:".into())),
+                IncludeOptions::default()
+            )
+        );
+
+        let link_type = parse_rustdoc_include_path(r"main.rs:/fn\smain/").unwrap();
+        assert_eq!(
+            link_type,
+            LinkType::RustdocInclude(
+                PathBuf::from("main.rs"),
+                RangeOrAnchor::Pattern(r"fn\smain".into(), None),
+                IncludeOptions::default()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_with_unterminated_pattern_selector() {
+        let err = parse_include_path("notes.txt:/^##").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r"the regular expression `/^##` has no closing `/`, use `\s` instead of spaces in it"
+        );
+        assert!(parse_include_path("notes.txt:/^Start/,/^End").is_err());
+        assert!(parse_rustdoc_include_path("main.rs:/").is_err());
+
+        let s = "Some {{#include notes.txt:/^## Usage/}} text";
+        let links: Vec<_> = find_links(s).collect();
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link_type,
+            LinkType::Invalid(
+                r"the regular expression `/^##` has no closing `/`, use `\s` instead of spaces in it"
+                    .into()
             )
         );
     }
@@ -1,6 +1,9 @@
+use anyhow::{Context, Result, bail};
 use mdbook_core::static_regex;
+use mdbook_markdown::pulldown_cmark::{Event, Tag};
+use mdbook_markdown::{MarkdownOptions, new_cmark_parser};
 use std::ops::Bound::{Excluded, Included, Unbounded};
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
 /// Take a range of lines from a string.
 pub(super) fn take_lines<R: RangeBounds<usize>>(s: &str, range: R) -> String {
@@ -56,6 +59,209 @@ pub(super) fn take_anchored_lines(s: &str, anchor: &str) -> String {
     retained.join("\n")
 }
 
+/// The kinds of Rust items that can be included by name.
+const ITEM_KINDS: &[&str] = &[
+    "fn", "struct", "enum", "union", "trait", "impl", "mod", "type", "const", "static", "macro",
+];
+
+/// Find the lines of the Rust item of the given kind and name, such as the
+/// function `parse_config` for `fn` and `parse_config`, including its doc
+/// comments and attributes.
+///
+/// The end of the item is found by matching braces, so this also works for
+/// other languages with a similar syntax as long as the item starts like a
+/// Rust item. An `impl` matches the first impl block of the type `name`,
+/// whether or not it implements a trait.
+pub(super) fn find_item_lines(s: &str, kind: &str, name: &str) -> Result<Range<usize>> {
+    let escaped = regex::escape(name);
+    let vis = r"^[ \t]*(?:pub(?:\([^)]*\))?\s+)?";
+    let pattern = match kind {
+        "fn" => format!(
+            r#"{vis}(?:(?:const|async|unsafe|default|extern(?:\s+"[^"]*")?)\s+)*fn\s+{escaped}\b"#
+        ),
+        "static" => format!(r"{vis}static\s+(?:mut\s+)?{escaped}\b"),
+        "macro" => format!(r"^[ \t]*macro_rules!\s*{escaped}\b"),
+        "impl" => format!(
+            r"^[ \t]*(?:unsafe\s+)?impl\b[^{{;]*?(?:\bfor\s+|[\s>])(?:[\w:]*::)?{escaped}\b[^{{;]*\{{"
+        ),
+        "struct" | "enum" | "union" | "trait" | "mod" | "type" | "const" => {
+            format!(r"{vis}(?:(?:unsafe|auto)\s+)?{kind}\s+{escaped}\b")
+        }
+        _ => bail!(
+            "unknown item kind `{kind}`, expected one of {}",
+            ITEM_KINDS.join(", ")
+        ),
+    };
+    let re = regex::RegexBuilder::new(&pattern)
+        .multi_line(true)
+        .build()
+        .expect("item pattern should be valid");
+    let Some(mat) = re.find_iter(s).find(|mat| {
+        // An impl block of `Foo<Bar>` isn't an impl block of `Bar`.
+        kind != "impl" || !impl_of_type_argument(mat.as_str(), name)
+    }) else {
+        bail!("no `{kind} {name}` item found");
+    };
+
+    let line_of = |offset: usize| s[..offset].matches('\n').count();
+    let lines: Vec<_> = s.lines().collect();
+    let mut start = line_of(mat.start());
+    while start > 0 {
+        let previous = lines[start - 1].trim_start();
+        if !(previous.starts_with("///") || previous.starts_with("#[")) {
+            break;
+        }
+        start -= 1;
+    }
+    let braces_only = !matches!(kind, "type" | "const" | "static");
+    let end = find_item_end(s, mat.start(), braces_only)
+        .with_context(|| format!("the end of `{kind} {name}` could not be found"))?;
+    Ok(start..line_of(end) + 1)
+}
+
+/// Whether the header of the impl block `header` only mentions `name` in the
+/// generic arguments of another type.
+fn impl_of_type_argument(header: &str, name: &str) -> bool {
+    let header = header.trim_end_matches('{');
+    let self_type = match header.rsplit_once(" for ") {
+        Some((_, self_type)) => self_type,
+        None => {
+            // Skip the generic parameters of the impl itself.
+            let rest = header
+                .trim_start()
+                .trim_start_matches("unsafe")
+                .trim_start();
+            let rest = rest.trim_start_matches("impl");
+            match rest.strip_prefix('<') {
+                Some(rest) => skip_angle_brackets(rest),
+                None => rest,
+            }
+        }
+    };
+    let self_type = self_type.split(" where ").next().unwrap_or_default();
+    let path = self_type.split('<').next().unwrap_or_default();
+    let base = path.rsplit("::").next().unwrap_or_default();
+    base.split_whitespace()
+        .last()
+        .is_none_or(|base| base.trim_start_matches('&') != name)
+}
+
+/// Returns the text after the `>` that closes an already opened `<`.
+fn skip_angle_brackets(s: &str) -> &str {
+    let mut depth = 1;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &s[i + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+/// Find the offset of the last character of the item starting at `start`,
+/// which is the `;` that ends it or, if `braces` is true, the `}` matching
+/// the first `{`. Comments, strings, and character literals are skipped.
+fn find_item_end(s: &str, start: usize, braces: bool) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i += s[i..].find('\n')?;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += s[i + 2..].find("*/")? + 3;
+            }
+            b'"' => i = skip_string(s, i + 1, "\"")?,
+            b'r' if matches!(bytes.get(i + 1), Some(b'"' | b'#'))
+                && !bytes[..i]
+                    .last()
+                    .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_') =>
+            {
+                let hashes = s[i + 1..].len() - s[i + 1..].trim_start_matches('#').len();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    let end = format!("\"{}", "#".repeat(hashes));
+                    let body = i + 2 + hashes;
+                    i = body + s[body..].find(&end)? + end.len() - 1;
+                }
+            }
+            b'\'' => {
+                // A character literal, or else a lifetime which is skipped
+                // like any other identifier.
+                if bytes.get(i + 1) == Some(&b'\\') {
+                    i = skip_string(s, i + 1, "'")?;
+                } else if let Some(c) = s[i + 1..].chars().next()
+                    && s[i + 1 + c.len_utf8()..].starts_with('\'')
+                {
+                    i += c.len_utf8() + 1;
+                }
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                if braces && depth == 0 {
+                    return Some(i);
+                }
+            }
+            b';' if depth == 0 => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the offset of the `end` that closes a string or character literal
+/// whose contents start at `start`, skipping escaped characters.
+fn skip_string(s: &str, start: usize, end: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s[start..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if s[start + i..].starts_with(end) {
+            return Some(start + i);
+        }
+    }
+    None
+}
+
+/// Find the lines from the first one matching the regex `start` to the next
+/// one matching the regex `end`, inclusive. Without an `end`, the lines go to
+/// the end of the file.
+pub(super) fn find_pattern_lines(s: &str, start: &str, end: Option<&str>) -> Result<Range<usize>> {
+    let start_re =
+        regex::Regex::new(start).with_context(|| format!("invalid regex `/{start}/`"))?;
+    let end_re = end
+        .map(|end| regex::Regex::new(end).with_context(|| format!("invalid regex `/{end}/`")))
+        .transpose()?;
+
+    let lines: Vec<_> = s.lines().collect();
+    let Some(first) = lines.iter().position(|l| start_re.is_match(l)) else {
+        bail!("no line matches `/{start}/`");
+    };
+    let Some(end_re) = end_re else {
+        return Ok(first..lines.len());
+    };
+    match lines[first + 1..].iter().position(|l| end_re.is_match(l)) {
+        Some(last) => Ok(first..first + 1 + last + 1),
+        None => bail!(
+            "no line after line {} matches `/{}/`",
+            first + 1,
+            end.unwrap()
+        ),
+    }
+}
+
 /// Keep lines contained within the range specified as-is.
 /// For any lines not in the range, include them but use `#` at the beginning. This will hide the
 /// lines from initial display but include them when expanding the code snippet or testing with
@@ -112,9 +318,87 @@ pub(super) fn take_rustdoc_include_anchored_lines(s: &str, anchor: &str) -> Stri
     output
 }
 
+/// Remove the leading whitespace that all of the non-blank lines have in common.
+pub(super) fn dedent(s: &str) -> String {
+    dedent_lines(s, |_| false)
+}
+
+/// Like [`dedent`], but for the output of [`take_rustdoc_include_lines`] and
+/// [`take_rustdoc_include_anchored_lines`]. The hidden lines starting with `#`
+/// are left as they are, so only the lines that are shown are dedented.
+pub(super) fn dedent_rustdoc(s: &str) -> String {
+    dedent_lines(s, |line| line == "#" || line.starts_with("# "))
+}
+
+fn dedent_lines(s: &str, is_hidden: impl Fn(&str) -> bool) -> String {
+    let indent = s
+        .lines()
+        .filter(|l| !is_hidden(l) && !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .reduce(|common, indent| {
+            let len = common
+                .chars()
+                .zip(indent.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(c, _)| c.len_utf8())
+                .sum();
+            &common[..len]
+        })
+        .unwrap_or("");
+
+    s.lines()
+        .map(|l| {
+            if is_hidden(l) {
+                l
+            } else {
+                l.strip_prefix(indent).unwrap_or_else(|| l.trim_start())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Move the markdown headings down by `shift` levels, or up if it is negative.
+/// Levels are kept between 1 and 6. Setext headings (underlined with `=` or
+/// `-`) are changed to ATX headings (starting with `#`).
+pub(super) fn shift_headings(s: &str, shift: i32) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut previous_end_index = 0;
+
+    for (event, range) in new_cmark_parser(s, &MarkdownOptions::default()).into_offset_iter() {
+        let Event::Start(Tag::Heading { level, .. }) = event else {
+            continue;
+        };
+        let hashes = "#".repeat((level as i32 + shift).clamp(1, 6) as usize);
+        let heading = &s[range.clone()];
+        let body = heading.trim_end();
+        let line_end = &heading[body.len()..];
+
+        output.push_str(&s[previous_end_index..range.start]);
+        if let Some(text) = body.strip_prefix('#') {
+            output.push_str(&hashes);
+            output.push_str(text.trim_start_matches('#'));
+        } else {
+            let text = body
+                .rsplit_once('\n')
+                .map_or(body, |(text, _underline)| text);
+            let text: Vec<_> = text.lines().map(str::trim).collect();
+            output.push_str(&hashes);
+            output.push(' ');
+            output.push_str(&text.join(" "));
+        }
+        output.push_str(line_end);
+        previous_end_index = range.end;
+    }
+
+    output.push_str(&s[previous_end_index..]);
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
+        dedent, dedent_rustdoc, find_item_lines, find_pattern_lines, shift_headings,
         take_anchored_lines, take_lines, take_rustdoc_include_anchored_lines,
         take_rustdoc_include_lines,
     };
@@ -250,4 +534,118 @@ mod tests {
             "# Lorem\nipsum\n# dolor\nsit\n# amet"
         );
     }
+
+    #[test]
+    fn dedent_common_indentation() {
+        let s = "    fn main() {\n        println!();\n\n    }";
+        assert_eq!(dedent(s), "fn main() {\n    println!();\n\n}");
+
+        let s = "\tone\n\t\ttwo\n  \n\tthree";
+        assert_eq!(dedent(s), "one\n\ttwo\n\nthree");
+
+        let s = "one\n    two";
+        assert_eq!(dedent(s), s);
+    }
+
+    #[test]
+    fn dedent_rustdoc_shown_lines() {
+        let s = "# fn main() {\n    let x = 1;\n        x + 1;\n# }";
+        assert_eq!(
+            dedent_rustdoc(s),
+            "# fn main() {\nlet x = 1;\n    x + 1;\n# }"
+        );
+    }
+
+    #[test]
+    fn shift_atx_and_setext_headings() {
+        let s = "# Title {#title}\n\nText with # sign.\n\n## Section ##\n\n```\n# not a heading\n```\n\nSetext\n======\n";
+        assert_eq!(
+            shift_headings(s, 1),
+            "## Title {#title}\n\nText with # sign.\n\n### Section ##\n\n```\n# not a heading\n```\n\n## Setext\n"
+        );
+        assert_eq!(
+            shift_headings("# One\n\n###### Six\n", 2),
+            "### One\n\n###### Six\n"
+        );
+        assert_eq!(
+            shift_headings("## Two\n\nMulti\nline\n---\n", -1),
+            "# Two\n\n# Multi line\n"
+        );
+    }
+
+    const SOURCE: &str = r##"use std::fmt;
+
+/// A configuration.
+#[derive(Debug)]
+pub struct Config {
+    name: String,
+}
+
+pub struct Unit;
+
+impl<T> From<T> for Wrapper<Config> {}
+
+impl Config {
+    /// Parses a configuration.
+    pub(crate) fn parse_config(s: &str) -> Config {
+        let braces = "}}}";
+        let raw = r#"{"#;
+        let c = '}';
+        // }
+        /* } */
+        let _: &'static str = s;
+        Config { name: s.into() }
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+const LIMIT: Limit = Limit { max: 3 };
+
+macro_rules! config {
+    () => {};
+}
+"##;
+
+    #[test]
+    fn find_rust_items() {
+        let lines = |kind, name| find_item_lines(SOURCE, kind, name).unwrap();
+        assert_eq!(lines("struct", "Config"), 2..7);
+        assert_eq!(lines("struct", "Unit"), 8..9);
+        assert_eq!(lines("fn", "parse_config"), 13..23);
+        assert_eq!(lines("impl", "Config"), 12..24);
+        assert_eq!(lines("fn", "fmt"), 26..29);
+        assert_eq!(lines("const", "LIMIT"), 31..32);
+        assert_eq!(lines("macro", "config"), 33..36);
+        assert_eq!(
+            find_item_lines(SOURCE, "fn", "missing")
+                .unwrap_err()
+                .to_string(),
+            "no `fn missing` item found"
+        );
+        assert!(find_item_lines(SOURCE, "class", "Config").is_err());
+    }
+
+    #[test]
+    fn find_lines_between_patterns() {
+        let s = "one\nstart here\ntwo\nend here\nthree\nend again";
+        assert_eq!(find_pattern_lines(s, "^start", Some("^end")).unwrap(), 1..4);
+        assert_eq!(find_pattern_lines(s, "two", None).unwrap(), 2..6);
+        assert_eq!(find_pattern_lines(s, "end", Some("end")).unwrap(), 3..6);
+        assert_eq!(
+            find_pattern_lines(s, "four", None).unwrap_err().to_string(),
+            "no line matches `/four/`"
+        );
+        assert_eq!(
+            find_pattern_lines(s, "three", Some("one"))
+                .unwrap_err()
+                .to_string(),
+            "no line after line 5 matches `/one/`"
+        );
+        assert!(find_pattern_lines(s, "(", None).is_err());
+    }
 }
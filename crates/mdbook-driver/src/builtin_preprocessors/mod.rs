@@ -1,9 +1,72 @@
 //! Built-in preprocessors.
 
+use mdbook_core::book::Chapter;
+use mdbook_core::diagnostic::{Diagnostic, Severity};
+use mdbook_preprocessor::PreprocessorContext;
+use std::ops::Range;
+use std::path::PathBuf;
+
 pub use self::cmd::CmdPreprocessor;
+pub use self::conditional::ConditionalPreprocessor;
 pub use self::index::IndexPreprocessor;
 pub use self::links::LinkPreprocessor;
+pub use self::vars::VarsPreprocessor;
+pub use self::xref::XrefPreprocessor;
 
 mod cmd;
+mod conditional;
 mod index;
 mod links;
+mod vars;
+mod xref;
+
+/// The chapter that a preprocessor is working on, used for reporting errors.
+pub(crate) struct ChapterSource<'a> {
+    /// The path of the chapter's file, relative to the root of the book.
+    pub(crate) path: PathBuf,
+    /// The path of the chapter's file on disk.
+    pub(crate) file: PathBuf,
+    /// The content of the chapter, as the preprocessor received it.
+    pub(crate) content: &'a str,
+}
+
+impl<'a> ChapterSource<'a> {
+    /// The source of `ch`, or `None` for a draft chapter.
+    ///
+    /// This uses the chapter's `source_path`, which isn't renamed by the
+    /// `index` preprocessor. The file of a chapter that hasn't been
+    /// translated is in the directory of the default language.
+    pub(crate) fn new(ctx: &PreprocessorContext, ch: &'a Chapter) -> Option<ChapterSource<'a>> {
+        let path = ch.source_path.as_ref().or(ch.path.as_ref())?;
+        let path = ctx.config.chapter_file(&ctx.root, path);
+        Some(ChapterSource {
+            file: ctx.root.join(&path),
+            path,
+            content: &ch.content,
+        })
+    }
+
+    /// Reports an error about the text at `span` of the chapter's content.
+    pub(crate) fn error(&self, code: &str, message: String, span: Range<usize>) -> Diagnostic {
+        // The content doesn't start at the beginning of the file if there is
+        // front matter. Reading the file gives the right line numbers, unless
+        // the content was changed by an earlier preprocessor.
+        let file = std::fs::read_to_string(&self.file).ok();
+        let file = file
+            .as_deref()
+            .map(|text| text.strip_prefix('\u{feff}').unwrap_or(text))
+            .filter(|text| text.ends_with(self.content));
+        let (text, offset) = match file {
+            Some(text) => (text, text.len() - self.content.len()),
+            None => (self.content, 0),
+        };
+        Diagnostic::new(
+            Severity::Error,
+            code,
+            message,
+            &self.path,
+            text,
+            span.start + offset..span.end + offset,
+        )
+    }
+}
@@ -0,0 +1,267 @@
+use super::ChapterSource;
+use anyhow::{Result, bail};
+use mdbook_core::book::Book;
+use mdbook_core::config::Config;
+use mdbook_core::static_regex;
+use mdbook_markdown::pulldown_cmark::{Event, Tag};
+use mdbook_markdown::{MarkdownOptions, new_cmark_parser};
+use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
+use regex::Captures;
+use std::ops::Range;
+use toml::Value;
+
+const ESCAPE_CHAR: char = '\\';
+
+/// A preprocessor for replacing `{{#var name}}` with the value of a variable.
+///
+/// Variables are defined in the `[preprocessor.vars]` table of `book.toml`.
+/// Names starting with `book.` refer to the `[book]` table instead, such as
+/// `{{#var book.title}}`, and names starting with `env.` refer to environment
+/// variables. Using a variable that isn't defined is an error. Variables in
+/// code spans and code blocks are left as they are, so that they can be shown
+/// in examples.
+///
+/// This only runs if there is a `[preprocessor.vars]` table in `book.toml`.
+/// It runs before the [`LinkPreprocessor`](super::LinkPreprocessor), so
+/// variables can be used in the paths of `{{#include}}` links. Variables in
+/// the included files are replaced by the `LinkPreprocessor` as they are
+/// included.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct VarsPreprocessor;
+
+impl VarsPreprocessor {
+    /// Name of this preprocessor.
+    pub const NAME: &'static str = "vars";
+
+    /// Create a new `VarsPreprocessor`.
+    pub fn new() -> Self {
+        VarsPreprocessor
+    }
+}
+
+impl Preprocessor for VarsPreprocessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
+        let mut errors = 0;
+        book.for_each_chapter_mut(|ch| {
+            let Some(source) = ChapterSource::new(ctx, ch) else {
+                return;
+            };
+            let content = replace_vars(&ctx.config, &ch.content, |message, span| {
+                source.error("var-undefined", message, span).emit();
+                errors += 1;
+            });
+            ch.content = content;
+        });
+
+        check_errors(errors)?;
+        Ok(book)
+    }
+}
+
+/// Returns whether the `vars` preprocessor runs for the renderer of `ctx`.
+pub(super) fn is_enabled(ctx: &PreprocessorContext) -> bool {
+    if !ctx.config.contains_key("preprocessor.vars") {
+        return false;
+    }
+    match ctx.config.get::<Vec<String>>("preprocessor.vars.renderers") {
+        Ok(Some(renderers)) => renderers.contains(&ctx.renderer),
+        _ => true,
+    }
+}
+
+/// Fails if any variables could not be replaced.
+pub(super) fn check_errors(errors: usize) -> Result<()> {
+    match errors {
+        0 => Ok(()),
+        1 => bail!("1 variable could not be replaced"),
+        n => bail!("{n} variables could not be replaced"),
+    }
+}
+
+/// Replaces the variables in `content`, except in code.
+///
+/// `error` is called with the message and location of each variable that
+/// isn't defined, which is left as it is.
+pub(super) fn replace_vars(
+    config: &Config,
+    content: &str,
+    mut error: impl FnMut(String, Range<usize>),
+) -> String {
+    let code = code_ranges(content);
+    replace_all(content, &code, |name, span| {
+        lookup(config, name).inspect_err(|message| error(message.clone(), span))
+    })
+}
+
+/// Finds the code spans and code blocks in `content`.
+pub(super) fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    new_cmark_parser(content, &MarkdownOptions::default())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replaces the variables in `s` with the values returned by `value`, which
+/// is called with the name and location of each variable. Variables whose
+/// value can't be found, and variables within the ranges in `skip`, are left
+/// as they are.
+fn replace_all(
+    s: &str,
+    skip: &[Range<usize>],
+    mut value: impl FnMut(&str, Range<usize>) -> Result<String, String>,
+) -> String {
+    static_regex!(
+        VAR,
+        r"(?x)                  # insignificant whitespace mode
+        \\\{\{\#var\s[^}]*\}\}  # match escaped variable
+        |                       # or
+        \{\{\s*\#var\s+         # variable opening parens and whitespace
+        ([^}\s]+)               # variable name
+        \s*\}\}                 # variable closing parens"
+    );
+
+    VAR.replace_all(s, |cap: &Captures<'_>| {
+        let mat = cap.get(0).unwrap();
+        if skip
+            .iter()
+            .any(|skip| skip.start < mat.end() && mat.start() < skip.end)
+        {
+            return mat.as_str().to_string();
+        }
+        let Some(name) = cap.get(1) else {
+            return mat.as_str()[ESCAPE_CHAR.len_utf8()..].to_string();
+        };
+        value(name.as_str(), mat.range()).unwrap_or_else(|_| mat.as_str().to_string())
+    })
+    .into_owned()
+}
+
+/// Returns the value of the variable `name`, or a message describing why it
+/// is not defined.
+fn lookup(config: &Config, name: &str) -> Result<String, String> {
+    if let Some(var) = name.strip_prefix("env.") {
+        return std::env::var(var).map_err(|_| format!("environment variable `{var}` is not set"));
+    }
+    let key = if name.starts_with("book.") {
+        name.to_string()
+    } else {
+        format!("preprocessor.vars.{name}")
+    };
+    match config.get::<Value>(&key) {
+        Ok(Some(value)) => {
+            format_value(&value).ok_or_else(|| format!("variable `{name}` is a table, not a value"))
+        }
+        Ok(None) => Err(format!("variable `{name}` is not defined")),
+        Err(e) => Err(format!("variable `{name}` could not be read: {e}")),
+    }
+}
+
+/// Formats a value from `book.toml` as text, or returns `None` for a table.
+fn format_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) => {
+            let values: Option<Vec<_>> = values.iter().map(format_value).collect();
+            Some(values?.join(", "))
+        }
+        Value::Table(_) => None,
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        r#"
+        [book]
+        title = "My Book"
+        authors = ["Alice", "Bob"]
+
+        [preprocessor.vars]
+        version = "1.2.3"
+        count = 3
+        urls.repo = "https://example.com/repo"
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn lookup_values() {
+        let config = config();
+        assert_eq!(lookup(&config, "version").unwrap(), "1.2.3");
+        assert_eq!(lookup(&config, "count").unwrap(), "3");
+        assert_eq!(
+            lookup(&config, "urls.repo").unwrap(),
+            "https://example.com/repo"
+        );
+        assert_eq!(lookup(&config, "book.title").unwrap(), "My Book");
+        assert_eq!(lookup(&config, "book.authors").unwrap(), "Alice, Bob");
+        assert_eq!(
+            lookup(&config, "nope").unwrap_err(),
+            "variable `nope` is not defined"
+        );
+        assert_eq!(
+            lookup(&config, "urls").unwrap_err(),
+            "variable `urls` is a table, not a value"
+        );
+        assert_eq!(
+            lookup(&config, "env.MDBOOK_VARS_TEST_UNSET").unwrap_err(),
+            "environment variable `MDBOOK_VARS_TEST_UNSET` is not set"
+        );
+    }
+
+    #[test]
+    fn replace_escaped_and_missing() {
+        let config = config();
+        let s = "Version {{#var version}} of {{ #var book.title }}, \
+                 not \\{{#var version}} or {{#include file.rs}}.";
+        let mut missing = Vec::new();
+        let replaced = replace_all(s, &[], |name, span| {
+            lookup(&config, name).inspect_err(|_| missing.push(span))
+        });
+        assert_eq!(
+            replaced,
+            "Version 1.2.3 of My Book, not {{#var version}} or {{#include file.rs}}."
+        );
+        assert!(missing.is_empty());
+
+        let s = "A {{#var nope}} here.";
+        let replaced = replace_all(s, &[], |name, span| {
+            lookup(&config, name).inspect_err(|_| missing.push(span))
+        });
+        assert_eq!(replaced, s);
+        assert_eq!(missing, [2..15]);
+    }
+
+    #[test]
+    fn skip_code() {
+        let config = config();
+        let s = "Version {{#var version}}, not `{{#var version}}`.\n\
+                 \n\
+                 ```toml\n\
+                 version = \"{{#var nope}}\"\n\
+                 ```\n";
+        let mut missing = Vec::new();
+        let replaced = replace_vars(&config, s, |_, span| missing.push(span));
+        assert_eq!(
+            replaced,
+            "Version 1.2.3, not `{{#var version}}`.\n\
+             \n\
+             ```toml\n\
+             version = \"{{#var nope}}\"\n\
+             ```\n"
+        );
+        assert!(missing.is_empty());
+    }
+}
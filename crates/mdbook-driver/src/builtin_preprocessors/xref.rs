@@ -0,0 +1,370 @@
+use super::ChapterSource;
+use anyhow::{Result, bail};
+use mdbook_core::book::{Book, BookItem};
+use mdbook_core::static_regex;
+use mdbook_core::utils::suggest;
+use mdbook_markdown::pulldown_cmark::{Event, Tag, TagEnd};
+use mdbook_markdown::{MarkdownOptions, new_cmark_parser};
+use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A preprocessor for linking to headings by label instead of by path.
+///
+/// A heading is given a label with the `{#label}` attribute, such as
+/// `## Installing {#install}`. Any chapter can then link to it with
+/// `[text](@install)`, `[](@install)`, or `{{#ref install}}`, which are
+/// replaced with a relative link to the heading. If the link has no text, the
+/// text of the heading is used. Referring to a label that doesn't exist is an
+/// error.
+///
+/// Like the `conditional` preprocessor, this only runs if there is a
+/// `[preprocessor.xref]` table in `book.toml`, because existing books may
+/// already link to paths that start with `@`.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct XrefPreprocessor;
+
+impl XrefPreprocessor {
+    /// Name of this preprocessor.
+    pub const NAME: &'static str = "xref";
+
+    /// Create a new `XrefPreprocessor`.
+    pub fn new() -> Self {
+        XrefPreprocessor
+    }
+}
+
+impl Preprocessor for XrefPreprocessor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
+        let mut targets: HashMap<String, Vec<Target>> = HashMap::new();
+        for item in book.iter() {
+            if let BookItem::Chapter(ch) = item
+                && let Some(path) = &ch.path
+            {
+                for (label, title) in find_labels(&ch.content) {
+                    let chapters = targets.entry(label).or_default();
+                    if !chapters.iter().any(|target| &target.path == path) {
+                        chapters.push(Target {
+                            path: path.clone(),
+                            title,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut errors = 0;
+        book.for_each_chapter_mut(|ch| {
+            let (Some(path), Some(source)) = (&ch.path, ChapterSource::new(ctx, ch)) else {
+                return;
+            };
+            let mut replaced = String::new();
+            let mut previous_end_index = 0;
+            for reference in find_refs(&ch.content) {
+                match resolve(&targets, path, &reference, &ch.content) {
+                    Ok(link) => {
+                        replaced.push_str(&ch.content[previous_end_index..reference.span.start]);
+                        replaced.push_str(&link);
+                        previous_end_index = reference.span.end;
+                    }
+                    Err((message, note)) => {
+                        let diagnostic =
+                            source.error("xref-label", message, reference.span.clone());
+                        match note {
+                            Some(note) => diagnostic.with_note(note).emit(),
+                            None => diagnostic.emit(),
+                        }
+                        errors += 1;
+                    }
+                }
+            }
+            if previous_end_index > 0 {
+                replaced.push_str(&ch.content[previous_end_index..]);
+                ch.content = replaced;
+            }
+        });
+
+        match errors {
+            0 => Ok(book),
+            1 => bail!("1 cross-reference could not be resolved"),
+            n => bail!("{n} cross-references could not be resolved"),
+        }
+    }
+}
+
+/// A heading with a label.
+struct Target {
+    /// The chapter containing the heading, relative to the source directory.
+    path: PathBuf,
+    /// The markdown of the text of the heading.
+    title: String,
+}
+
+/// A reference to a label in a chapter.
+#[derive(Debug, PartialEq)]
+struct Reference {
+    /// The location of the whole reference in the chapter.
+    span: Range<usize>,
+    /// The label that is referred to.
+    label: String,
+    /// The location of the text of the link, if it has any.
+    text: Option<Range<usize>>,
+    /// The title of the link, shown as a tooltip.
+    title: String,
+}
+
+/// Finds the labeled headings in `content`, returning each label with the
+/// markdown of the heading's text.
+fn find_labels(content: &str) -> Vec<(String, String)> {
+    let mut labels = Vec::new();
+    let mut heading = None;
+    let parser = new_cmark_parser(content, &MarkdownOptions::default());
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => {
+                heading = Some((id.to_string(), None::<Range<usize>>));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((label, text)) = heading.take() {
+                    let title = text.map_or("", |text| content[text].trim());
+                    labels.push((label, title.to_string()));
+                }
+            }
+            _ => {
+                if let Some((_, text)) = &mut heading {
+                    extend(text, range);
+                }
+            }
+        }
+    }
+    labels
+}
+
+/// Finds the references to labels in `content`, in order.
+///
+/// `{{#ref label}}` is not replaced in code, so that it can be shown in
+/// examples.
+fn find_refs(content: &str) -> Vec<Reference> {
+    static_regex!(REF, r"\{\{\s*#ref\s+([^}\s]+)\s*\}\}");
+
+    let mut refs = Vec::new();
+    let mut skip = Vec::new();
+    let mut link: Option<Reference> = None;
+    let parser = new_cmark_parser(content, &MarkdownOptions::default());
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => {
+                skip.push(range.clone());
+                if let Some(label) = dest_url.strip_prefix('@') {
+                    link = Some(Reference {
+                        span: range,
+                        label: label.to_string(),
+                        text: None,
+                        title: title.to_string(),
+                    });
+                }
+            }
+            Event::End(TagEnd::Link) => refs.extend(link.take()),
+            Event::Start(Tag::CodeBlock(_)) => skip.push(range),
+            _ => {
+                if let Event::Code(_) = event {
+                    skip.push(range.clone());
+                }
+                if let Some(link) = &mut link {
+                    extend(&mut link.text, range);
+                }
+            }
+        }
+    }
+
+    for cap in REF.captures_iter(content) {
+        let span = cap.get(0).unwrap().range();
+        if skip
+            .iter()
+            .any(|skip| skip.start < span.end && span.start < skip.end)
+        {
+            continue;
+        }
+        refs.push(Reference {
+            span,
+            label: cap[1].to_string(),
+            text: None,
+            title: String::new(),
+        });
+    }
+
+    refs.sort_by_key(|reference| reference.span.start);
+    refs
+}
+
+/// Extends `text` to include `range`.
+fn extend(text: &mut Option<Range<usize>>, range: Range<usize>) {
+    *text = Some(match text.take() {
+        Some(text) => text.start.min(range.start)..text.end.max(range.end),
+        None => range,
+    });
+}
+
+/// Returns the markdown link that `reference` in the chapter at `path` is
+/// replaced with, or the error message and an optional note if the label
+/// can't be resolved.
+fn resolve(
+    targets: &HashMap<String, Vec<Target>>,
+    path: &Path,
+    reference: &Reference,
+    content: &str,
+) -> Result<String, (String, Option<String>)> {
+    let label = &reference.label;
+    let target = match targets.get(label).map(Vec::as_slice) {
+        Some([target]) => target,
+        Some(chapters) if !chapters.is_empty() => {
+            let paths: Vec<_> = chapters
+                .iter()
+                .map(|target| format!("`{}`", target.path.display()))
+                .collect();
+            return Err((
+                format!("label `{label}` is defined in more than one chapter"),
+                Some(format!("defined in {}", paths.join(", "))),
+            ));
+        }
+        _ => {
+            let suggestion = suggest(label, targets.keys().map(String::as_str))
+                .map(|candidate| format!("did you mean `{candidate}`?"));
+            return Err((format!("unknown label `{label}`"), suggestion));
+        }
+    };
+
+    let mut url = String::new();
+    if target.path != path {
+        url.push_str(&relative_url(path, &target.path));
+    }
+    url.push('#');
+    url.push_str(label);
+    if url.contains([' ', '(', ')']) {
+        url = format!("<{url}>");
+    }
+
+    let text = match &reference.text {
+        Some(text) => &content[text.clone()],
+        None => &target.title,
+    };
+    let mut link = format!("[{text}]({url}");
+    if !reference.title.is_empty() {
+        let title = reference.title.replace('\\', "\\\\").replace('"', "\\\"");
+        link.push_str(&format!(" \"{title}\""));
+    }
+    link.push(')');
+    Ok(link)
+}
+
+/// The relative URL of the chapter at `to` from the chapter at `from`.
+fn relative_url(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.parent().into_iter().flat_map(Path::iter).collect();
+    let to: Vec<_> = to.iter().collect();
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(to.len() - 1);
+    let mut url = "../".repeat(from.len() - common);
+    let rest: Vec<_> = to[common..]
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect();
+    url.push_str(&rest.join("/"));
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_of_headings() {
+        let content = "# Intro {#intro}\n\n## The `main` function {#main .fn}\n\n## No label\n";
+        assert_eq!(
+            find_labels(content),
+            [
+                ("intro".to_string(), "Intro".to_string()),
+                ("main".to_string(), "The `main` function".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn refs_outside_code() {
+        let content = "See [](@intro), [the intro](@intro \"Tip\") and {{#ref main}}.\n\
+                       Not [a link](other.md) or `{{#ref code}}`.\n\n\
+                       ```\n{{#ref block}}\n```\n";
+        let refs = find_refs(content);
+        let labels: Vec<_> = refs.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["intro", "intro", "main"]);
+        assert_eq!(&content[refs[0].span.clone()], "[](@intro)");
+        assert_eq!(refs[0].text, None);
+        assert_eq!(&content[refs[1].text.clone().unwrap()], "the intro");
+        assert_eq!(refs[1].title, "Tip");
+        assert_eq!(&content[refs[2].span.clone()], "{{#ref main}}");
+    }
+
+    #[test]
+    fn resolve_relative_urls() {
+        let mut targets = HashMap::new();
+        targets.insert(
+            "intro".to_string(),
+            vec![Target {
+                path: PathBuf::from("intro.md"),
+                title: "Introduction".to_string(),
+            }],
+        );
+        targets.insert(
+            "setup".to_string(),
+            vec![Target {
+                path: PathBuf::from("guide/setup.md"),
+                title: "Setting up".to_string(),
+            }],
+        );
+        let content = "[](@intro) [the intro](@intro) {{#ref setup}} [](@stup) [](@nope)";
+        let refs = find_refs(content);
+        let link = |path: &str, i: usize| {
+            resolve(&targets, Path::new(path), &refs[i], content).map_err(|(message, _)| message)
+        };
+        assert_eq!(
+            link("guide/usage.md", 0).unwrap(),
+            "[Introduction](../intro.md#intro)"
+        );
+        assert_eq!(
+            link("guide/usage.md", 1).unwrap(),
+            "[the intro](../intro.md#intro)"
+        );
+        assert_eq!(
+            link("guide/usage.md", 2).unwrap(),
+            "[Setting up](setup.md#setup)"
+        );
+        assert_eq!(link("intro.md", 0).unwrap(), "[Introduction](#intro)");
+        assert_eq!(
+            link("intro.md", 2).unwrap(),
+            "[Setting up](guide/setup.md#setup)"
+        );
+        assert_eq!(
+            link("other/deep/page.md", 2).unwrap(),
+            "[Setting up](../../guide/setup.md#setup)"
+        );
+        assert_eq!(
+            resolve(&targets, Path::new("intro.md"), &refs[3], content),
+            Err((
+                "unknown label `stup`".to_string(),
+                Some("did you mean `setup`?".to_string())
+            ))
+        );
+        assert_eq!(link("intro.md", 4).unwrap_err(), "unknown label `nope`");
+    }
+}
@@ -0,0 +1,574 @@
+use anyhow::{Context, Result};
+use mdbook_core::book::{Book, BookItem, Chapter};
+use mdbook_core::config::{EpubConfig, TextDirection};
+use mdbook_core::utils::{escape_html, fs};
+use mdbook_html::theme::fonts;
+use mdbook_html::{XhtmlChapter, render_xhtml_chapters};
+use mdbook_renderer::{RenderContext, Renderer};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, trace, warn};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// The stylesheet included in every EPUB.
+static STYLESHEET: &[u8] = include_bytes!("epub.css");
+
+/// All content files live in this directory of the archive.
+const CONTENT_DIR: &str = "OEBPS";
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// A renderer that outputs the book as an EPUB 3 e-book.
+///
+/// The chapters are converted to XHTML with the same process used by the HTML
+/// renderer.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct EpubRenderer;
+
+impl EpubRenderer {
+    /// Create a new `EpubRenderer` instance.
+    pub fn new() -> Self {
+        EpubRenderer
+    }
+}
+
+impl Renderer for EpubRenderer {
+    fn name(&self) -> &str {
+        "epub"
+    }
+
+    fn render(&self, ctx: &RenderContext) -> Result<()> {
+        let destination = &ctx.destination;
+        let epub_config: EpubConfig = ctx.config.get("output.epub")?.unwrap_or_default();
+        let html_config = ctx.config.html_config().unwrap_or_default();
+
+        if destination.exists() {
+            fs::remove_dir_content(destination)
+                .with_context(|| "Unable to remove stale EPUB output")?;
+        }
+        fs::create_dir_all(destination)
+            .with_context(|| "Unexpected error when constructing destination path")?;
+
+        trace!("epub render");
+        let chapters = render_xhtml_chapters(&ctx.book, &html_config, ctx.config.rust.edition);
+
+        let mut epub = Epub::default();
+        epub.add_resource("css/epub.css", STYLESHEET.to_vec());
+        for css in &epub_config.additional_css {
+            let data = std::fs::read(ctx.root.join(css))
+                .with_context(|| format!("failed to read `{}`", css.display()))?;
+            epub.add_resource(&css.to_string_lossy(), data);
+        }
+        for (name, data) in fonts::OPEN_SANS
+            .iter()
+            .chain(std::iter::once(&fonts::SOURCE_CODE_PRO))
+        {
+            epub.add_resource(name, data.to_vec());
+        }
+
+        let src_dir = ctx.root.join(&ctx.config.book.src);
+        for chapter in &chapters {
+            for image in &chapter.images {
+                let Some(path) = resolve_image(&chapter.path, image) else {
+                    warn!(
+                        "image `{image}` in `{}` is outside of the book and was not included",
+                        chapter.path.display()
+                    );
+                    continue;
+                };
+                let name = path.to_string_lossy().replace('\\', "/");
+                if epub.resources.iter().any(|(n, _)| *n == name) {
+                    continue;
+                }
+                match std::fs::read(src_dir.join(&path)) {
+                    Ok(data) => epub.add_resource(&name, data),
+                    Err(e) => warn!(
+                        "failed to read image `{image}` in `{}`: {e}",
+                        chapter.path.display()
+                    ),
+                }
+            }
+        }
+
+        let stylesheets: Vec<String> = std::iter::once("css/epub.css".to_string())
+            .chain(
+                epub_config
+                    .additional_css
+                    .iter()
+                    .map(|css| css.to_string_lossy().replace('\\', "/")),
+            )
+            .collect();
+
+        let title = ctx.config.book.title.as_deref().unwrap_or("Book");
+        let language = ctx.config.book.language.as_deref().unwrap_or("en");
+        let direction = match ctx.config.book.realized_text_direction() {
+            TextDirection::RightToLeft => "rtl",
+            _ => "ltr",
+        };
+
+        let filename = format!("{}.epub", file_stem(title));
+        let path = destination.join(&filename);
+        let file = std::fs::File::create(&path)
+            .with_context(|| format!("failed to create `{}`", path.display()))?;
+        let mut zip = ZipWriter::new(file);
+        let stored =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let deflated =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        // The mimetype must be the first file, and must not be compressed.
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+
+        zip.start_file("META-INF/container.xml", deflated)?;
+        zip.write_all(CONTAINER_XML.as_bytes())?;
+
+        for chapter in &chapters {
+            let name = chapter_href(chapter);
+            let page_title = ctx
+                .chapter_titles
+                .get(chapter.chapter.path.as_ref().unwrap())
+                .unwrap_or(&chapter.chapter.name);
+            let page = xhtml_page(
+                page_title,
+                language,
+                direction,
+                &stylesheets,
+                &fs::path_to_root(&chapter.path),
+                &chapter.content,
+            );
+            zip.start_file(format!("{CONTENT_DIR}/{name}"), deflated)?;
+            zip.write_all(page.as_bytes())?;
+        }
+
+        let nav = nav_document(&ctx.book, language, direction, html_config.no_section_label);
+        zip.start_file(format!("{CONTENT_DIR}/nav.xhtml"), deflated)?;
+        zip.write_all(nav.as_bytes())?;
+
+        for (name, data) in &epub.resources {
+            zip.start_file(format!("{CONTENT_DIR}/{name}"), deflated)?;
+            zip.write_all(data)?;
+        }
+
+        let package = package_document(ctx, &epub_config, &epub, &chapters, direction);
+        zip.start_file(format!("{CONTENT_DIR}/content.opf"), deflated)?;
+        zip.write_all(package.as_bytes())?;
+
+        zip.finish()
+            .with_context(|| format!("failed to write `{}`", path.display()))?;
+
+        info!("EPUB book written to `{}`", path.display());
+
+        Ok(())
+    }
+}
+
+/// Files other than chapters to include in the EPUB.
+#[derive(Default)]
+struct Epub {
+    /// Pairs of (path relative to the content directory, contents).
+    resources: Vec<(String, Vec<u8>)>,
+}
+
+impl Epub {
+    fn add_resource(&mut self, name: &str, data: Vec<u8>) {
+        self.resources.push((name.to_string(), data));
+    }
+}
+
+/// Returns the location of a chapter relative to the content directory.
+fn chapter_href(chapter: &XhtmlChapter<'_>) -> String {
+    chapter.path.to_string_lossy().replace('\\', "/")
+}
+
+/// Resolves the `src` of an image relative to the book's source directory.
+///
+/// Returns `None` if the image is outside of the source directory.
+fn resolve_image(chapter_path: &Path, src: &str) -> Option<PathBuf> {
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+    let mut path = PathBuf::new();
+    let joined = chapter_path.parent().unwrap_or(Path::new("")).join(src);
+    for component in joined.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::ParentDir => {
+                if !path.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Returns a file name based on the book title.
+fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = stem.trim().trim_matches('.');
+    if stem.is_empty() {
+        "book".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Returns the media type of a resource based on its extension.
+fn media_type(name: &str) -> &'static str {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "css" => "text/css",
+        "gif" => "image/gif",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "otf" => "font/otf",
+        "ttf" => "font/ttf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Wraps the content of a chapter in an XHTML document.
+fn xhtml_page(
+    title: &str,
+    language: &str,
+    direction: &str,
+    stylesheets: &[String],
+    path_to_root: &str,
+    content: &str,
+) -> String {
+    let mut links = String::new();
+    for css in stylesheets {
+        links.push_str(&format!(
+            "    <link rel=\"stylesheet\" type=\"text/css\" href=\"{path_to_root}{}\" />\n",
+            escape_html(css)
+        ));
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{language}" lang="{language}" dir="{direction}">
+<head>
+    <meta charset="UTF-8" />
+    <title>{title}</title>
+{links}</head>
+<body>
+{content}
+</body>
+</html>
+"#,
+        language = escape_html(language),
+        title = escape_html(title),
+    )
+}
+
+/// An entry in the navigation document.
+struct NavEntry {
+    label: String,
+    href: Option<String>,
+    children: Vec<NavEntry>,
+}
+
+/// Builds the navigation entries for a list of book items.
+fn nav_entries(items: &[BookItem], no_section_label: bool) -> Vec<NavEntry> {
+    let mut entries = Vec::new();
+    let mut part: Option<NavEntry> = None;
+    for item in items {
+        let entry = match item {
+            BookItem::Chapter(ch) => chapter_entry(ch, no_section_label),
+            BookItem::PartTitle(title) => {
+                entries.extend(part.take());
+                part = Some(NavEntry {
+                    label: title.clone(),
+                    href: None,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            BookItem::Separator => continue,
+        };
+        // Prefix and suffix chapters are unnumbered and never part of a part.
+        let in_part = matches!(item, BookItem::Chapter(ch) if ch.number.is_some());
+        match &mut part {
+            Some(part) if in_part => part.children.push(entry),
+            _ => {
+                entries.extend(part.take());
+                entries.push(entry);
+            }
+        }
+    }
+    entries.extend(part);
+    entries
+}
+
+fn chapter_entry(ch: &Chapter, no_section_label: bool) -> NavEntry {
+    NavEntry {
+        label: if no_section_label {
+            ch.name.clone()
+        } else {
+            ch.to_string()
+        },
+        href: ch.path.as_ref().map(|path| {
+            path.with_extension("html")
+                .to_string_lossy()
+                .replace('\\', "/")
+        }),
+        children: nav_entries(&ch.sub_items, no_section_label),
+    }
+}
+
+/// Renders a list of navigation entries as an `<ol>`.
+///
+/// An entry without a link must have children, so entries for draft chapters
+/// without any sub-chapters are omitted.
+fn render_nav_list(entries: &[NavEntry], depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    output.push_str(&format!("{indent}<ol>\n"));
+    for entry in entries {
+        if entry.href.is_none() && entry.children.is_empty() {
+            continue;
+        }
+        let label = escape_html(&entry.label);
+        match &entry.href {
+            Some(href) => output.push_str(&format!(
+                "{indent}  <li><a href=\"{}\">{label}</a>",
+                escape_html(href)
+            )),
+            None => output.push_str(&format!("{indent}  <li><span>{label}</span>")),
+        }
+        if entry.children.is_empty() {
+            output.push_str("</li>\n");
+        } else {
+            output.push('\n');
+            render_nav_list(&entry.children, depth + 2, output);
+            output.push_str(&format!("{indent}  </li>\n"));
+        }
+    }
+    output.push_str(&format!("{indent}</ol>\n"));
+}
+
+/// Generates the EPUB navigation document from the book's hierarchy.
+fn nav_document(book: &Book, language: &str, direction: &str, no_section_label: bool) -> String {
+    let mut list = String::new();
+    render_nav_list(&nav_entries(&book.items, no_section_label), 2, &mut list);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{language}" lang="{language}" dir="{direction}">
+<head>
+    <meta charset="UTF-8" />
+    <title>Table of Contents</title>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>Table of Contents</h1>
+{list}  </nav>
+</body>
+</html>
+"#,
+        language = escape_html(language),
+    )
+}
+
+/// Generates the EPUB package document.
+fn package_document(
+    ctx: &RenderContext,
+    epub_config: &EpubConfig,
+    epub: &Epub,
+    chapters: &[XhtmlChapter<'_>],
+    direction: &str,
+) -> String {
+    let book = &ctx.config.book;
+    let title = book.title.as_deref().unwrap_or("Book");
+    let language = book.language.as_deref().unwrap_or("en");
+    let identifier = epub_config
+        .identifier
+        .clone()
+        .unwrap_or_else(|| default_identifier(title, &book.authors));
+
+    let mut metadata = String::new();
+    metadata.push_str(&format!(
+        "    <dc:identifier id=\"book-id\">{}</dc:identifier>\n",
+        escape_html(&identifier)
+    ));
+    metadata.push_str(&format!(
+        "    <dc:title>{}</dc:title>\n",
+        escape_html(title)
+    ));
+    metadata.push_str(&format!(
+        "    <dc:language>{}</dc:language>\n",
+        escape_html(language)
+    ));
+    for author in &book.authors {
+        metadata.push_str(&format!(
+            "    <dc:creator>{}</dc:creator>\n",
+            escape_html(author)
+        ));
+    }
+    if let Some(description) = &book.description {
+        metadata.push_str(&format!(
+            "    <dc:description>{}</dc:description>\n",
+            escape_html(description)
+        ));
+    }
+    metadata.push_str(&format!(
+        "    <meta property=\"dcterms:modified\">{}</meta>\n",
+        modified_timestamp()
+    ));
+
+    let mut manifest = String::new();
+    manifest.push_str(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let properties = if chapter.content.contains("<svg") {
+            " properties=\"svg\""
+        } else {
+            ""
+        };
+        manifest.push_str(&format!(
+            "    <item id=\"chapter-{i}\" href=\"{}\" media-type=\"application/xhtml+xml\"{properties}/>\n",
+            escape_html(&chapter_href(chapter))
+        ));
+        spine.push_str(&format!("    <itemref idref=\"chapter-{i}\"/>\n"));
+    }
+    for (i, (name, _)) in epub.resources.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"resource-{i}\" href=\"{}\" media-type=\"{}\"/>\n",
+            escape_html(name),
+            media_type(name)
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{language}" dir="{direction}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+{metadata}  </metadata>
+  <manifest>
+{manifest}  </manifest>
+  <spine page-progression-direction="{direction}">
+{spine}  </spine>
+</package>
+"#,
+        language = escape_html(language),
+    )
+}
+
+/// Derives a stable identifier from the book's title and authors.
+fn default_identifier(title: &str, authors: &[String]) -> String {
+    let mut digest = Sha256::new();
+    digest.update(title);
+    for author in authors {
+        digest.update([0]);
+        digest.update(author);
+    }
+    let hash = hex::encode(&digest.finalize()[..16]);
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hash[..8],
+        &hash[8..12],
+        &hash[12..16],
+        &hash[16..20],
+        &hash[20..]
+    )
+}
+
+/// The modification time of the book, in the format required by EPUB.
+///
+/// This honors `SOURCE_DATE_EPOCH` for reproducible builds.
+fn modified_timestamp() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        });
+    format_timestamp(secs)
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DDThh:mm:ssZ`.
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Converts days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1700000000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn image_paths() {
+        let chapter = Path::new("dir/chapter.html");
+        assert_eq!(
+            resolve_image(chapter, "img/a.png"),
+            Some(PathBuf::from("dir/img/a.png"))
+        );
+        assert_eq!(
+            resolve_image(chapter, "../a.png?v=1"),
+            Some(PathBuf::from("a.png"))
+        );
+        assert_eq!(resolve_image(chapter, "../../a.png"), None);
+    }
+
+    #[test]
+    fn file_stems() {
+        assert_eq!(file_stem("My Book: Part 1"), "My Book_ Part 1");
+        assert_eq!(file_stem("  "), "book");
+    }
+}
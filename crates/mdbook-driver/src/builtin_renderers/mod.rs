@@ -1,6 +1,7 @@
 //! Built-in renderers.
 //!
-//! The HTML renderer can be found in the [`mdbook_html`] crate.
+//! The HTML renderer can be found in the [`mdbook_html`] crate. The EPUB
+//! renderer reuses its conversion of chapters to XHTML.
 
 use anyhow::{Context, Result, bail};
 use mdbook_core::utils::fs;
@@ -8,8 +9,10 @@ use mdbook_renderer::{RenderContext, Renderer};
 use std::process::Stdio;
 use tracing::{error, info, trace, warn};
 
+pub use self::epub_renderer::EpubRenderer;
 pub use self::markdown_renderer::MarkdownRenderer;
 
+mod epub_renderer;
 mod markdown_renderer;
 
 /// A generic renderer which will shell out to an arbitrary executable.
@@ -71,7 +71,7 @@ mod load;
 mod mdbook;
 
 use anyhow::{Context, Result, bail};
-pub use mdbook::MDBook;
+pub use mdbook::{LoadOptions, MDBook};
 pub use mdbook_core::{book, config, errors};
 use shlex::Shlex;
 use std::path::{Path, PathBuf};
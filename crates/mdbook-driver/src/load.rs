@@ -1,10 +1,13 @@
 use anyhow::{Context, Result};
-use mdbook_core::book::{Book, BookItem, Chapter};
-use mdbook_core::config::BuildConfig;
+use mdbook_core::book::{Book, BookItem, Chapter, ChapterMetadata};
+use mdbook_core::config::{BuildConfig, GlobOrder};
 use mdbook_core::utils::{escape_html, fs};
+use mdbook_markdown::pulldown_cmark::{Event, Parser, Tag, TagEnd};
 use mdbook_summary::{Link, Summary, SummaryItem, parse_summary};
-use std::path::Path;
-use tracing::debug;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
 
 /// Load a book into memory from its `src/` directory.
 pub(crate) fn load_book<P: AsRef<Path>>(src_dir: P, cfg: &BuildConfig) -> Result<Book> {
@@ -19,7 +22,39 @@ pub(crate) fn load_book<P: AsRef<Path>>(src_dir: P, cfg: &BuildConfig) -> Result
         create_missing(src_dir, &summary).with_context(|| "Unable to create missing chapters")?;
     }
 
-    load_book_from_disk(&summary, src_dir)
+    load_book_from_disk(&summary, src_dir, cfg)
+}
+
+/// Load a translation of a book from its source directory.
+///
+/// The chapters that haven't been translated are loaded from `fallback_dir`,
+/// the source directory of the default language, which is next to `src_dir`.
+/// Their paths are still relative to `src_dir`, and
+/// [`Config::chapter_file`](mdbook_core::config::Config::chapter_file) finds
+/// their files.
+/// If the translation has no `SUMMARY.md`, the one of the default language is
+/// used.
+pub(crate) fn load_translation(
+    src_dir: &Path,
+    fallback_dir: &Path,
+    cfg: &BuildConfig,
+) -> Result<Book> {
+    let summary_md = if src_dir.join("SUMMARY.md").exists() {
+        src_dir.join("SUMMARY.md")
+    } else {
+        debug!(
+            "Using the summary of the default language for {}",
+            src_dir.display()
+        );
+        fallback_dir.join("SUMMARY.md")
+    };
+
+    let summary_content = fs::read_to_string(&summary_md)?;
+    let summary = parse_summary(&summary_content)
+        .with_context(|| format!("Summary parsing failed for file={summary_md:?}"))?;
+
+    let glob_dir = summary_md.parent().unwrap();
+    load_chapters(&summary, src_dir, glob_dir, Some(fallback_dir), cfg)
 }
 
 fn create_missing(src_dir: &Path, summary: &Summary) -> Result<()> {
@@ -57,9 +92,35 @@ fn create_missing(src_dir: &Path, summary: &Summary) -> Result<()> {
 ///
 /// You need to pass in the book's source directory because all the links in
 /// `SUMMARY.md` give the chapter locations relative to it.
-pub(crate) fn load_book_from_disk<P: AsRef<Path>>(summary: &Summary, src_dir: P) -> Result<Book> {
-    debug!("Loading the book from disk");
+pub(crate) fn load_book_from_disk<P: AsRef<Path>>(
+    summary: &Summary,
+    src_dir: P,
+    cfg: &BuildConfig,
+) -> Result<Book> {
     let src_dir = src_dir.as_ref();
+    load_chapters(summary, src_dir, src_dir, None, cfg)
+}
+
+/// Loads the chapters of `summary` from `src_dir`, or from `fallback_dir` if
+/// they don't exist. Globs are matched against the files in `glob_dir`.
+fn load_chapters(
+    summary: &Summary,
+    src_dir: &Path,
+    glob_dir: &Path,
+    fallback_dir: Option<&Path>,
+    cfg: &BuildConfig,
+) -> Result<Book> {
+    debug!("Loading the book from disk");
+
+    let mut summary = summary.clone();
+    for items in [
+        &mut summary.prefix_chapters,
+        &mut summary.numbered_chapters,
+        &mut summary.suffix_chapters,
+    ] {
+        expand_globs(items, glob_dir, cfg.summary_glob_order)?;
+    }
+    summary.check_for_duplicates()?;
 
     let prefix = summary.prefix_chapters.iter();
     let numbered = summary.numbered_chapters.iter();
@@ -69,42 +130,236 @@ pub(crate) fn load_book_from_disk<P: AsRef<Path>>(summary: &Summary, src_dir: P)
 
     let mut chapters = Vec::new();
 
+    let dirs = SourceDirs {
+        src_dir,
+        fallback_dir,
+    };
     for summary_item in summary_items {
-        let chapter = load_summary_item(summary_item, src_dir, Vec::new())?;
+        let chapter = load_summary_item(summary_item, &dirs, Vec::new())?;
         chapters.push(chapter);
     }
 
     Ok(Book::new_with_items(chapters))
 }
 
-fn load_summary_item<P: AsRef<Path> + Clone>(
+/// Removes the front matter from the chapters of `book`, and returns their
+/// metadata by [`Chapter::source_path`].
+pub(crate) fn split_front_matter(book: &mut Book) -> Result<HashMap<PathBuf, ChapterMetadata>> {
+    let mut metadata = HashMap::new();
+    let mut error = None;
+    book.for_each_chapter_mut(|ch| {
+        let Some(source_path) = &ch.source_path else {
+            return;
+        };
+        if error.is_some() {
+            return;
+        }
+        match ChapterMetadata::split_front_matter(&ch.content) {
+            Ok((chapter_metadata, body)) => {
+                if body.len() != ch.content.len() {
+                    ch.content = body.to_string();
+                }
+                if !chapter_metadata.is_empty() {
+                    metadata.insert(source_path.clone(), chapter_metadata);
+                }
+            }
+            Err(e) => {
+                let message = format!(
+                    "failed to read front matter of chapter `{}`",
+                    source_path.display()
+                );
+                error = Some(e.context(message));
+            }
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(metadata),
+    }
+}
+
+/// Replaces the nested items of every glob entry with the files that match it.
+fn expand_globs(items: &mut [SummaryItem], src_dir: &Path, order: GlobOrder) -> Result<()> {
+    for item in items {
+        let SummaryItem::Link(link) = item else {
+            continue;
+        };
+        if link.glob.is_some() {
+            expand_glob(link, src_dir, order)?;
+        } else {
+            expand_globs(&mut link.nested_items, src_dir, order)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the files that match the glob of `link` as its nested items.
+///
+/// An `index.md` or `README.md` next to the matched files becomes the page
+/// of the entry itself. Otherwise, the entry is a draft chapter.
+fn expand_glob(link: &mut Link, src_dir: &Path, order: GlobOrder) -> Result<()> {
+    let pattern = link.glob.as_ref().unwrap();
+    let full_pattern = format!(
+        "{}/{}",
+        glob::Pattern::escape(&src_dir.to_string_lossy()),
+        pattern.to_string_lossy()
+    );
+    let index_dir = src_dir.join(pattern).parent().map(Path::to_path_buf);
+    let paths = glob::glob(&full_pattern)
+        .with_context(|| format!("invalid glob `{}` in SUMMARY.md", pattern.display()))?;
+
+    let mut chapters = Vec::new();
+    for path in paths {
+        let path =
+            path.with_context(|| format!("failed to expand glob `{}`", pattern.display()))?;
+        let Ok(location) = path.strip_prefix(src_dir) else {
+            continue;
+        };
+        if !path.is_file() || location == Path::new("SUMMARY.md") {
+            continue;
+        }
+        let is_index = matches!(
+            location.file_name().and_then(|name| name.to_str()),
+            Some("index.md" | "README.md")
+        );
+        if is_index && link.location.is_none() && path.parent() == index_dir.as_deref() {
+            link.location = Some(location.to_path_buf());
+            continue;
+        }
+        let title = chapter_title(&path)?;
+        chapters.push((title, location.to_path_buf()));
+    }
+    if chapters.is_empty() {
+        warn!(
+            "glob `{}` in SUMMARY.md did not match any files",
+            pattern.display()
+        );
+    }
+
+    chapters.sort_by(|(a_title, a_path), (b_title, b_path)| {
+        let by_name = || natural_cmp(&a_path.to_string_lossy(), &b_path.to_string_lossy());
+        match order {
+            GlobOrder::Title => a_title
+                .to_lowercase()
+                .cmp(&b_title.to_lowercase())
+                .then_with(by_name),
+            _ => by_name(),
+        }
+    });
+
+    link.nested_items = chapters
+        .into_iter()
+        .enumerate()
+        .map(|(i, (title, location))| {
+            let mut child = Link::new(title, location);
+            child.number = link.number.clone().map(|mut number| {
+                number.push(i as u32 + 1);
+                number
+            });
+            SummaryItem::Link(child)
+        })
+        .collect();
+    Ok(())
+}
+
+/// The title of a chapter added by a glob: the text of its first heading, or
+/// the file name if it doesn't have one.
+fn chapter_title(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)?;
+    let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+    let (_, body) = ChapterMetadata::split_front_matter(content)
+        .with_context(|| format!("failed to read front matter of `{}`", path.display()))?;
+
+    let mut title = None::<String>;
+    for event in Parser::new(body) {
+        match (event, &mut title) {
+            (Event::Start(Tag::Heading { .. }), None) => title = Some(String::new()),
+            (Event::End(TagEnd::Heading(_)), Some(_)) => break,
+            (Event::Text(text) | Event::Code(text), Some(title)) => title.push_str(&text),
+            _ => {}
+        }
+    }
+    match title {
+        Some(title) if !title.trim().is_empty() => Ok(title.trim().to_string()),
+        _ => Ok(path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()),
+    }
+}
+
+/// Compares two strings, treating runs of ASCII digits as numbers.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ac), Some(bc)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ac.is_ascii_digit() && bc.is_ascii_digit() {
+            let a_end = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let b_end = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (a_num, b_num) = (
+                a[..a_end].trim_start_matches('0'),
+                b[..b_end].trim_start_matches('0'),
+            );
+            let ordering = a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (&a[a_end..], &b[b_end..]);
+        } else {
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            (a, b) = (&a[ac.len_utf8()..], &b[bc.len_utf8()..]);
+        }
+    }
+}
+
+/// The directories that chapters are loaded from.
+struct SourceDirs<'a> {
+    /// The source directory of the book.
+    src_dir: &'a Path,
+    /// The source directory of the default language, for the chapters that
+    /// haven't been translated.
+    fallback_dir: Option<&'a Path>,
+}
+
+fn load_summary_item(
     item: &SummaryItem,
-    src_dir: P,
+    dirs: &SourceDirs<'_>,
     parent_names: Vec<String>,
 ) -> Result<BookItem> {
     match item {
         SummaryItem::Separator => Ok(BookItem::Separator),
-        SummaryItem::Link(link) => load_chapter(link, src_dir, parent_names).map(BookItem::Chapter),
+        SummaryItem::Link(link) => load_chapter(link, dirs, parent_names).map(BookItem::Chapter),
         SummaryItem::PartTitle(title) => Ok(BookItem::PartTitle(title.clone())),
         _ => panic!("SummaryItem {item:?} not covered"),
     }
 }
 
-fn load_chapter<P: AsRef<Path>>(
-    link: &Link,
-    src_dir: P,
-    parent_names: Vec<String>,
-) -> Result<Chapter> {
-    let src_dir = src_dir.as_ref();
+fn load_chapter(link: &Link, dirs: &SourceDirs<'_>, parent_names: Vec<String>) -> Result<Chapter> {
+    let src_dir = dirs.src_dir;
 
     let mut ch = if let Some(ref link_location) = link.location {
         debug!("Loading {} ({})", link.name, link_location.display());
 
-        let location = if link_location.is_absolute() {
+        let mut location = if link_location.is_absolute() {
             link_location.clone()
         } else {
             src_dir.join(link_location)
         };
+        // The paths of a chapter that hasn't been translated are still
+        // relative to the source directory of the translation.
+        let mut dir = src_dir;
+        if let Some(fallback_dir) = dirs.fallback_dir
+            && !location.exists()
+            && !link_location.is_absolute()
+        {
+            debug!("Using the default language for {}", link_location.display());
+            location = fallback_dir.join(link_location);
+            dir = fallback_dir;
+        }
 
         let mut content = std::fs::read_to_string(&location)
             .with_context(|| format!("failed to read chapter `{}`", link_location.display()))?;
@@ -114,7 +369,7 @@ fn load_chapter<P: AsRef<Path>>(
         }
 
         let stripped = location
-            .strip_prefix(src_dir)
+            .strip_prefix(dir)
             .expect("Chapters are always inside a book");
 
         Chapter::new(&link.name, content, stripped, parent_names.clone())
@@ -130,7 +385,7 @@ fn load_chapter<P: AsRef<Path>>(
     let sub_items = link
         .nested_items
         .iter()
-        .map(|i| load_summary_item(i, src_dir, sub_item_parents.clone()))
+        .map(|i| load_summary_item(i, dirs, sub_item_parents.clone()))
         .collect::<Result<Vec<_>>>()?;
 
     ch.sub_items = sub_items;
@@ -154,6 +409,14 @@ And here is some \
                                      more text.
 ";
 
+    /// The directories to load a book without translations from.
+    fn dirs(src_dir: &Path) -> SourceDirs<'_> {
+        SourceDirs {
+            src_dir,
+            fallback_dir: None,
+        }
+    }
+
     /// Create a dummy `Link` in a temporary directory.
     fn dummy_link() -> (Link, TempDir) {
         let temp = TempFileBuilder::new().prefix("book").tempdir().unwrap();
@@ -193,7 +456,7 @@ And here is some \
             Vec::new(),
         );
 
-        let got = load_chapter(&link, temp_dir.path(), Vec::new()).unwrap();
+        let got = load_chapter(&link, &dirs(temp_dir.path()), Vec::new()).unwrap();
         assert_eq!(got, should_be);
     }
 
@@ -213,7 +476,7 @@ And here is some \
             Vec::new(),
         );
 
-        let got = load_chapter(&link, temp_dir.path(), Vec::new()).unwrap();
+        let got = load_chapter(&link, &dirs(temp_dir.path()), Vec::new()).unwrap();
         assert_eq!(got, should_be);
     }
 
@@ -221,7 +484,7 @@ And here is some \
     fn cant_load_a_nonexistent_chapter() {
         let link = Link::new("Chapter 1", "/foo/bar/baz.md");
 
-        let got = load_chapter(&link, "", Vec::new());
+        let got = load_chapter(&link, &dirs(Path::new("")), Vec::new());
         assert!(got.is_err());
     }
 
@@ -245,7 +508,8 @@ And here is some \
         ];
         let should_be = BookItem::Chapter(chapter);
 
-        let got = load_summary_item(&SummaryItem::Link(root), temp.path(), Vec::new()).unwrap();
+        let got =
+            load_summary_item(&SummaryItem::Link(root), &dirs(temp.path()), Vec::new()).unwrap();
         assert_eq!(got, should_be);
     }
 
@@ -263,7 +527,7 @@ And here is some \
         let items = vec![BookItem::Chapter(chapter)];
         let should_be = Book::new_with_items(items);
 
-        let got = load_book_from_disk(&summary, temp.path()).unwrap();
+        let got = load_book_from_disk(&summary, temp.path(), &BuildConfig::default()).unwrap();
 
         assert_eq!(got, should_be);
     }
@@ -274,7 +538,7 @@ And here is some \
         let mut summary = Summary::default();
         let link = Link::new("Empty", "");
         summary.numbered_chapters = vec![SummaryItem::Link(link)];
-        let got = load_book_from_disk(&summary, temp.path());
+        let got = load_book_from_disk(&summary, temp.path(), &BuildConfig::default());
         assert!(got.is_err());
     }
 
@@ -288,7 +552,7 @@ And here is some \
         let link = Link::new("nested", dir);
         summary.numbered_chapters = vec![SummaryItem::Link(link)];
 
-        let got = load_book_from_disk(&summary, temp.path());
+        let got = load_book_from_disk(&summary, temp.path(), &BuildConfig::default());
         assert!(got.is_err());
     }
 
@@ -306,4 +570,105 @@ And here is some \
         );
         assert_eq!(error_message, expected);
     }
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec!["10-usage.md", "2-intro.md", "b.md", "02-setup.md", "a.md"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            ["2-intro.md", "02-setup.md", "10-usage.md", "a.md", "b.md"]
+        );
+    }
+
+    #[test]
+    fn expand_glob_entries() {
+        let temp = TempFileBuilder::new().prefix("book").tempdir().unwrap();
+        let reference = temp.path().join("reference");
+        fs::write(reference.join("README.md"), "# Reference").unwrap();
+        fs::write(reference.join("10-zeta.md"), "# Zeta\n").unwrap();
+        fs::write(
+            reference.join("2-alpha.md"),
+            "---\ndraft: true\n---\n## *Alpha*\n",
+        )
+        .unwrap();
+        fs::write(reference.join("notes.md"), "No heading.\n").unwrap();
+
+        let summary = parse_summary("- [Reference](reference/*.md)\n").unwrap();
+        let mut cfg = BuildConfig::default();
+        let got = load_book_from_disk(&summary, temp.path(), &cfg).unwrap();
+        let BookItem::Chapter(parent) = &got.items[0] else {
+            panic!("expected a chapter");
+        };
+        assert_eq!(parent.path, Some(PathBuf::from("reference/README.md")));
+        let children: Vec<_> = parent
+            .sub_items
+            .iter()
+            .map(|item| match item {
+                BookItem::Chapter(ch) => format!("{} {}", ch.number.as_ref().unwrap(), ch.name),
+                _ => panic!("expected a chapter"),
+            })
+            .collect();
+        assert_eq!(children, ["1.1. Alpha", "1.2. Zeta", "1.3. notes"]);
+
+        cfg.summary_glob_order = GlobOrder::Title;
+        let got = load_book_from_disk(&summary, temp.path(), &cfg).unwrap();
+        let names: Vec<_> = got
+            .iter()
+            .map(|item| match item {
+                BookItem::Chapter(ch) => ch.name.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(names, ["Reference", "Alpha", "notes", "Zeta"]);
+    }
+
+    #[test]
+    fn glob_entries_are_checked_for_duplicates() {
+        let temp = TempFileBuilder::new().prefix("book").tempdir().unwrap();
+        fs::write(temp.path().join("reference/a.md"), "# A").unwrap();
+
+        let summary =
+            parse_summary("- [A](reference/a.md)\n- [Reference](reference/*.md)\n").unwrap();
+        let got = load_book_from_disk(&summary, temp.path(), &BuildConfig::default());
+        assert_eq!(
+            got.unwrap_err().to_string(),
+            r#"Duplicate file in SUMMARY.md: "reference/a.md""#
+        );
+    }
+
+    #[test]
+    fn untranslated_chapters_fall_back_to_the_default_language() {
+        let temp = TempFileBuilder::new().prefix("book").tempdir().unwrap();
+        let en = temp.path().join("en");
+        let ja = temp.path().join("ja");
+        fs::write(
+            en.join("SUMMARY.md"),
+            "- [Intro](intro.md)\n- [Guide](guide/index.md)\n",
+        )
+        .unwrap();
+        fs::write(en.join("intro.md"), "# Intro").unwrap();
+        fs::write(en.join("guide/index.md"), "# Guide").unwrap();
+        fs::write(ja.join("intro.md"), "# はじめに").unwrap();
+
+        let got = load_translation(&ja, &en, &BuildConfig::default()).unwrap();
+        let chapters: Vec<_> = got
+            .iter()
+            .map(|item| match item {
+                BookItem::Chapter(ch) => (
+                    ch.content.as_str(),
+                    ch.path.clone().unwrap(),
+                    ch.source_path.clone().unwrap(),
+                ),
+                _ => panic!("expected a chapter"),
+            })
+            .collect();
+        assert_eq!(
+            chapters,
+            [
+                ("# はじめに", "intro.md".into(), "intro.md".into()),
+                ("# Guide", "guide/index.md".into(), "guide/index.md".into()),
+            ]
+        );
+    }
 }
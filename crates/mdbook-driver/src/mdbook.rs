@@ -1,30 +1,49 @@
 //! The high-level interface for loading and rendering books.
 
-use crate::builtin_preprocessors::{CmdPreprocessor, IndexPreprocessor, LinkPreprocessor};
-use crate::builtin_renderers::{CmdRenderer, MarkdownRenderer};
+use crate::builtin_preprocessors::{
+    CmdPreprocessor, ConditionalPreprocessor, IndexPreprocessor, LinkPreprocessor,
+    VarsPreprocessor, XrefPreprocessor,
+};
+use crate::builtin_renderers::{CmdRenderer, EpubRenderer, MarkdownRenderer};
 use crate::init::BookBuilder;
-use crate::load::{load_book, load_book_from_disk};
+use crate::load::{load_book, load_book_from_disk, load_translation, split_front_matter};
 use anyhow::{Context, Error, Result, bail};
 use indexmap::IndexMap;
-use mdbook_core::book::{Book, BookItem, BookItems};
-use mdbook_core::config::{Config, RustEdition};
+use mdbook_core::book::{Book, BookItem, BookItems, ChapterMetadata};
+use mdbook_core::config::{Config, RustEdition, SchemaRegistry};
+use mdbook_core::diagnostic::Diagnostic;
 use mdbook_core::utils::fs;
 use mdbook_html::HtmlHandlebars;
 use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
 use mdbook_renderer::{RenderContext, Renderer};
 use mdbook_summary::Summary;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::Builder as TempFileBuilder;
 use topological_sort::TopologicalSort;
-use tracing::{debug, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 #[cfg(test)]
 mod tests;
 
+/// Options for loading a book with [`MDBook::load_with_options`].
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct LoadOptions {
+    /// The name of the config profile to apply.
+    ///
+    /// If `None`, the profile in `build.profile` is used, if any.
+    pub profile: Option<String>,
+    /// Whether unknown keys in `book.toml` are an error instead of a warning.
+    pub strict: bool,
+    /// The schemas that the keys in `book.toml` are checked against.
+    pub schemas: SchemaRegistry,
+}
+
 /// The object used to manage and build a book.
 pub struct MDBook {
     /// The book's root directory.
@@ -34,8 +53,16 @@ pub struct MDBook {
     pub config: Config,
 
     /// A representation of the book's contents in memory.
+    ///
+    /// For a multilingual book, this is the book in the default language.
     pub book: Book,
 
+    /// The front matter of the chapters of [`MDBook::book`], by source path.
+    chapter_metadata: HashMap<PathBuf, ChapterMetadata>,
+
+    /// The other languages of a multilingual book.
+    translations: Vec<Translation>,
+
     /// Renderers to execute.
     renderers: IndexMap<String, Box<dyn Renderer>>,
 
@@ -43,19 +70,71 @@ pub struct MDBook {
     preprocessors: IndexMap<String, Box<dyn Preprocessor>>,
 }
 
+/// A language of a multilingual book other than the default one.
+struct Translation {
+    /// The language code.
+    code: String,
+    book: Book,
+    /// The front matter of the chapters of `book`, by source path.
+    chapter_metadata: HashMap<PathBuf, ChapterMetadata>,
+}
+
 impl MDBook {
     /// Load a book from its root directory on disk.
     pub fn load<P: Into<PathBuf>>(book_root: P) -> Result<MDBook> {
+        MDBook::load_with_options(book_root, &LoadOptions::default())
+    }
+
+    /// Load a book from its root directory on disk with the given options.
+    ///
+    /// See [`MDBook::load_config`] for how the configuration is loaded.
+    pub fn load_with_options<P: Into<PathBuf>>(
+        book_root: P,
+        options: &LoadOptions,
+    ) -> Result<MDBook> {
         let book_root = book_root.into();
+        let config = MDBook::load_config(&book_root, options)?;
+        MDBook::load_with_config(book_root, config)
+    }
+
+    /// Load the configuration of the book in the given root directory.
+    ///
+    /// The keys in `book.toml` are checked against [`LoadOptions::schemas`].
+    /// Unknown keys are ignored with a warning, or are an error if
+    /// [`LoadOptions::strict`] is set.
+    ///
+    /// The profile is applied before the environment variables, so that
+    /// those can still override any setting. See [`Config::apply_profile`]
+    /// for how profiles are merged.
+    pub fn load_config(book_root: &Path, options: &LoadOptions) -> Result<Config> {
         let config_location = book_root.join("book.toml");
 
         let mut config = if config_location.exists() {
             debug!("Loading config from {}", config_location.display());
-            Config::from_disk(&config_location)?
+            let src = fs::read_to_string(&config_location)?;
+            let (config, unknown) = Config::from_str_checked(&src, &options.schemas)?;
+            for key in &unknown {
+                if options.strict {
+                    error!("{key}");
+                } else {
+                    warn!("{key}");
+                }
+            }
+            if options.strict && !unknown.is_empty() {
+                bail!("found {} unknown key(s) in book.toml", unknown.len());
+            }
+            config
         } else {
             Config::default()
         };
 
+        if let Some(profile) = options
+            .profile
+            .clone()
+            .or_else(|| config.build.profile.clone())
+        {
+            config.apply_profile(&profile)?;
+        }
         config.update_from_env()?;
 
         if tracing::enabled!(tracing::Level::TRACE) {
@@ -64,15 +143,29 @@ impl MDBook {
             }
         }
 
-        MDBook::load_with_config(book_root, config)
+        Ok(config)
     }
 
     /// Load a book from its root directory using a custom `Config`.
+    ///
+    /// For a multilingual book, the source of each language is loaded from its
+    /// own directory in the source directory. See [`Config::for_language`].
     pub fn load_with_config<P: Into<PathBuf>>(book_root: P, config: Config) -> Result<MDBook> {
         let root = book_root.into();
 
-        let src_dir = root.join(&config.book.src);
-        let book = load_book(src_dir, &config.build)?;
+        let src = match config.is_multilingual() {
+            true => config.book.src.join(config.default_language()?),
+            false => config.book.src.clone(),
+        };
+        let mut book = load_book(root.join(&src), &config.build).map_err(|mut e| {
+            // Errors in `SUMMARY.md` are relative to the `src` directory.
+            if let Some(diagnostic) = e.downcast_mut::<Diagnostic>() {
+                diagnostic.path = src.join(&diagnostic.path);
+            }
+            e
+        })?;
+        let chapter_metadata = split_front_matter(&mut book)?;
+        let translations = load_translations(&root, &config)?;
 
         let renderers = determine_renderers(&config)?;
         let preprocessors = determine_preprocessors(&config, &root)?;
@@ -81,6 +174,8 @@ impl MDBook {
             root,
             config,
             book,
+            chapter_metadata,
+            translations,
             renderers,
             preprocessors,
         })
@@ -94,8 +189,13 @@ impl MDBook {
     ) -> Result<MDBook> {
         let root = book_root.into();
 
-        let src_dir = root.join(&config.book.src);
-        let book = load_book_from_disk(&summary, src_dir)?;
+        let src_dir = match config.is_multilingual() {
+            true => root.join(&config.book.src).join(config.default_language()?),
+            false => root.join(&config.book.src),
+        };
+        let mut book = load_book_from_disk(&summary, src_dir, &config.build)?;
+        let chapter_metadata = split_front_matter(&mut book)?;
+        let translations = load_translations(&root, &config)?;
 
         let renderers = determine_renderers(&config)?;
         let preprocessors = determine_preprocessors(&config, &root)?;
@@ -104,6 +204,8 @@ impl MDBook {
             root,
             config,
             book,
+            chapter_metadata,
+            translations,
             renderers,
             preprocessors,
         })
@@ -169,13 +271,48 @@ impl MDBook {
     }
 
     /// Run preprocessors and return the final book.
+    ///
+    /// For a multilingual book, this is the book in the default language.
     pub fn preprocess_book(&self, renderer: &dyn Renderer) -> Result<(Book, PreprocessorContext)> {
-        let preprocess_ctx = PreprocessorContext::new(
-            self.root.clone(),
-            self.config.clone(),
-            renderer.name().to_string(),
-        );
-        let mut preprocessed_book = self.book.clone();
+        let config = match self.config.is_multilingual() {
+            true => self.config.for_language(self.config.default_language()?)?,
+            false => self.config.clone(),
+        };
+        self.preprocess(renderer, &self.book, &self.chapter_metadata, config)
+    }
+
+    /// Run preprocessors on the translations of a multilingual book, and
+    /// return the final books by language code.
+    pub fn preprocess_translations(
+        &self,
+        renderer: &dyn Renderer,
+    ) -> Result<Vec<(String, Book, PreprocessorContext)>> {
+        self.translations
+            .iter()
+            .map(|translation| {
+                let config = self.config.for_language(&translation.code)?;
+                let (book, ctx) = self.preprocess(
+                    renderer,
+                    &translation.book,
+                    &translation.chapter_metadata,
+                    config,
+                )?;
+                Ok((translation.code.clone(), book, ctx))
+            })
+            .collect()
+    }
+
+    fn preprocess(
+        &self,
+        renderer: &dyn Renderer,
+        book: &Book,
+        chapter_metadata: &HashMap<PathBuf, ChapterMetadata>,
+        config: Config,
+    ) -> Result<(Book, PreprocessorContext)> {
+        let mut preprocess_ctx =
+            PreprocessorContext::new(self.root.clone(), config, renderer.name().to_string());
+        preprocess_ctx.chapter_metadata = chapter_metadata.clone();
+        let mut preprocessed_book = book.clone();
         for preprocessor in self.preprocessors.values() {
             if preprocessor_should_run(&**preprocessor, renderer, &self.config)? {
                 debug!("Running the {} preprocessor.", preprocessor.name());
@@ -186,21 +323,64 @@ impl MDBook {
     }
 
     /// Run the entire build process for a particular [`Renderer`].
+    ///
+    /// Each language of a multilingual book is built into its own directory
+    /// of [`MDBook::build_dir_for`], named after the language code.
     pub fn execute_build_process(&self, renderer: &dyn Renderer) -> Result<()> {
-        let (preprocessed_book, preprocess_ctx) = self.preprocess_book(renderer)?;
-
         let name = renderer.name();
         let build_dir = self.build_dir_for(name);
 
+        if !self.config.is_multilingual() {
+            let (preprocessed_book, preprocess_ctx) = self.preprocess_book(renderer)?;
+            return self.render(renderer, preprocessed_book, preprocess_ctx, build_dir);
+        }
+
+        let default = self.config.default_language()?;
+        info!("Building the `{default}` language");
+        let (preprocessed_book, preprocess_ctx) = self.preprocess_book(renderer)?;
+        self.render(
+            renderer,
+            preprocessed_book,
+            preprocess_ctx,
+            build_dir.join(default),
+        )?;
+        for translation in &self.translations {
+            let code = &translation.code;
+            info!("Building the `{code}` language");
+            let config = self.config.for_language(code)?;
+            let (preprocessed_book, preprocess_ctx) = self.preprocess(
+                renderer,
+                &translation.book,
+                &translation.chapter_metadata,
+                config,
+            )?;
+            self.render(
+                renderer,
+                preprocessed_book,
+                preprocess_ctx,
+                build_dir.join(code),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn render(
+        &self,
+        renderer: &dyn Renderer,
+        preprocessed_book: Book,
+        preprocess_ctx: PreprocessorContext,
+        build_dir: PathBuf,
+    ) -> Result<()> {
         let mut render_context = RenderContext::new(
             self.root.clone(),
             preprocessed_book,
-            self.config.clone(),
+            preprocess_ctx.config.clone(),
             build_dir,
         );
         render_context
             .chapter_titles
             .extend(preprocess_ctx.chapter_titles.borrow_mut().drain());
+        render_context.chapter_metadata = preprocess_ctx.chapter_metadata;
 
         info!("Running the {} backend", renderer.name());
         renderer
@@ -398,6 +578,33 @@ struct OutputConfig {
     command: Option<String>,
 }
 
+/// Loads the translations of a multilingual book, which are all of its
+/// languages except the default one.
+fn load_translations(root: &Path, config: &Config) -> Result<Vec<Translation>> {
+    if !config.is_multilingual() {
+        return Ok(Vec::new());
+    }
+    let default = config.default_language()?;
+    let src_dir = root.join(&config.book.src);
+    config
+        .language
+        .keys()
+        .filter(|code| *code != default)
+        .map(|code| {
+            let mut book =
+                load_translation(&src_dir.join(code), &src_dir.join(default), &config.build)
+                    .with_context(|| format!("failed to load the `{code}` language"))?;
+            let chapter_metadata = split_front_matter(&mut book)
+                .with_context(|| format!("failed to load the `{code}` language"))?;
+            Ok(Translation {
+                code: code.clone(),
+                book,
+                chapter_metadata,
+            })
+        })
+        .collect()
+}
+
 /// Look at the `Config` and try to figure out what renderers to use.
 fn determine_renderers(config: &Config) -> Result<IndexMap<String, Box<dyn Renderer>>> {
     let mut renderers = IndexMap::new();
@@ -408,6 +615,8 @@ fn determine_renderers(config: &Config) -> Result<IndexMap<String, Box<dyn Rende
             Box::new(HtmlHandlebars::new()) as Box<dyn Renderer>
         } else if key == "markdown" {
             Box::new(MarkdownRenderer::new()) as Box<dyn Renderer>
+        } else if key == "epub" && table.command.is_none() {
+            Box::new(EpubRenderer::new()) as Box<dyn Renderer>
         } else {
             let command = table.command.unwrap_or_else(|| format!("mdbook-{key}"));
             Box::new(CmdRenderer::new(key.clone(), command))
@@ -425,6 +634,16 @@ fn determine_renderers(config: &Config) -> Result<IndexMap<String, Box<dyn Rende
 
 const DEFAULT_PREPROCESSORS: &[&str] = &["links", "index"];
 
+/// Built-in preprocessors that run first, in this order.
+///
+/// Conditional blocks are removed first, so that nothing else is done to the
+/// text inside them. Variables are replaced before `links` unescapes
+/// `\{{#var}}`, and so that they can be used in include paths.
+///
+/// This doesn't add a dependency on them, which would move `links` after all
+/// of the preprocessors without dependencies.
+const EARLY_PREPROCESSORS: &[&str] = &["conditional", "vars"];
+
 fn is_default_preprocessor(pre: &dyn Preprocessor) -> bool {
     let name = pre.name();
     name == LinkPreprocessor::NAME || name == IndexPreprocessor::NAME
@@ -458,15 +677,25 @@ fn determine_preprocessors(
     }
 
     let preprocessor_table = config.preprocessors::<PreprocessorConfig>()?;
+    let exists = |name: &str| {
+        (config.build.use_default_preprocessors && DEFAULT_PREPROCESSORS.contains(&name))
+            || preprocessor_table.contains_key(name)
+    };
+
+    // Cross-references are resolved after includes are expanded and
+    // `README.md` files are renamed, so that they point to the final
+    // chapters.
+    if preprocessor_table.contains_key(XrefPreprocessor::NAME) {
+        for name in [LinkPreprocessor::NAME, IndexPreprocessor::NAME] {
+            if exists(name) {
+                preprocessor_names.add_dependency(name, XrefPreprocessor::NAME);
+            }
+        }
+    }
 
     for (name, table) in preprocessor_table.iter() {
         preprocessor_names.insert(name.to_string());
 
-        let exists = |name| {
-            (config.build.use_default_preprocessors && DEFAULT_PREPROCESSORS.contains(&name))
-                || preprocessor_table.contains_key(name)
-        };
-
         for after in &table.before {
             if !exists(&after) {
                 // Only warn so that preprocessors can be toggled on and off (e.g. for
@@ -507,10 +736,21 @@ fn determine_preprocessors(
         // preprocessor execution order.
         // [1]: https://doc.rust-lang.org/stable/std/cmp/trait.Ord.html#impl-Ord-14
         names.sort();
+        // Some built-in preprocessors go before anything else in the same
+        // round. See `EARLY_PREPROCESSORS` for why.
+        names.sort_by_key(|name| {
+            EARLY_PREPROCESSORS
+                .iter()
+                .position(|early| early == name)
+                .unwrap_or(EARLY_PREPROCESSORS.len())
+        });
         for name in names {
             let preprocessor: Box<dyn Preprocessor> = match name.as_str() {
                 "links" => Box::new(LinkPreprocessor::new()),
                 "index" => Box::new(IndexPreprocessor::new()),
+                "conditional" => Box::new(ConditionalPreprocessor::new()),
+                "vars" => Box::new(VarsPreprocessor::new()),
+                "xref" => Box::new(XrefPreprocessor::new()),
                 _ => {
                     // The only way to request a custom preprocessor is through the `preprocessor`
                     // table, so it must exist, be a table, and contain the key.
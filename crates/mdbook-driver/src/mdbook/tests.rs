@@ -267,6 +267,46 @@ fn preprocessor_sorted_by_name() {
     assert_eq!(names, ["abc", "index", "links", "xyz"]);
 }
 
+// The `xref` preprocessor only runs if it is configured, after links are
+// expanded and `README.md` files are renamed.
+#[test]
+fn xref_runs_after_links_and_index() {
+    let cfg_str = r#"
+        [preprocessor.abc]
+        [preprocessor.xref]
+        "#;
+
+    let cfg = Config::from_str(cfg_str).unwrap();
+
+    let got = determine_preprocessors(&cfg, Path::new("")).unwrap();
+
+    let names: Vec<_> = got.values().map(|p| p.name()).collect();
+    assert_eq!(names, ["abc", "index", "links", "xref"]);
+}
+
+// Conditional blocks and variables are handled first, before links are
+// expanded.
+#[test]
+fn early_preprocessors_run_before_links() {
+    let cfg_str = r#"
+        [preprocessor.xyz]
+        [preprocessor.conditional]
+        [preprocessor.vars]
+        [preprocessor.first]
+        before = ["links"]
+        "#;
+
+    let cfg = Config::from_str(cfg_str).unwrap();
+
+    let got = determine_preprocessors(&cfg, Path::new("")).unwrap();
+
+    let names: Vec<_> = got.values().map(|p| p.name()).collect();
+    assert_eq!(
+        names,
+        ["conditional", "vars", "first", "index", "xyz", "links"]
+    );
+}
+
 // Default is to sort renderers alphabetically.
 #[test]
 fn renderers_sorted_by_name() {
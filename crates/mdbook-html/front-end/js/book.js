@@ -19,6 +19,45 @@ function playground_text(playground, hidden = true) {
     }
 }
 
+// The English strings of the user interface, used if the page doesn't have
+// them, such as with a custom `index.hbs` without `#mdbook-ui-strings`.
+// These must be the same as in `strings/en.toml`.
+const mdbook_default_ui_strings = {
+    'running': 'Running...',
+    'no-output': 'No output',
+    'playground-error': 'Playground Communication: {error}',
+    'show-hidden-lines': 'Show hidden lines',
+    'hide-lines': 'Hide lines',
+    'copy-to-clipboard': 'Copy to clipboard',
+    'copied': 'Copied!',
+    'clipboard-error': 'Clipboard error!',
+    'run-code': 'Run this code',
+    'undo-changes': 'Undo changes',
+    'search-result': '{count} search result for \'{term}\':',
+    'search-results': '{count} search results for \'{term}\':',
+    'no-search-results': 'No search results for \'{term}\'.',
+    'search-result-teaser': 'Search Result Teaser',
+};
+
+/**
+ * Returns a string of the user interface in the language of the book.
+ *
+ * The strings are in the `data-` attributes of `#mdbook-ui-strings` in the
+ * page, falling back to the English ones. The `{name}` placeholders of the
+ * string are replaced with `values`.
+ */
+function mdbook_ui_string(name, values = {}) {
+    const strings = document.getElementById('mdbook-ui-strings');
+    let string = strings ? strings.getAttribute('data-' + name) : null;
+    if (string === null) {
+        string = mdbook_default_ui_strings[name] || name;
+    }
+    for (const [key, value] of Object.entries(values)) {
+        string = string.replace('{' + key + '}', () => value);
+    }
+    return string;
+}
+
 /**
  * Helper for global keypress handlers so they don't trigger when certain elements are active.
  * @returns {boolean} True if the keypress handler should be skipped.
@@ -145,7 +184,7 @@ function mdbook_something_else_has_focus(e) {
             params.version = 'nightly';
         }
 
-        result_block.innerText = 'Running...';
+        result_block.innerText = mdbook_ui_string('running');
 
         fetch_with_timeout('https://play.rust-lang.org/evaluate.json', {
             headers: {
@@ -158,21 +197,33 @@ function mdbook_something_else_has_focus(e) {
             .then(response => response.json())
             .then(response => {
                 if (response.result.trim() === '') {
-                    result_block.innerText = 'No output';
+                    result_block.innerText = mdbook_ui_string('no-output');
                     result_block.classList.add('result-no-output');
                 } else {
                     result_block.innerText = response.result;
                     result_block.classList.remove('result-no-output');
                 }
             })
-            .catch(error => result_block.innerText = 'Playground Communication: ' + error.message);
+            .catch(error => result_block.innerText = mdbook_ui_string('playground-error', {
+                error: error.message,
+            }));
     }
 
     // Syntax highlighting Configuration
-    hljs.configure({
-        tabReplace: '    ', // 4 spaces
-        languages: [], // Languages used for auto-detection
-    });
+    // highlight.js is not loaded when code is highlighted when the book is built.
+    if (window.hljs) {
+        hljs.configure({
+            tabReplace: '    ', // 4 spaces
+            languages: [], // Languages used for auto-detection
+        });
+    }
+
+    function highlightBlock(block) {
+        // Blocks highlighted when the book was built already have the hljs class.
+        if (window.hljs && !block.classList.contains('hljs')) {
+            hljs.highlightBlock(block);
+        }
+    }
 
     const code_nodes = Array
         .from(document.querySelectorAll('code'))
@@ -196,13 +247,9 @@ function mdbook_something_else_has_focus(e) {
             .filter(function(node) {
                 return !node.classList.contains('editable');
             })
-            .forEach(function(block) {
-                hljs.highlightBlock(block);
-            });
+            .forEach(highlightBlock);
     } else {
-        code_nodes.forEach(function(block) {
-            hljs.highlightBlock(block);
-        });
+        code_nodes.forEach(highlightBlock);
     }
 
     // Adding the hljs class gives code blocks the color css
@@ -222,8 +269,9 @@ function mdbook_something_else_has_focus(e) {
 
         const buttons = document.createElement('div');
         buttons.className = 'buttons';
-        buttons.innerHTML = '<button title="Show hidden lines" \
-aria-label="Show hidden lines"></button>';
+        buttons.innerHTML = '<button></button>';
+        buttons.firstChild.title = mdbook_ui_string('show-hidden-lines');
+        buttons.firstChild.setAttribute('aria-label', buttons.firstChild.title);
         buttons.firstChild.innerHTML = document.getElementById('fa-eye').innerHTML;
 
         // add expand button
@@ -231,15 +279,15 @@ aria-label="Show hidden lines"></button>';
         pre_block.insertBefore(buttons, pre_block.firstChild);
 
         buttons.firstChild.addEventListener('click', function(e) {
-            if (this.title === 'Show hidden lines') {
+            if (block.classList.contains('hide-boring')) {
                 this.innerHTML = document.getElementById('fa-eye-slash').innerHTML;
-                this.title = 'Hide lines';
+                this.title = mdbook_ui_string('hide-lines');
                 this.setAttribute('aria-label', e.target.title);
 
                 block.classList.remove('hide-boring');
-            } else if (this.title === 'Hide lines') {
+            } else {
                 this.innerHTML = document.getElementById('fa-eye').innerHTML;
-                this.title = 'Show hidden lines';
+                this.title = mdbook_ui_string('show-hidden-lines');
                 this.setAttribute('aria-label', e.target.title);
 
                 block.classList.add('hide-boring');
@@ -260,7 +308,7 @@ aria-label="Show hidden lines"></button>';
 
                 const clipButton = document.createElement('button');
                 clipButton.className = 'clip-button';
-                clipButton.title = 'Copy to clipboard';
+                clipButton.title = mdbook_ui_string('copy-to-clipboard');
                 clipButton.setAttribute('aria-label', clipButton.title);
                 clipButton.innerHTML = '<i class="tooltiptext"></i>';
 
@@ -282,7 +330,7 @@ aria-label="Show hidden lines"></button>';
         const runCodeButton = document.createElement('button');
         runCodeButton.className = 'play-button';
         runCodeButton.hidden = true;
-        runCodeButton.title = 'Run this code';
+        runCodeButton.title = mdbook_ui_string('run-code');
         runCodeButton.setAttribute('aria-label', runCodeButton.title);
         runCodeButton.innerHTML = document.getElementById('fa-play').innerHTML;
 
@@ -295,7 +343,7 @@ aria-label="Show hidden lines"></button>';
             const copyCodeClipboardButton = document.createElement('button');
             copyCodeClipboardButton.className = 'clip-button';
             copyCodeClipboardButton.innerHTML = '<i class="tooltiptext"></i>';
-            copyCodeClipboardButton.title = 'Copy to clipboard';
+            copyCodeClipboardButton.title = mdbook_ui_string('copy-to-clipboard');
             copyCodeClipboardButton.setAttribute('aria-label', copyCodeClipboardButton.title);
 
             buttons.insertBefore(copyCodeClipboardButton, buttons.firstChild);
@@ -305,7 +353,7 @@ aria-label="Show hidden lines"></button>';
         if (window.ace && code_block.classList.contains('editable')) {
             const undoChangesButton = document.createElement('button');
             undoChangesButton.className = 'reset-button';
-            undoChangesButton.title = 'Undo changes';
+            undoChangesButton.title = mdbook_ui_string('undo-changes');
             undoChangesButton.setAttribute('aria-label', undoChangesButton.title);
             undoChangesButton.innerHTML +=
                 document.getElementById('fa-clock-rotate-left').innerHTML;
@@ -533,6 +581,85 @@ aria-label="Show hidden lines"></button>';
     });
 })();
 
+(function languages() {
+    const languageToggleButton = document.getElementById('mdbook-language-toggle');
+    const languagePopup = document.getElementById('mdbook-language-list');
+    if (!languageToggleButton || !languagePopup) {
+        return;
+    }
+
+    function showLanguages() {
+        languagePopup.style.display = 'block';
+        languageToggleButton.setAttribute('aria-expanded', true);
+        const selected = languagePopup.querySelector('a.language-selected') ??
+            languagePopup.querySelector('a.language');
+        selected.focus();
+    }
+
+    function hideLanguages() {
+        languagePopup.style.display = 'none';
+        languageToggleButton.setAttribute('aria-expanded', false);
+        languageToggleButton.focus();
+    }
+
+    languageToggleButton.addEventListener('click', function() {
+        if (languagePopup.style.display === 'block') {
+            hideLanguages();
+        } else {
+            showLanguages();
+        }
+    });
+
+    languagePopup.addEventListener('focusout', function(e) {
+        if (!!e.relatedTarget &&
+            !languageToggleButton.contains(e.relatedTarget) &&
+            !languagePopup.contains(e.relatedTarget)
+        ) {
+            hideLanguages();
+        }
+    });
+
+    document.addEventListener('click', function(e) {
+        if (languagePopup.style.display === 'block' &&
+            !languageToggleButton.contains(e.target) &&
+            !languagePopup.contains(e.target)
+        ) {
+            hideLanguages();
+        }
+    });
+
+    document.addEventListener('keydown', function(e) {
+        if (e.altKey || e.ctrlKey || e.metaKey || e.shiftKey) {
+            return;
+        }
+        if (!languagePopup.contains(e.target)) {
+            return;
+        }
+
+        let li;
+        switch (e.key) {
+        case 'Escape':
+            e.preventDefault();
+            hideLanguages();
+            break;
+        case 'ArrowUp':
+            e.preventDefault();
+            li = document.activeElement.parentElement;
+            if (li && li.previousElementSibling) {
+                li.previousElementSibling.querySelector('a').focus();
+            }
+            break;
+        case 'ArrowDown':
+            e.preventDefault();
+            li = document.activeElement.parentElement;
+            if (li && li.nextElementSibling) {
+                li.nextElementSibling.querySelector('a').focus();
+            }
+            break;
+        }
+    });
+})();
+
 (function sidebar() {
     const sidebar = document.getElementById('mdbook-sidebar');
     const sidebarLinks = document.querySelectorAll('#mdbook-sidebar a');
@@ -794,11 +921,11 @@ aria-label="Show hidden lines"></button>';
 
     clipboardSnippets.on('success', function(e) {
         e.clearSelection();
-        showTooltip(e.trigger, 'Copied!');
+        showTooltip(e.trigger, mdbook_ui_string('copied'));
     });
 
     clipboardSnippets.on('error', function(e) {
-        showTooltip(e.trigger, 'Clipboard error!');
+        showTooltip(e.trigger, mdbook_ui_string('clipboard-error'));
     });
 })();
 
@@ -1,6 +1,6 @@
 'use strict';
 
-/* global Mark, elasticlunr, path_to_root */
+/* global Mark, elasticlunr, path_to_root, mdbook_ui_string */
 
 window.search = window.search || {};
 (function search() {
@@ -48,12 +48,19 @@ window.search = window.search || {};
             },
         },
         searchindex = null,
+        search_language = null,
+        search_shards = null,
+        shard_urls = {},
+        doc_boosts = {},
         results_options = {
             teaser_word_count: 30,
             limit_results: 30,
         },
         teaser_count = 0;
 
+    const loaded_shards = {},
+        shard_callbacks = {};
+
     function hasFocus() {
         return searchbar === document.activeElement;
     }
@@ -129,12 +136,13 @@ window.search = window.search || {};
     })();
 
     function formatSearchMetric(count, searchterm) {
+        const values = {count: count, term: searchterm};
         if (count === 1) {
-            return count + ' search result for \'' + searchterm + '\':';
+            return mdbook_ui_string('search-result', values);
         } else if (count === 0) {
-            return 'No search results for \'' + searchterm + '\'.';
+            return mdbook_ui_string('no-search-results', values);
         } else {
-            return count + ' search results for \'' + searchterm + '\':';
+            return mdbook_ui_string('search-results', values);
         }
     }
 
@@ -157,7 +165,8 @@ window.search = window.search || {};
             + '#' + url[1] + '" aria-details="mdbook-teaser_' + teaser_count + '">'
             + result.doc.breadcrumbs + '</a>'
             + '<span class="teaser" id="mdbook-teaser_' + teaser_count
-            + '" aria-label="Search Result Teaser">' + teaser + '</span>';
+            + '" aria-label="' + escapeHTML(mdbook_ui_string('search-result-teaser')) + '">'
+            + teaser + '</span>';
     }
 
     function makeTeaser(body, searchterms) {
@@ -171,7 +180,7 @@ window.search = window.search || {};
         // maximum sum. If there are multiple maximas, then get the last one.
         // Enclose the terms in <em>.
         const stemmed_searchterms = searchterms.map(function(w) {
-            return elasticlunr.stemmer(w.toLowerCase());
+            return normalizeWord(w.toLowerCase());
         });
         const searchterm_weight = 40;
         const weighted = []; // contains elements of ["word", weight, index_in_document]
@@ -187,7 +196,7 @@ window.search = window.search || {};
                 const word = words[wordindex];
                 if (word.length > 0) {
                     for (const searchtermindex in stemmed_searchterms) {
-                        if (elasticlunr.stemmer(word).startsWith(
+                        if (wordMatches(normalizeWord(word),
                             stemmed_searchterms[searchtermindex])
                         ) {
                             value = searchterm_weight;
@@ -258,10 +267,157 @@ window.search = window.search || {};
         return teaser_split.join('');
     }
 
+    // Normalizes a word of a teaser in the same way as the search index.
+    function normalizeWord(word) {
+        return searchindex.pipeline.run([word])[0] || word;
+    }
+
+    // Checks whether a normalized word of a teaser matches a search term.
+    function wordMatches(word, term) {
+        if (search_language && search_language.tokenizer === 'cjk') {
+            // Words are not separated by spaces, so the term can be anywhere.
+            return word.includes(term);
+        }
+        return word.startsWith(term);
+    }
+
+    // Splits text like the index does for Chinese, Japanese and Korean: runs
+    // of these characters become overlapping pairs of characters.
+    function cjkTokenizer(text) {
+        if (text === null || text === undefined) {
+            return [];
+        }
+        const runs = new RegExp(
+            '(?<cjk>[\\p{sc=Han}\\p{sc=Hiragana}\\p{sc=Katakana}\\p{sc=Hangul}ー]+)'
+            + '|[^\\p{sc=Han}\\p{sc=Hiragana}\\p{sc=Katakana}\\p{sc=Hangul}ー]+', 'gu');
+        const tokens = [];
+        for (const word of text.toString().toLowerCase().split(/[\s-]+/)) {
+            for (const run of word.matchAll(runs)) {
+                const chars = Array.from(run[0]);
+                if (run.groups.cjk === undefined || chars.length === 1) {
+                    tokens.push(run[0]);
+                } else {
+                    for (let i = 0; i < chars.length - 1; i++) {
+                        tokens.push(chars[i] + chars[i + 1]);
+                    }
+                }
+            }
+        }
+        return tokens;
+    }
+
+    // Registers the pipeline functions of the search index for languages
+    // other than English, which are defined in `search/language.rs`.
+    function registerLanguage(language) {
+        const trimmer = /^[^\p{Alphabetic}\p{N}_]+|[^\p{Alphabetic}\p{N}_]+$/gu;
+        elasticlunr.Pipeline.registerFunction(token => {
+            return token.replace(trimmer, '') || undefined;
+        }, 'mdbook-trimmer');
+
+        const stop_words = new Set(language.stop_words);
+        elasticlunr.Pipeline.registerFunction(token => {
+            return stop_words.has(token) ? undefined : token;
+        }, 'mdbook-stop-words');
+
+        const stemmer = language.stemmer;
+        const fold = new Map(stemmer && stemmer.rules ? stemmer.rules.fold : []);
+        elasticlunr.Pipeline.registerFunction(token => {
+            if (stemmer === 'english') {
+                // eslint-disable-next-line no-control-regex
+                return /^[\x00-\x7f]*$/.test(token) ? elasticlunr.stemmer(token) : token;
+            }
+            // Fold the accents, then apply the first matching rule of each step.
+            let word = Array.from(token, c => fold.has(c) ? fold.get(c) : c).join('');
+            for (const rules of stemmer.rules.steps) {
+                for (const [suffix, replacement, min] of rules) {
+                    const stem = word.slice(0, word.length - suffix.length);
+                    if (word.endsWith(suffix) && Array.from(stem).length >= min) {
+                        word = stem + replacement;
+                        break;
+                    }
+                }
+            }
+            return word;
+        }, 'mdbook-stemmer');
+
+        if (language.tokenizer === 'cjk') {
+            elasticlunr.tokenizer = cjkTokenizer;
+        }
+    }
+
+    // Registers the pipeline function which replaces synonyms with the first
+    // word of their group, see `search/synonyms.rs`.
+    function registerSynonyms(synonyms) {
+        elasticlunr.Pipeline.registerFunction(token => {
+            return Object.hasOwn(synonyms, token) ? synonyms[token] : token;
+        }, 'mdbook-synonyms');
+    }
+
+    // Multiplies the scores of the results in chapters with a `boost`, and
+    // sorts them again.
+    function boostResults(results) {
+        for (const result of results) {
+            if (Object.hasOwn(doc_boosts, result.ref)) {
+                result.score *= doc_boosts[result.ref];
+            }
+        }
+        return results.sort((a, b) => b.score - a.score || a.ref - b.ref);
+    }
+
+    // Loads the shards of a sharded search index with the given names, and
+    // adds them to the index.
+    function loadShards(names) {
+        return Promise.all(Array.from(new Set(names), name => {
+            if (!(name in loaded_shards)) {
+                loaded_shards[name] = new Promise((resolve, reject) => {
+                    shard_callbacks[name] = resolve;
+                    const script = document.createElement('script');
+                    script.src = path_to_root + shard_urls[name];
+                    script.onerror = () => {
+                        delete loaded_shards[name];
+                        reject(new Error(`Failed to load \`${script.src}\``));
+                    };
+                    document.head.append(script);
+                }).then(decodeShard).then(shard => {
+                    if (name.startsWith('t')) {
+                        for (const field in shard) {
+                            Object.assign(searchindex.index[field].root, shard[field]);
+                        }
+                    } else {
+                        Object.assign(searchindex.documentStore.docs, shard);
+                    }
+                });
+            }
+            return loaded_shards[name];
+        }));
+    }
+
+    // Decodes the base64 of the gzip-compressed JSON of a shard.
+    function decodeShard(data) {
+        const bytes = Uint8Array.from(atob(data), c => c.charCodeAt(0));
+        const json = new Blob([bytes]).stream().pipeThrough(new DecompressionStream('gzip'));
+        return new Response(json).json();
+    }
+
+    // Called by the scripts of the shards, see `search/shards.rs`.
+    window.search.addShard = (name, data) => {
+        shard_callbacks[name](data);
+    };
+
     function init(config) {
         results_options = config.results_options;
         search_options = config.search_options;
         doc_urls = config.doc_urls;
+        search_language = config.language;
+        search_shards = config.shards || null;
+        shard_urls = config.shard_urls || {};
+        doc_boosts = config.doc_boosts || {};
+        if (search_language) {
+            registerLanguage(search_language);
+        }
+        if (config.synonyms) {
+            registerSynonyms(config.synonyms);
+        }
         searchindex = elasticlunr.Index.load(config.index);
 
         searchbar_outer.classList.remove('searching');
@@ -532,8 +688,43 @@ window.search = window.search || {};
 
         current_searchterm = searchterm;
 
-        // Do the actual search
-        const results = searchindex.search(searchterm, search_options);
+        if (search_shards === null) {
+            showSearchResults(
+                searchterm, boostResults(searchindex.search(searchterm, search_options)));
+            return;
+        }
+
+        // Load the shards with the search terms, and then the shards with the
+        // documents of the results. Characters that aren't in the map don't
+        // start any of the terms of the index.
+        const tokens = searchindex.pipeline.run(elasticlunr.tokenizer(searchterm));
+        const term_shards = tokens
+            .map(token => search_shards.terms[Array.from(token)[0]])
+            .filter(shard => shard !== undefined);
+        loadShards(term_shards.map(shard => 't' + shard))
+            .then(() => {
+                const results = boostResults(searchindex.search(searchterm, search_options))
+                    .slice(0, results_options.limit_results);
+                return loadShards(results.map(result => {
+                    return 'd' + Math.floor(result.ref / search_shards.docs_per_shard);
+                })).then(() => results);
+            })
+            .then(results => {
+                // Skip the results if the search term changed in the meantime.
+                if (current_searchterm !== searchterm) {
+                    return;
+                }
+                for (const result of results) {
+                    result.doc = searchindex.documentStore.getDoc(result.ref);
+                }
+                showSearchResults(searchterm, results);
+            })
+            .catch(error => {
+                console.error(`Failed to search for \`${searchterm}\`: ${error}`);
+            });
+    }
+
+    function showSearchResults(searchterm, results) {
         const resultcount = Math.min(results.length, results_options.limit_results);
 
         // Display search metrics
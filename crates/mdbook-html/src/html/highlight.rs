@@ -0,0 +1,220 @@
+//! Build-time syntax highlighting of code blocks.
+//!
+//! The code is tokenized with [`syntect`], and the TextMate scopes of each
+//! token are mapped to the class names that highlight.js uses. That way the
+//! highlighting themes (`highlight.css`, `tomorrow-night.css`, and
+//! `ayu-highlight.css`) work the same with either highlighter.
+
+use crate::html::{Element, Node};
+use ego_tree::{NodeId, Tree};
+use std::sync::LazyLock;
+use syntect::easy::ScopeRegionIterator;
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxSet};
+use tracing::warn;
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Map of scope prefixes to the highlight.js classes.
+///
+/// The scopes of a token are checked from the innermost outwards, and the
+/// first prefix that matches determines the class. More specific prefixes
+/// need to come before less specific ones. A class of `None` means the token
+/// is not highlighted.
+static SCOPE_CLASSES: LazyLock<Vec<(Scope, Option<&'static str>)>> = LazyLock::new(|| {
+    [
+        ("comment", Some("hljs-comment")),
+        ("string.regexp", Some("hljs-regexp")),
+        ("string", Some("hljs-string")),
+        ("constant.character", Some("hljs-string")),
+        ("constant.numeric", Some("hljs-number")),
+        ("constant.language", Some("hljs-literal")),
+        ("constant.other.symbol", Some("hljs-symbol")),
+        ("keyword.operator", None),
+        ("keyword", Some("hljs-keyword")),
+        ("storage", Some("hljs-keyword")),
+        ("variable.language", Some("hljs-keyword")),
+        ("variable.parameter", Some("hljs-params")),
+        ("variable", None),
+        ("entity.name.function", Some("hljs-title function_")),
+        ("entity.name.class", Some("hljs-title class_")),
+        ("entity.name.struct", Some("hljs-title class_")),
+        ("entity.name.enum", Some("hljs-title class_")),
+        ("entity.name.trait", Some("hljs-title class_")),
+        ("entity.name.interface", Some("hljs-title class_")),
+        ("entity.name.type", Some("hljs-type")),
+        ("entity.name.tag", Some("hljs-name")),
+        ("entity.name.section", Some("hljs-section")),
+        ("entity.name", Some("hljs-title")),
+        ("entity.other.attribute-name", Some("hljs-attr")),
+        ("entity.other.inherited-class", Some("hljs-title class_")),
+        ("support.function", Some("hljs-built_in")),
+        ("support.macro", Some("hljs-built_in")),
+        ("support.constant", Some("hljs-built_in")),
+        ("support.type", Some("hljs-type")),
+        ("support.class", Some("hljs-type")),
+        ("meta.annotation", Some("hljs-meta")),
+        ("meta.attribute", Some("hljs-meta")),
+        ("meta.preprocessor", Some("hljs-meta")),
+        ("markup.heading", Some("hljs-section")),
+        ("markup.bold", Some("hljs-strong")),
+        ("markup.italic", Some("hljs-emphasis")),
+        ("markup.quote", Some("hljs-quote")),
+        ("markup.inserted", Some("hljs-addition")),
+        ("markup.deleted", Some("hljs-deletion")),
+        ("markup.underline.link", Some("hljs-link")),
+        ("markup.list", Some("hljs-bullet")),
+    ]
+    .into_iter()
+    .map(|(scope, class)| (Scope::new(scope).unwrap(), class))
+    .collect()
+});
+
+/// Highlights the text of the given `<code>` element.
+///
+/// The `hljs` class is added so that the colors of the theme apply. Lines
+/// that were wrapped in a `<span class="boring">` by
+/// [`hide_lines`](super::hide_lines) keep their span, with the highlighted
+/// tokens inside of it. Code blocks in a language that isn't supported are
+/// left as-is.
+pub(crate) fn highlight_code(tree: &mut Tree<Node>, code_id: NodeId) {
+    let mut node = tree.get_mut(code_id).unwrap();
+    let el = node.value().as_element_mut().unwrap();
+    let class = el.attr("class").unwrap_or_default().to_string();
+    let language = class
+        .split(' ')
+        .find_map(|cls| cls.strip_prefix("language-"))
+        .unwrap_or_default();
+    let new_class = if class.is_empty() {
+        "hljs".to_string()
+    } else {
+        format!("{class} hljs")
+    };
+    el.insert_attr("class", new_class.into());
+
+    let Some(syntax) = SYNTAX_SET.find_syntax_by_token(language) else {
+        return;
+    };
+    let mut highlighter = Highlighter {
+        state: ParseState::new(syntax),
+        stack: ScopeStack::new(),
+        failed: false,
+    };
+
+    let node = tree.get(code_id).unwrap();
+    let mut fragment = Tree::new(Node::Fragment);
+    for child in node.children() {
+        match child.value() {
+            Node::Text(text) => {
+                highlighter.highlight(text, fragment.root_mut().id(), &mut fragment)
+            }
+            Node::Element(el) => {
+                let span = fragment.root_mut().append(Node::Element(el.clone())).id();
+                for grandchild in child.children() {
+                    let Node::Text(text) = grandchild.value() else {
+                        return;
+                    };
+                    highlighter.highlight(text, span, &mut fragment);
+                }
+            }
+            _ => return,
+        }
+    }
+    if highlighter.failed {
+        return;
+    }
+
+    let mut node = tree.get_mut(code_id).unwrap();
+    while let Some(mut child) = node.first_child() {
+        child.detach();
+    }
+    let root_id = tree.extend_tree(fragment).id();
+    tree.get_mut(code_id)
+        .unwrap()
+        .reparent_from_id_append(root_id);
+}
+
+/// The state of highlighting a code block.
+struct Highlighter {
+    state: ParseState,
+    stack: ScopeStack,
+    /// Set if there was an error, in which case the code block is left as-is.
+    failed: bool,
+}
+
+impl Highlighter {
+    /// Highlights the given text, and appends the tokens to `parent`.
+    fn highlight(&mut self, text: &str, parent: NodeId, tree: &mut Tree<Node>) {
+        if self.failed {
+            return;
+        }
+        let mut tokens: Vec<(Option<&'static str>, String)> = Vec::new();
+        for line in text.split_inclusive('\n') {
+            // The syntax definitions expect every line to end with a newline.
+            let with_newline;
+            let parse_line = if line.ends_with('\n') {
+                line
+            } else {
+                with_newline = format!("{line}\n");
+                &with_newline
+            };
+            let ops = match self.state.parse_line(parse_line, &SYNTAX_SET) {
+                Ok(ops) => ops,
+                Err(e) => {
+                    warn!("failed to highlight code block: {e}");
+                    self.failed = true;
+                    return;
+                }
+            };
+            let mut pos = 0;
+            for (region, op) in ScopeRegionIterator::new(&ops, parse_line) {
+                // The op applies to the region that follows it.
+                if let Err(e) = self.stack.apply(op) {
+                    warn!("failed to highlight code block: {e}");
+                    self.failed = true;
+                    return;
+                }
+                // Skip the newline that was added above.
+                let start = pos.min(line.len());
+                pos += region.len();
+                let region = &line[start..pos.min(line.len())];
+                if !region.is_empty() {
+                    let class = self.class();
+                    match tokens.last_mut() {
+                        Some((last_class, last_text)) if *last_class == class => {
+                            last_text.push_str(region)
+                        }
+                        _ => tokens.push((class, region.to_string())),
+                    }
+                }
+            }
+        }
+
+        let mut parent = tree.get_mut(parent).unwrap();
+        for (class, text) in tokens {
+            match class {
+                Some(class) => {
+                    let mut span = Element::new("span");
+                    span.insert_attr("class", class.into());
+                    parent
+                        .append(Node::Element(span))
+                        .append(Node::Text(text.into()));
+                }
+                None => {
+                    parent.append(Node::Text(text.into()));
+                }
+            }
+        }
+    }
+
+    /// The highlight.js class for the current scope.
+    fn class(&self) -> Option<&'static str> {
+        for scope in self.stack.as_slice().iter().rev() {
+            for (prefix, class) in SCOPE_CLASSES.iter() {
+                if prefix.is_prefix_of(*scope) {
+                    return *class;
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,358 @@
+//! Checking of internal links.
+//!
+//! Every link in the chapter trees (after being adjusted by [`fix_link`]) is
+//! checked against the pages that the HTML renderer generates, and the `id`s
+//! of the elements on those pages, such as the ones added to headings.
+
+use super::print::print_page_ids;
+use super::tree::fix_link;
+use super::{ChapterTree, HtmlRenderOptions, Node};
+use crate::utils::{ToUrlPath, normalize_path};
+use mdbook_core::config::{Config, HtmlConfig};
+use mdbook_core::diagnostic::{Diagnostic, Severity};
+use mdbook_core::static_regex;
+use mdbook_markdown::new_cmark_parser;
+use pulldown_cmark::{Event, LinkType, Tag};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A link to a page or an anchor that does not exist.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BrokenLink {
+    /// The file containing the link, relative to the root of the book.
+    pub path: PathBuf,
+    /// The line of the link in the file, if known.
+    pub line: Option<usize>,
+    /// The destination of the link, as it appears in the HTML output.
+    pub href: String,
+    /// A description of why the link is broken.
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": broken link `{}`: {}", self.href, self.reason)
+    }
+}
+
+impl BrokenLink {
+    /// Creates a [`Diagnostic`] pointing at the link, reading the file from
+    /// `root`, the root of the book.
+    ///
+    /// The whole line of the link is marked, since the destination in the
+    /// source may differ from [`BrokenLink::href`]. Without a line, the
+    /// first quoted occurrence of the destination is marked instead, such as
+    /// the value of a redirect in `book.toml`.
+    pub fn to_diagnostic(&self, root: &Path, severity: Severity) -> Diagnostic {
+        let source = std::fs::read_to_string(root.join(&self.path)).unwrap_or_default();
+        let span = match self.line {
+            Some(line) => line_span(&source, line),
+            None => source
+                .find(&format!("\"{}\"", self.href))
+                .map_or(0..0, |start| start + 1..start + 1 + self.href.len()),
+        };
+        Diagnostic::new(
+            severity,
+            "broken-link",
+            format!("broken link `{}`: {}", self.href, self.reason),
+            &self.path,
+            &source,
+            span,
+        )
+    }
+}
+
+/// The byte range of the text of the given line (starting from 1) in
+/// `source`, without the surrounding whitespace.
+fn line_span(source: &str, line: usize) -> Range<usize> {
+    let mut start = 0;
+    for (n, text) in source.split_inclusive('\n').enumerate() {
+        if n + 1 == line {
+            let trimmed = text.trim_start();
+            let start = start + text.len() - trimmed.len();
+            return start..start + trimmed.trim_end().len();
+        }
+        start += text.len();
+    }
+    source.len()..source.len()
+}
+
+/// The pages that links can point to.
+struct Targets {
+    /// Map of the page path (relative to the output directory) to the IDs on
+    /// that page. If the IDs are `None`, then anchors are not checked.
+    pages: HashMap<PathBuf, Option<HashSet<String>>>,
+    /// Directories whose files are copied to the output directory.
+    dirs: Vec<PathBuf>,
+}
+
+impl Targets {
+    /// Checks the link `href` found on the page at `page`.
+    ///
+    /// Returns the reason why the link is broken, or `None` if it is fine.
+    fn check(&self, page: &Path, href: &str) -> Option<String> {
+        static_regex!(SCHEME_LINK, r"^[a-z][a-z0-9+.-]*:");
+
+        if href.starts_with("//") || SCHEME_LINK.is_match(href) {
+            return None;
+        }
+        let (path, fragment) = match href.split_once('#') {
+            Some((path, fragment)) => (path, Some(percent_decode(fragment))),
+            None => (href, None),
+        };
+        let path = percent_decode(path.split_once('?').map_or(path, |(path, _)| path));
+        let mut target = if path.is_empty() {
+            page.to_path_buf()
+        } else if let Some(path) = path.strip_prefix('/') {
+            normalize_path(Path::new(path))
+        } else {
+            normalize_path(&page.parent().unwrap_or(Path::new("")).join(&path))
+        };
+        if target.starts_with("..") {
+            // Can't check links outside of the book.
+            return None;
+        }
+        if path.ends_with('/') || target.as_os_str().is_empty() {
+            target.push("index.html");
+        }
+        match self.pages.get(&target) {
+            Some(Some(ids)) => match fragment {
+                Some(fragment) if !fragment.is_empty() && !ids.contains(&fragment) => {
+                    Some(format!(
+                        "anchor `#{fragment}` not found in `{}`",
+                        target.to_url_path()
+                    ))
+                }
+                _ => None,
+            },
+            Some(None) => None,
+            None if self.dirs.iter().any(|dir| dir.join(&target).is_file()) => None,
+            None => Some(format!("`{}` does not exist", target.to_url_path())),
+        }
+    }
+}
+
+/// Checks all of the links in the chapters and the redirects in the config.
+///
+/// `root` is the root of the book.
+pub(crate) fn check_links(
+    chapter_trees: &[ChapterTree<'_>],
+    html_config: &HtmlConfig,
+    root: &Path,
+    config: &Config,
+) -> Vec<BrokenLink> {
+    let targets = collect_targets(chapter_trees, html_config, root, &config.book.src);
+    let mut broken = Vec::new();
+
+    for ChapterTree {
+        chapter,
+        html_path,
+        tree,
+    } in chapter_trees
+    {
+        let mut lines = None;
+        for value in tree.values() {
+            let Some(el) = value.as_element() else {
+                continue;
+            };
+            if el.name() != "a" {
+                continue;
+            }
+            let Some(href) = el.attr("href") else {
+                continue;
+            };
+            let Some(reason) = targets.check(html_path, href) else {
+                continue;
+            };
+            let source_path = chapter.source_path.as_ref().or(chapter.path.as_ref());
+            let path = config.chapter_file(root, source_path.unwrap());
+            let lines = lines.get_or_insert_with(|| {
+                let content = chapter
+                    .source_path
+                    .as_ref()
+                    .and_then(|_| std::fs::read_to_string(root.join(&path)).ok());
+                let content = content.as_deref().unwrap_or(&chapter.content);
+                let options = HtmlRenderOptions::new(html_path, html_config, None);
+                link_lines(content, &options)
+            });
+            broken.push(BrokenLink {
+                path,
+                line: lines.get_mut(href).and_then(VecDeque::pop_front),
+                href: href.to_string(),
+                reason,
+            });
+        }
+    }
+
+    let redirects: BTreeMap<_, _> = html_config.redirect.iter().collect();
+    for (original, dest) in redirects {
+        let original = original.trim_start_matches('/');
+        let original = original.split_once('#').map_or(original, |(path, _)| path);
+        if dest.is_empty() {
+            continue;
+        }
+        if let Some(reason) = targets.check(Path::new(original), dest) {
+            broken.push(BrokenLink {
+                path: PathBuf::from("book.toml"),
+                line: None,
+                href: dest.to_string(),
+                reason: format!("redirect from `/{original}`: {reason}"),
+            });
+        }
+    }
+
+    broken
+}
+
+/// Gathers all of the pages that the HTML renderer creates.
+fn collect_targets(
+    chapter_trees: &[ChapterTree<'_>],
+    html_config: &HtmlConfig,
+    root: &Path,
+    src: &Path,
+) -> Targets {
+    let mut pages = HashMap::new();
+    for ChapterTree {
+        html_path, tree, ..
+    } in chapter_trees
+    {
+        let ids = tree
+            .values()
+            .filter_map(Node::as_element)
+            .filter_map(|el| match el.attr("id") {
+                Some(id) => Some(id),
+                None if el.name() == "a" => el.attr("name"),
+                None => None,
+            })
+            .map(str::to_string)
+            .collect();
+        pages.insert(html_path.clone(), Some(ids));
+    }
+    if let Some(first) = chapter_trees.first() {
+        let index = pages[&first.html_path].clone();
+        pages.entry(PathBuf::from("index.html")).or_insert(index);
+    }
+    if html_config.print.enable {
+        pages.insert(
+            PathBuf::from("print.html"),
+            Some(print_page_ids(chapter_trees)),
+        );
+    }
+    pages.insert(PathBuf::from("toc.html"), None);
+    if html_config.input_404.as_deref() != Some("") {
+        pages.insert(PathBuf::from(html_config.get_404_output_file()), None);
+    }
+    for original in html_config.redirect.keys() {
+        let original = original.trim_start_matches('/');
+        let original = original.split_once('#').map_or(original, |(path, _)| path);
+        pages.entry(PathBuf::from(original)).or_insert(None);
+    }
+
+    Targets {
+        pages,
+        dirs: vec![root.join(src), html_config.theme_dir(root)],
+    }
+}
+
+/// Finds the line numbers of the links in the given markdown.
+///
+/// The result is a map of the link destination (after [`fix_link`]) to the
+/// lines where it appears, in order.
+fn link_lines(text: &str, options: &HtmlRenderOptions<'_>) -> HashMap<String, VecDeque<usize>> {
+    static_regex!(
+        HREF,
+        r#"(?i)\bhref\s*=\s*(?:"(?P<dq>[^"]*)"|'(?P<sq>[^']*)')"#
+    );
+
+    let line_of = |offset: usize| text[..offset].matches('\n').count() + 1;
+    let mut lines: HashMap<String, VecDeque<usize>> = HashMap::new();
+    let events = new_cmark_parser(text, &options.markdown_options).into_offset_iter();
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) if link_type != LinkType::Email => {
+                let dest = fix_link(dest_url).to_string();
+                lines
+                    .entry(dest)
+                    .or_default()
+                    .push_back(line_of(range.start));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for caps in HREF.captures_iter(&html) {
+                    let value = caps.name("dq").or_else(|| caps.name("sq")).unwrap();
+                    let dest = fix_link(value.as_str().into()).to_string();
+                    // The html text may not start at the beginning of the
+                    // range if it is indented.
+                    let offset = text[range.clone()]
+                        .find(html.as_ref())
+                        .map_or(range.start, |start| range.start + start);
+                    let line = line_of(offset) + html[..value.start()].matches('\n').count();
+                    lines.entry(dest).or_default().push_back(line);
+                }
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Decodes `%XX` escapes in a URL component.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("a%2"), "a%2");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn lines_of_links() {
+        let config = HtmlConfig::default();
+        let options = HtmlRenderOptions::new(Path::new("chapter.md"), &config, None);
+        let text = "# Title\n\
+                    \n\
+                    See [one](one.md) and [two](two.md#part).\n\
+                    \n\
+                    <div>\n\
+                    <a href=\"one.md\">again</a>\n\
+                    </div>\n\
+                    \n\
+                    [ref]: https://example.com/\n";
+        let lines = link_lines(text, &options);
+        assert_eq!(lines["one.html"], [3, 6]);
+        assert_eq!(lines["two.html#part"], [3]);
+    }
+}
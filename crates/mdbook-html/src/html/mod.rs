@@ -10,14 +10,19 @@
 //!      2. Apply various transformations to the tree data structure, such as adding header links.
 //! 3. Serialize the tree to HTML in [`serialize()`].
 
+use anyhow::Result;
 use ego_tree::Tree;
 use mdbook_core::book::{Book, Chapter};
-use mdbook_core::config::{HtmlConfig, RustEdition};
+use mdbook_core::config::{Config, HtmlConfig, RustEdition};
 use mdbook_markdown::{MarkdownOptions, new_cmark_parser};
 use std::path::{Path, PathBuf};
 
 mod admonitions;
 mod hide_lines;
+#[cfg(feature = "highlight")]
+mod highlight;
+mod links;
+mod page_toc;
 mod print;
 mod serialize;
 #[cfg(test)]
@@ -26,8 +31,12 @@ mod tokenizer;
 mod tree;
 
 pub(crate) use hide_lines::{hide_lines, wrap_rust_main};
+pub use links::BrokenLink;
+pub(crate) use links::check_links;
+pub(crate) use page_toc::page_toc;
 pub(crate) use print::render_print_page;
 pub(crate) use serialize::serialize;
+use serialize::serialize_xhtml;
 pub(crate) use tree::{Element, Node};
 
 /// Options for converting a single chapter's markdown to HTML.
@@ -106,3 +115,67 @@ pub(crate) fn build_trees<'book>(
         })
         .collect()
 }
+
+/// A chapter rendered to XHTML.
+///
+/// This is used by renderers other than the HTML renderer that want to
+/// reuse the same conversion from markdown, such as the EPUB renderer.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct XhtmlChapter<'book> {
+    /// The chapter that was rendered.
+    pub chapter: &'book Chapter,
+    /// The path to the chapter relative to the root with the `.html` extension.
+    pub path: PathBuf,
+    /// The body of the chapter as an XHTML fragment.
+    pub content: String,
+    /// The `src` of every image in the chapter that refers to a local file,
+    /// relative to the chapter.
+    pub images: Vec<String>,
+}
+
+/// Renders all of the non-draft chapters of the book to XHTML.
+///
+/// This goes through the same steps as the HTML renderer, such as adding
+/// header links, hiding lines in code blocks, and rendering admonitions and
+/// footnotes.
+pub fn render_xhtml_chapters<'book>(
+    book: &'book Book,
+    html_config: &HtmlConfig,
+    edition: Option<RustEdition>,
+) -> Vec<XhtmlChapter<'book>> {
+    build_trees(book, html_config, edition)
+        .into_iter()
+        .map(|chapter_tree| {
+            let mut content = String::new();
+            serialize_xhtml(&chapter_tree.tree, &mut content);
+            let images = chapter_tree
+                .tree
+                .values()
+                .filter_map(|node| match node {
+                    Node::Element(el) if el.name() == "img" => el.attr("src"),
+                    _ => None,
+                })
+                .filter(|src| !src.is_empty() && !src.starts_with('/') && !src.contains(':'))
+                .map(str::to_string)
+                .collect();
+            XhtmlChapter {
+                chapter: chapter_tree.chapter,
+                path: chapter_tree.html_path,
+                content,
+                images,
+            }
+        })
+        .collect()
+}
+
+/// Checks that all of the links in the book point to pages and anchors that
+/// exist in the HTML output.
+///
+/// This includes links to the print page, and the destinations of
+/// `output.html.redirect`. `root` is the root directory of the book.
+pub fn check_book_links(root: &Path, book: &Book, config: &Config) -> Result<Vec<BrokenLink>> {
+    let html_config = config.get::<HtmlConfig>("output.html")?.unwrap_or_default();
+    let chapter_trees = build_trees(book, &html_config, config.rust.edition);
+    Ok(check_links(&chapter_trees, &html_config, root, config))
+}
@@ -0,0 +1,63 @@
+//! Support for the "On this page" outline of a chapter.
+
+use super::Node;
+use super::tree::text_in_node;
+use ego_tree::Tree;
+use serde::Serialize;
+
+/// A heading in the outline of a chapter.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct PageTocEntry {
+    /// The heading level, from 2 to 6.
+    pub(crate) level: u8,
+    /// The `id` of the heading element.
+    pub(crate) id: String,
+    /// The plain text of the heading.
+    pub(crate) title: String,
+    /// The headings nested under this heading.
+    pub(crate) children: Vec<PageTocEntry>,
+}
+
+/// Collects the headings of a chapter into a tree.
+///
+/// The `h1` heading is skipped since that is usually the title of the
+/// chapter. `depth` is the number of levels to include, starting from `h2`.
+/// Headings without an `id` can't be linked to, and are skipped.
+pub(crate) fn page_toc(tree: &Tree<Node>, depth: u8) -> Vec<PageTocEntry> {
+    let max_level = depth.saturating_add(1);
+    let mut entries = Vec::new();
+    for node in tree.root().descendants() {
+        let Some(el) = node.value().as_element() else {
+            continue;
+        };
+        let Some(level) = el.heading_level() else {
+            continue;
+        };
+        if !(2..=max_level).contains(&level) {
+            continue;
+        }
+        let Some(id) = el.attr("id") else {
+            continue;
+        };
+        let mut title = String::new();
+        text_in_node(node, &mut title);
+        insert(
+            &mut entries,
+            PageTocEntry {
+                level,
+                id: id.to_string(),
+                title: title.trim().to_string(),
+                children: Vec::new(),
+            },
+        );
+    }
+    entries
+}
+
+/// Adds an entry as a child of the last entry with a lower level.
+fn insert(entries: &mut Vec<PageTocEntry>, entry: PageTocEntry) {
+    match entries.last_mut() {
+        Some(last) if last.level < entry.level => insert(&mut last.children, entry),
+        _ => entries.push(entry),
+    }
+}
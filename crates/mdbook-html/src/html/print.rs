@@ -7,6 +7,7 @@
 use super::Node;
 use crate::html::{ChapterTree, Element, serialize};
 use crate::utils::{ToUrlPath, id_from_content, normalize_path, unique_id};
+use ego_tree::Tree;
 use mdbook_core::static_regex;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
@@ -83,22 +84,9 @@ fn make_root_id_map(
         ..
     } in chapter_trees
     {
-        let mut h1_found = false;
-        for value in tree.values_mut() {
-            if let Node::Element(el) = value {
-                if el.name() == "h1" {
-                    if let Some(id) = el.attr("id") {
-                        h1_found = true;
-                        path_to_root_id.insert(html_path.clone(), id.to_string());
-                    }
-                    break;
-                } else if matches!(el.name(), "h2" | "h3" | "h4" | "h5" | "h6") {
-                    // h1 not found.
-                    break;
-                }
-            }
-        }
-        if !h1_found {
+        if let Some(id) = root_heading_id(tree) {
+            path_to_root_id.insert(html_path.clone(), id.to_string());
+        } else {
             // Synthesize a root id to be able to link to the start of the page.
             // TODO: This might want to be a warning? Chapters generally
             // should start with an h1.
@@ -120,6 +108,43 @@ fn make_root_id_map(
     path_to_root_id
 }
 
+/// Returns the ID of the `h1` tag at the start of the chapter, if it has one.
+fn root_heading_id(tree: &Tree<Node>) -> Option<&str> {
+    for value in tree.values() {
+        if let Node::Element(el) = value {
+            if el.name() == "h1" {
+                return el.attr("id");
+            } else if matches!(el.name(), "h2" | "h3" | "h4" | "h5" | "h6") {
+                // h1 not found.
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// Returns all of the IDs that will be on the print page.
+///
+/// This mirrors the changes made by [`render_print_page`] without modifying
+/// the chapter trees.
+pub(crate) fn print_page_ids(chapter_trees: &[ChapterTree<'_>]) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for ChapterTree { tree, .. } in chapter_trees {
+        for id in tree
+            .values()
+            .filter_map(|value| value.as_element()?.attr("id"))
+        {
+            unique_id(id, &mut ids);
+        }
+    }
+    for ChapterTree { chapter, tree, .. } in chapter_trees {
+        if root_heading_id(tree).is_none() {
+            unique_id(&id_from_content(&chapter.name), &mut ids);
+        }
+    }
+    ids
+}
+
 /// Rewrite links so that they point to IDs on the print page.
 fn rewrite_links(
     chapter_trees: &mut [ChapterTree<'_>],
@@ -9,10 +9,22 @@ use std::ops::Deref;
 
 /// Serializes the given tree of [`Node`] elements to an HTML string.
 pub(crate) fn serialize(tree: &Tree<Node>, output: &mut String) {
+    serialize_impl(tree, output, false);
+}
+
+/// Serializes the given tree of [`Node`] elements to an XHTML string.
+///
+/// This differs from [`serialize`] in that void elements are always closed,
+/// and SVG elements are given their namespace.
+pub(crate) fn serialize_xhtml(tree: &Tree<Node>, output: &mut String) {
+    serialize_impl(tree, output, true);
+}
+
+fn serialize_impl(tree: &Tree<Node>, output: &mut String, xhtml: bool) {
     for edge in tree.root().traverse() {
         match edge {
             Edge::Open(node) => match node.value() {
-                Node::Element(el) => serialize_start(el, output),
+                Node::Element(el) => serialize_start(el, output, xhtml),
                 Node::Text(text) => {
                     output.push_str(&escape_html(text));
                 }
@@ -28,7 +40,7 @@ pub(crate) fn serialize(tree: &Tree<Node>, output: &mut String) {
             },
             Edge::Close(node) => {
                 if let Node::Element(el) = node.value() {
-                    serialize_end(el, output);
+                    serialize_end(el, output, xhtml);
                 }
             }
         }
@@ -61,7 +73,7 @@ fn wants_pretty_html_newline(name: &str) -> bool {
 }
 
 /// Emit the start tag of an element.
-fn serialize_start(el: &Element, output: &mut String) {
+fn serialize_start(el: &Element, output: &mut String, xhtml: bool) {
     let el_name = el.name();
     if wants_pretty_html_newline(el_name) {
         if !output.is_empty() {
@@ -71,7 +83,11 @@ fn serialize_start(el: &Element, output: &mut String) {
         }
     }
     output.push('<');
-    output.push_str(el_name);
+    if xhtml {
+        output.push_str(svg_case(el_name));
+    } else {
+        output.push_str(el_name);
+    }
     for (attr_name, value) in &el.attrs {
         output.push(' ');
         match attr_name.ns {
@@ -85,26 +101,66 @@ fn serialize_start(el: &Element, output: &mut String) {
             ns!(xlink) => output.push_str("xlink:"),
             _ => (), // TODO what should it do here?
         }
-        output.push_str(attr_name.local.deref());
+        if xhtml {
+            output.push_str(svg_case(attr_name.local.deref()));
+        } else {
+            output.push_str(attr_name.local.deref());
+        }
         output.push_str("=\"");
         output.push_str(&escape_html_attribute(&value));
         output.push('"');
     }
-    if el.self_closing {
+    if xhtml && el_name == "svg" && !el.attrs.keys().any(|name| &*name.local == "xmlns") {
+        output.push_str(" xmlns=\"http://www.w3.org/2000/svg\"");
+    }
+    if el.self_closing || (xhtml && is_void_element(el_name)) {
         output.push_str(" /");
     }
     output.push('>');
 }
 
+/// Restores the case of SVG element and attribute names.
+///
+/// HTML is case-insensitive, so the tokenizer lowercases all names. XHTML is
+/// case-sensitive, so the camel-cased names used by SVG must be restored.
+fn svg_case(name: &str) -> &str {
+    match name {
+        "attributename" => "attributeName",
+        "clippath" => "clipPath",
+        "clippathunits" => "clipPathUnits",
+        "foreignobject" => "foreignObject",
+        "gradienttransform" => "gradientTransform",
+        "gradientunits" => "gradientUnits",
+        "lineargradient" => "linearGradient",
+        "markerheight" => "markerHeight",
+        "markerunits" => "markerUnits",
+        "markerwidth" => "markerWidth",
+        "patterntransform" => "patternTransform",
+        "patternunits" => "patternUnits",
+        "preserveaspectratio" => "preserveAspectRatio",
+        "radialgradient" => "radialGradient",
+        "refx" => "refX",
+        "refy" => "refY",
+        "textlength" => "textLength",
+        "textpath" => "textPath",
+        "viewbox" => "viewBox",
+        _ => name,
+    }
+}
+
 /// Emit the end tag of an element.
-fn serialize_end(el: &Element, output: &mut String) {
+fn serialize_end(el: &Element, output: &mut String, xhtml: bool) {
     // Void elements do not have an end tag.
     if el.self_closing || is_void_element(el.name()) {
         return;
     }
     let name = el.name();
     output.push_str("</");
-    output.push_str(name);
+    if xhtml {
+        output.push_str(svg_case(name));
+    } else {
+        output.push_str(name);
+    }
     output.push('>');
     if wants_pretty_html_newline(name) {
         output.push('\n');
@@ -59,7 +59,7 @@ impl Node {
     }
 
     /// If this is an [`Element`], return it (mutable).
-    fn as_element_mut(&mut self) -> Option<&mut Element> {
+    pub(crate) fn as_element_mut(&mut self) -> Option<&mut Element> {
         if let Node::Element(el) = self {
             Some(el)
         } else {
@@ -1040,9 +1040,27 @@ where
             pre.insert_attr("class", "playground".into());
         }
 
-        for code_id in code_ids {
+        for code_id in code_ids.iter().copied() {
             hide_lines(&mut self.tree, code_id, &self.options.config.code.hidelines);
         }
+
+        #[cfg(feature = "highlight")]
+        if self.options.config.code.highlighter == mdbook_core::config::Highlighter::Builtin {
+            for code_id in code_ids {
+                let node = self.tree.get(code_id).unwrap();
+                let in_pre = node
+                    .parent()
+                    .and_then(|parent| parent.value().as_element())
+                    .is_some_and(|parent| parent.name() == "pre");
+                let class = node.value().as_element().unwrap().attr("class");
+                // Editable code blocks are handled by the ACE editor.
+                let is_editable = self.options.config.playground.editable
+                    && class.is_some_and(|class| class.split(' ').any(|c| c == "editable"));
+                if in_pre && !is_editable {
+                    super::highlight::highlight_code(&mut self.tree, code_id);
+                }
+            }
+        }
     }
 
     /// This is used after parsing is complete to replace `<i>` tags with a
@@ -1108,7 +1126,7 @@ where
 /// Traverse the given node, emitting any plain text into the output.
 ///
 /// This is used to generate the `id` of a header.
-fn text_in_node(node: NodeRef<'_, Node>, output: &mut String) {
+pub(crate) fn text_in_node(node: NodeRef<'_, Node>, output: &mut String) {
     for child in node.children() {
         match child.value() {
             Node::Element(_) => {}
@@ -1124,7 +1142,7 @@ fn text_in_node(node: NodeRef<'_, Node>, output: &mut String) {
 /// Modifies links to work with HTML.
 ///
 /// For local paths, this changes the `.md` extension to `.html`.
-fn fix_link<'a>(link: CowStr<'a>) -> CowStr<'a> {
+pub(crate) fn fix_link<'a>(link: CowStr<'a>) -> CowStr<'a> {
     static_regex!(SCHEME_LINK, r"^[a-z][a-z0-9+.-]*:");
     static_regex!(MD_LINK, r"(?P<link>.*)\.md(?P<anchor>#.*)?");
 
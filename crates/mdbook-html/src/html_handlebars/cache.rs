@@ -0,0 +1,142 @@
+//! Support for incremental builds.
+//!
+//! When `output.html.incremental` is enabled, a small cache file is kept in
+//! the destination directory. It records a fingerprint of everything that
+//! affects every page (the config, theme, static files, and the structure of
+//! the book), along with a hash of the inputs of each chapter. If the
+//! fingerprint is unchanged on the next build, chapters whose hash has not
+//! changed are not rendered again. Otherwise, the destination is cleaned and
+//! everything is rebuilt.
+
+use anyhow::Result;
+use mdbook_core::book::{BookItem, Chapter, ChapterMetadata};
+use mdbook_core::utils::fs;
+use mdbook_renderer::RenderContext;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// The name of the cache file in the destination directory.
+const CACHE_FILE: &str = ".mdbook-cache.json";
+
+/// The contents of the incremental build cache.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct BuildCache {
+    /// Hash of the inputs shared by all pages.
+    pub(super) fingerprint: String,
+    /// Hash of the inputs of each chapter, keyed by the output filename.
+    pub(super) chapters: BTreeMap<PathBuf, String>,
+}
+
+impl BuildCache {
+    /// Loads the cache from the destination directory, if there is one.
+    pub(super) fn load(destination: &Path) -> Option<BuildCache> {
+        let path = destination.join(CACHE_FILE);
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                debug!("ignoring invalid build cache `{}`: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Writes the cache to the destination directory.
+    pub(super) fn save(&self, destination: &Path) -> Result<()> {
+        fs::write(destination.join(CACHE_FILE), serde_json::to_string(self)?)
+    }
+
+    /// Returns `true` if the chapter rendered to `filepath` has not changed
+    /// since it was last written.
+    pub(super) fn is_fresh(&self, destination: &Path, filepath: &Path, hash: &str) -> bool {
+        self.chapters.get(filepath).map(String::as_str) == Some(hash)
+            && destination.join(filepath).exists()
+    }
+}
+
+/// Computes the hash of everything that can affect every page of the book.
+pub(super) fn fingerprint(
+    ctx: &RenderContext,
+    theme_dir: &Path,
+    hash_map: &HashMap<String, String>,
+) -> Result<String> {
+    let mut digest = Sha256::new();
+    digest.update(env!("CARGO_PKG_VERSION"));
+    digest.update(serde_json::to_vec(&ctx.config)?);
+
+    let hash_map: BTreeMap<_, _> = hash_map.iter().collect();
+    digest.update(serde_json::to_vec(&hash_map)?);
+
+    let structure: Vec<_> = ctx
+        .book
+        .iter()
+        .map(|item| match item {
+            BookItem::Chapter(ch) => json!([ch.name, ch.path, ch.source_path, ch.number]),
+            BookItem::Separator => json!("---"),
+            BookItem::PartTitle(title) => json!(["#", title]),
+        })
+        .collect();
+    digest.update(serde_json::to_vec(&structure)?);
+
+    if theme_dir.is_dir() {
+        hash_dir(&mut digest, theme_dir, theme_dir, None, true)?;
+    }
+
+    // Files copied from the source directory are not tracked individually, so
+    // adding or removing one requires a full build to clean up the output.
+    let src_dir = ctx.root.join(&ctx.config.book.src);
+    let build_dir = ctx.root.join(&ctx.config.build.build_dir);
+    if src_dir.is_dir() {
+        hash_dir(&mut digest, &src_dir, &src_dir, Some(&build_dir), false)?;
+    }
+
+    Ok(hex::encode(digest.finalize()))
+}
+
+/// Computes the hash of the inputs that only affect a single chapter's page.
+pub(super) fn chapter_hash(
+    ch: &Chapter,
+    title: Option<&String>,
+    metadata: Option<&ChapterMetadata>,
+) -> Result<String> {
+    let inputs = json!({
+        "content": ch.content,
+        "metadata": metadata,
+        "title": title,
+    });
+    Ok(hex::encode(Sha256::digest(serde_json::to_vec(&inputs)?)))
+}
+
+/// Adds the paths (and optionally the contents) of all files in `dir` to the
+/// digest, in a stable order.
+fn hash_dir(
+    digest: &mut Sha256,
+    root: &Path,
+    dir: &Path,
+    avoid_dir: Option<&Path>,
+    contents: bool,
+) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if Some(entry.as_path()) == avoid_dir {
+            continue;
+        }
+        if entry.is_dir() {
+            hash_dir(digest, root, &entry, avoid_dir, contents)?;
+        } else {
+            let relative = entry.strip_prefix(root).unwrap_or(&entry);
+            digest.update(relative.to_string_lossy().as_bytes());
+            if contents {
+                digest.update(std::fs::read(&entry)?);
+            }
+        }
+    }
+    Ok(())
+}
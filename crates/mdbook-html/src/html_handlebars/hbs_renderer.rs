@@ -1,13 +1,17 @@
+use super::cache::{self, BuildCache};
 use super::helpers;
 use super::static_files::StaticFiles;
 use crate::html::ChapterTree;
-use crate::html::{build_trees, render_markdown, serialize};
+use crate::html::{build_trees, check_links, page_toc, render_markdown, serialize};
 use crate::theme::Theme;
 use crate::utils::ToUrlPath;
 use anyhow::{Context, Result, bail};
 use handlebars::Handlebars;
-use mdbook_core::book::{Book, BookItem, Chapter};
-use mdbook_core::config::{BookConfig, Config, HtmlConfig};
+use mdbook_core::book::{Book, BookItem, Chapter, ChapterMetadata};
+use mdbook_core::config::{
+    BookConfig, Config, Highlighter, HtmlConfig, LanguageConfig, LinkCheckMode,
+};
+use mdbook_core::diagnostic::Severity;
 use mdbook_core::utils::fs;
 use mdbook_renderer::{RenderContext, Renderer};
 use serde_json::json;
@@ -44,13 +48,11 @@ impl HtmlHandlebars {
         };
 
         if let Some(ref edit_url_template) = ctx.html_config.edit_url_template {
-            let full_path = ctx.book_config.src.to_str().unwrap_or_default().to_owned()
-                + "/"
-                + ch.source_path
-                    .clone()
-                    .unwrap_or_default()
-                    .to_str()
-                    .unwrap_or_default();
+            let full_path = ctx
+                .source_file
+                .as_deref()
+                .map(ToUrlPath::to_url_path)
+                .unwrap_or_default();
 
             let edit_url = edit_url_template.replace("{path}", &full_path);
             ctx.data
@@ -85,10 +87,31 @@ impl HtmlHandlebars {
         ctx.data.insert("title".to_owned(), json!(title));
         ctx.data
             .insert("path_to_root".to_owned(), json!(fs::path_to_root(path)));
+        if !ctx.languages.is_empty() {
+            let links = language_links(ctx.languages, &ctx.book_config, path);
+            ctx.data.insert("languages".to_owned(), links);
+        }
         if let Some(ref section) = ch.number {
             ctx.data
                 .insert("section".to_owned(), json!(section.to_string()));
         }
+        let metadata = ch
+            .source_path
+            .as_ref()
+            .and_then(|source_path| ctx.chapter_metadata.get(source_path))
+            .cloned()
+            .unwrap_or_default();
+        if let Some(ref description) = metadata.description {
+            ctx.data
+                .insert("description".to_owned(), json!(description));
+        }
+        ctx.data.insert("metadata".to_owned(), json!(metadata));
+        if ctx.html_config.page_toc.enable {
+            let page_toc = page_toc(&chapter_tree.tree, ctx.html_config.page_toc.depth);
+            if !page_toc.is_empty() {
+                ctx.data.insert("page_toc".to_owned(), json!(page_toc));
+            }
+        }
 
         let redirects = collect_redirects_for_path(&filepath, &ctx.html_config.redirect)?;
         if !redirects.is_empty() {
@@ -127,6 +150,10 @@ impl HtmlHandlebars {
             ctx.data.insert("path".to_owned(), json!("index.md"));
             ctx.data.insert("path_to_root".to_owned(), json!(""));
             ctx.data.insert("is_index".to_owned(), json!(true));
+            if !ctx.languages.is_empty() {
+                let links = language_links(ctx.languages, &ctx.book_config, "index.md");
+                ctx.data.insert("languages".to_owned(), links);
+            }
             let rendered_index = ctx.handlebars.render("index", &ctx.data)?;
             debug!("Creating index.html from {}", ctx_path);
             fs::write(ctx.destination.join("index.html"), rendered_index)?;
@@ -218,13 +245,22 @@ impl HtmlHandlebars {
             "path_to_root".to_owned(),
             json!(fs::path_to_root(Path::new("print.md"))),
         );
+        if ctx.config.is_multilingual() {
+            let links = language_links(&ctx.config.language, &ctx.config.book, "print.md");
+            data.insert("languages".to_owned(), links);
+        }
 
         debug!("Render template");
         let rendered = handlebars.render("index", &data)?;
         Ok(rendered)
     }
 
-    fn register_hbs_helpers(&self, handlebars: &mut Handlebars<'_>, html_config: &HtmlConfig) {
+    fn register_hbs_helpers(
+        &self,
+        handlebars: &mut Handlebars<'_>,
+        book_config: &BookConfig,
+        html_config: &HtmlConfig,
+    ) {
         handlebars.register_helper(
             "toc",
             Box::new(helpers::toc::RenderToc {
@@ -232,6 +268,13 @@ impl HtmlHandlebars {
             }),
         );
         handlebars.register_helper("fa", Box::new(helpers::fontawesome::fa_helper));
+        handlebars.register_helper(
+            "t",
+            Box::new(helpers::ui_strings::UiStrings::new(
+                book_config.language.as_deref(),
+                &html_config.ui_strings,
+            )),
+        );
     }
 
     fn emit_redirects(
@@ -271,6 +314,21 @@ impl HtmlHandlebars {
         Ok(())
     }
 
+    /// Writes an `index.html` next to the directory of the default language
+    /// of a multilingual book, which redirects to it.
+    fn emit_language_redirect(
+        &self,
+        destination: &Path,
+        handlebars: &Handlebars<'_>,
+        default: &str,
+    ) -> Result<()> {
+        let Some(root) = destination.parent() else {
+            return Ok(());
+        };
+        let url = format!("{default}/index.html");
+        self.emit_redirect(handlebars, &root.join("index.html"), &url, &BTreeMap::new())
+    }
+
     fn emit_redirect(
         &self,
         handlebars: &Handlebars<'_>,
@@ -313,11 +371,6 @@ impl Renderer for HtmlHandlebars {
         let book = &ctx.book;
         let build_dir = ctx.root.join(&ctx.config.build.build_dir);
 
-        if destination.exists() {
-            fs::remove_dir_content(destination)
-                .with_context(|| "Unable to remove stale HTML output")?;
-        }
-
         trace!("render");
         let mut handlebars = Handlebars::new();
 
@@ -332,7 +385,7 @@ impl Renderer for HtmlHandlebars {
             None => ctx.root.join("theme"),
         };
 
-        let theme = Theme::new(theme_dir);
+        let theme = Theme::new(&theme_dir);
 
         debug!("Register the index handlebars template");
         handlebars.register_template_string("index", String::from_utf8(theme.index.clone())?)?;
@@ -353,14 +406,26 @@ impl Renderer for HtmlHandlebars {
             .register_template_string("toc_html", String::from_utf8(theme.toc_html.clone())?)?;
 
         debug!("Register handlebars helpers");
-        self.register_hbs_helpers(&mut handlebars, &html_config);
+        self.register_hbs_helpers(&mut handlebars, book_config, &html_config);
 
         let mut data = make_data(&ctx.root, book, &ctx.config, &html_config, &theme)?;
 
         let chapter_trees = build_trees(book, &html_config, ctx.config.rust.edition);
 
-        fs::create_dir_all(destination)
-            .with_context(|| "Unexpected error when constructing destination path")?;
+        if html_config.check_links != LinkCheckMode::Off {
+            debug!("Checking links");
+            let broken = check_links(&chapter_trees, &html_config, &ctx.root, &ctx.config);
+            let severity = match html_config.check_links {
+                LinkCheckMode::Error => Severity::Error,
+                _ => Severity::Warning,
+            };
+            for link in &broken {
+                link.to_diagnostic(&ctx.root, severity).emit();
+            }
+            if html_config.check_links == LinkCheckMode::Error && !broken.is_empty() {
+                bail!("found {} broken link(s)", broken.len());
+            }
+        }
 
         let mut static_files = StaticFiles::new(&theme, &html_config, &ctx.root)?;
 
@@ -370,7 +435,12 @@ impl Renderer for HtmlHandlebars {
             let default = mdbook_core::config::Search::default();
             let search = html_config.search.as_ref().unwrap_or(&default);
             if search.enable {
-                super::search::create_files(&search, &mut static_files, &chapter_trees)?;
+                super::search::create_files(
+                    &search,
+                    ctx.config.book.language.as_deref(),
+                    &mut static_files,
+                    &chapter_trees,
+                )?;
             }
         }
 
@@ -385,6 +455,33 @@ impl Renderer for HtmlHandlebars {
             static_files.hash_files()?;
         }
 
+        let mut previous_cache = BuildCache::default();
+        let mut new_cache = None;
+        if html_config.incremental {
+            // Every page refers to the hashed names of the static files,
+            // including `toc.js` and the search index, so they are part of
+            // the fingerprint.
+            let fingerprint = cache::fingerprint(ctx, &theme_dir, static_files.hash_map())?;
+            if let Some(previous) = BuildCache::load(destination)
+                && previous.fingerprint == fingerprint
+            {
+                debug!("Reusing previous HTML output");
+                previous_cache = previous;
+            }
+            new_cache = Some(BuildCache {
+                fingerprint,
+                ..Default::default()
+            });
+        }
+
+        if previous_cache.fingerprint.is_empty() && destination.exists() {
+            fs::remove_dir_content(destination)
+                .with_context(|| "Unable to remove stale HTML output")?;
+        }
+
+        fs::create_dir_all(destination)
+            .with_context(|| "Unexpected error when constructing destination path")?;
+
         debug!("Copy static files");
         let resource_helper = static_files
             .write_files(&destination)
@@ -415,6 +512,22 @@ impl Renderer for HtmlHandlebars {
         for (i, chapter_tree) in chapter_trees.iter().enumerate() {
             let previous = (i != 0).then(|| chapter_trees[i - 1].chapter);
             let next = (i != chapter_trees.len() - 1).then(|| chapter_trees[i + 1].chapter);
+            if let Some(new_cache) = &mut new_cache {
+                let ch = chapter_tree.chapter;
+                let path = ch.path.as_ref().unwrap();
+                let metadata = ch
+                    .source_path
+                    .as_ref()
+                    .and_then(|source_path| ctx.chapter_metadata.get(source_path));
+                let hash = cache::chapter_hash(ch, ctx.chapter_titles.get(path), metadata)?;
+                let filepath = path.with_extension("html");
+                let fresh = previous_cache.is_fresh(destination, &filepath, &hash);
+                new_cache.chapters.insert(filepath, hash);
+                if fresh {
+                    debug!("Skipping unchanged chapter {}", path.display());
+                    continue;
+                }
+            }
             let ctx = RenderChapterContext {
                 handlebars: &handlebars,
                 destination: destination.to_path_buf(),
@@ -422,6 +535,13 @@ impl Renderer for HtmlHandlebars {
                 book_config: book_config.clone(),
                 html_config: html_config.clone(),
                 chapter_titles: &ctx.chapter_titles,
+                chapter_metadata: &ctx.chapter_metadata,
+                languages: &ctx.config.language,
+                source_file: chapter_tree
+                    .chapter
+                    .source_path
+                    .as_ref()
+                    .map(|source_path| ctx.config.chapter_file(&ctx.root, source_path)),
             };
             self.render_chapter(chapter_tree, previous, next, ctx)?;
         }
@@ -443,8 +563,31 @@ impl Renderer for HtmlHandlebars {
         self.emit_redirects(&ctx.destination, &handlebars, &html_config.redirect)
             .context("Unable to emit redirects")?;
 
+        if ctx.config.is_multilingual() {
+            let default = ctx.config.default_language()?;
+            if book_config.language.as_deref() == Some(default) {
+                self.emit_language_redirect(destination, &handlebars, default)?;
+            } else if let Some(default_src_dir) = src_dir.parent().map(|dir| dir.join(default)) {
+                // The files that haven't been translated are taken from the
+                // default language.
+                fs::copy_files_except_ext(
+                    &default_src_dir,
+                    destination,
+                    true,
+                    Some(&build_dir),
+                    &["md"],
+                )?;
+            }
+        }
+
         // Copy all remaining files, avoid a recursive copy from/to the book build dir
-        fs::copy_files_except_ext(&src_dir, destination, true, Some(&build_dir), &["md"])?;
+        if src_dir.exists() {
+            fs::copy_files_except_ext(&src_dir, destination, true, Some(&build_dir), &["md"])?;
+        }
+
+        if let Some(new_cache) = new_cache {
+            new_cache.save(destination)?;
+        }
 
         info!("HTML book written to `{}`", destination.display());
 
@@ -452,6 +595,30 @@ impl Renderer for HtmlHandlebars {
     }
 }
 
+/// The links to the page at `path` in each language of a multilingual book,
+/// for the language switcher.
+fn language_links(
+    languages: &BTreeMap<String, LanguageConfig>,
+    book_config: &BookConfig,
+    path: impl AsRef<Path>,
+) -> serde_json::Value {
+    let path = path.as_ref();
+    let path_to_root = fs::path_to_root(path);
+    let page = path.with_extension("html").to_url_path();
+    let links: Vec<_> = languages
+        .iter()
+        .map(|(code, language)| {
+            json!({
+                "code": code,
+                "name": language.name.as_deref().unwrap_or(code),
+                "current": book_config.language.as_deref() == Some(code),
+                "link": format!("{path_to_root}../{code}/{page}"),
+            })
+        })
+        .collect();
+    json!(links)
+}
+
 fn make_data(
     root: &Path,
     book: &Book,
@@ -568,6 +735,21 @@ fn make_data(
         )
     }
 
+    if html_config.code.highlighter == Highlighter::Builtin {
+        if cfg!(feature = "highlight") {
+            data.insert("builtin_highlighting".to_owned(), json!(true));
+        } else {
+            warn!(
+                "mdBook compiled without build-time highlighting support, \
+                 using highlight.js instead"
+            );
+            warn!(
+                "please reinstall with `cargo install mdbook --force --features highlight` \
+                 to use `output.html.code.highlighter = \"builtin\"`"
+            );
+        }
+    }
+
     if let Some(ref git_repository_url) = html_config.git_repository_url {
         data.insert("git_repository_url".to_owned(), json!(git_repository_url));
     }
@@ -637,6 +819,13 @@ struct RenderChapterContext<'a> {
     book_config: BookConfig,
     html_config: HtmlConfig,
     chapter_titles: &'a HashMap<PathBuf, String>,
+    /// The front matter of the chapters, by source path.
+    chapter_metadata: &'a HashMap<PathBuf, ChapterMetadata>,
+    /// The languages of a multilingual book, for the language switcher.
+    languages: &'a BTreeMap<String, LanguageConfig>,
+    /// The file of the chapter, relative to the root of the book, for the
+    /// edit link.
+    source_file: Option<PathBuf>,
 }
 
 /// Redirect mapping.
@@ -1,3 +1,4 @@
 pub(crate) mod fontawesome;
 pub(crate) mod resources;
 pub(crate) mod toc;
+pub(crate) mod ui_strings;
@@ -0,0 +1,209 @@
+//! Translated strings for the user interface of the HTML theme.
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, Output, RenderContext, RenderError, RenderErrorReason,
+};
+use mdbook_core::utils::escape_html_attribute;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// The built-in message catalogs, by language code.
+///
+/// English must be first, since it is the fallback for every other language.
+static CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../../../front-end/strings/en.toml")),
+    ("de", include_str!("../../../front-end/strings/de.toml")),
+    ("es", include_str!("../../../front-end/strings/es.toml")),
+    ("fr", include_str!("../../../front-end/strings/fr.toml")),
+    ("ja", include_str!("../../../front-end/strings/ja.toml")),
+    ("zh", include_str!("../../../front-end/strings/zh.toml")),
+];
+
+/// Handlebars helper to look up a string of the user interface in the
+/// language of the book.
+///
+/// `{{t "print-book"}}` writes the string with the HTML characters escaped.
+/// Placeholders like `{key}` in the string are replaced with the value of the
+/// hash parameter of the same name, which is not escaped, such as
+/// `{{t "help-show" key="<kbd>?</kbd>"}}`.
+#[derive(Clone, Debug)]
+pub(crate) struct UiStrings {
+    strings: BTreeMap<String, String>,
+}
+
+impl UiStrings {
+    /// Creates the strings for `language`, which defaults to English.
+    ///
+    /// Each string is taken from the first of these that has it: the
+    /// overrides for the language, the built-in catalog for the language,
+    /// then the same for the language without its region (`pt` for
+    /// `pt-BR`), and finally the built-in English catalog.
+    pub(crate) fn new(
+        language: Option<&str>,
+        overrides: &BTreeMap<String, BTreeMap<String, String>>,
+    ) -> UiStrings {
+        let english = parse_catalog("en", CATALOGS[0].1);
+        for (code, strings) in overrides {
+            for key in strings.keys().filter(|key| !english.contains_key(*key)) {
+                warn!("unknown UI string `{key}` in `output.html.ui-strings.{code}`");
+            }
+        }
+
+        let language = language.unwrap_or("en");
+        let mut codes = vec![language];
+        if let Some((primary, _)) = language.split_once('-') {
+            codes.push(primary);
+        }
+
+        let mut strings = english;
+        for code in codes.into_iter().rev() {
+            if let Some((code, catalog)) = CATALOGS
+                .iter()
+                .find(|(catalog_code, _)| catalog_code.eq_ignore_ascii_case(code))
+            {
+                strings.extend(parse_catalog(code, catalog));
+            }
+            if let Some((_, overrides)) = overrides
+                .iter()
+                .find(|(override_code, _)| override_code.eq_ignore_ascii_case(code))
+            {
+                strings.extend(overrides.clone());
+            }
+        }
+        UiStrings { strings }
+    }
+
+    /// Returns the string for `key`, if there is one.
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+}
+
+fn parse_catalog(code: &str, catalog: &str) -> BTreeMap<String, String> {
+    toml::from_str(catalog)
+        .unwrap_or_else(|e| panic!("the built-in `{code}` UI strings should be valid: {e}"))
+}
+
+impl HelperDef for UiStrings {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _r: &'reg Handlebars<'_>,
+        _ctx: &'rc Context,
+        _rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> Result<(), RenderError> {
+        let key = h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+            RenderErrorReason::Other(
+                "Param 0 with String type is required for t helper.".to_owned(),
+            )
+        })?;
+        let string = self
+            .get(key)
+            .ok_or_else(|| RenderErrorReason::Other(format!("unknown UI string `{key}`")))?;
+
+        let mut result = escape_html_attribute(string).into_owned();
+        for (name, value) in h.hash() {
+            let value = match value.value() {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            result = result.replace(&format!("{{{name}}}"), &value);
+        }
+        out.write(&result)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_catalogs_are_complete() {
+        let english = parse_catalog("en", CATALOGS[0].1);
+        for (code, catalog) in CATALOGS {
+            let strings = parse_catalog(code, catalog);
+            let keys: Vec<_> = strings.keys().collect();
+            let expected: Vec<_> = english.keys().collect();
+            assert_eq!(keys, expected, "keys of the `{code}` catalog");
+        }
+    }
+
+    #[test]
+    fn language_fallbacks() {
+        let overrides = BTreeMap::from([
+            (
+                "pt-BR".to_string(),
+                BTreeMap::from([("print-book".to_string(), "Imprimir este livro".to_string())]),
+            ),
+            (
+                "de".to_string(),
+                BTreeMap::from([("next-chapter".to_string(), "Weiter".to_string())]),
+            ),
+        ]);
+
+        let strings = UiStrings::new(None, &overrides);
+        assert_eq!(strings.get("print-book"), Some("Print this book"));
+        assert_eq!(strings.get("unknown"), None);
+
+        let strings = UiStrings::new(Some("de-AT"), &overrides);
+        assert_eq!(strings.get("print-book"), Some("Dieses Buch drucken"));
+        assert_eq!(strings.get("next-chapter"), Some("Weiter"));
+
+        let strings = UiStrings::new(Some("pt-BR"), &overrides);
+        assert_eq!(strings.get("print-book"), Some("Imprimir este livro"));
+        assert_eq!(strings.get("next-chapter"), Some("Next chapter"));
+    }
+
+    #[test]
+    fn render_with_placeholders() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("t", Box::new(UiStrings::new(Some("fr"), &BTreeMap::new())));
+        let rendered = handlebars
+            .render_template(
+                r#"{{t "help-show" key="<kbd>?</kbd>"}} {{t "help-navigate"}}"#,
+                &(),
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "Appuyez sur <kbd>?</kbd> pour afficher cette aide \
+             Appuyez sur {left} ou {right} pour passer d’un chapitre à l’autre"
+        );
+
+        let err = handlebars
+            .render_template(r#"{{t "other"}}"#, &())
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown UI string `other`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn javascript_defaults_match_english() {
+        let js = include_str!("../../../front-end/js/book.js");
+        let searcher = include_str!("../../../front-end/searcher/searcher.js");
+        let english = parse_catalog("en", CATALOGS[0].1);
+
+        let entry = regex::Regex::new(r"(?m)^    '([a-z-]+)': '(.*)',$").unwrap();
+        let defaults: BTreeMap<_, _> = entry
+            .captures_iter(js)
+            .map(|cap| (cap[1].to_string(), cap[2].replace("\\'", "'")))
+            .collect();
+        assert!(!defaults.is_empty());
+        for (key, value) in &defaults {
+            assert_eq!(Some(value), english.get(key), "default of `{key}`");
+        }
+
+        let used = regex::Regex::new(r"mdbook_ui_string\('([a-z-]+)'").unwrap();
+        for cap in used.captures_iter(js).chain(used.captures_iter(searcher)) {
+            assert!(
+                defaults.contains_key(&cap[1]),
+                "no default for `{}`",
+                &cap[1]
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod cache;
 mod hbs_renderer;
 mod helpers;
 #[cfg(feature = "search")]
@@ -5,3 +6,5 @@ mod search;
 mod static_files;
 
 pub use self::hbs_renderer::HtmlHandlebars;
+#[cfg(feature = "search")]
+pub use self::search::{SearchResult, search_book};
@@ -0,0 +1,470 @@
+//! Language-specific tokenization and normalization for the search index.
+//!
+//! English uses the pipeline built into elasticlunr. The other languages use
+//! the `mdbook-*` pipeline functions defined here, whose data is also written
+//! to the search index so that `searcher.js` can normalize the search terms
+//! in exactly the same way.
+
+use super::{MAX_WORD_LENGTH_TO_INDEX, tokenize};
+use elasticlunr::Pipeline;
+use elasticlunr::lang::common::StopWordFilter;
+use elasticlunr::lang::{English, Language};
+use elasticlunr::pipeline::{FnWrapper, PipelineFn};
+use mdbook_core::static_regex;
+use serde::Serialize;
+
+/// A stemming rule: if a word ends with the suffix, and at least the given
+/// number of characters remain without it, the suffix is replaced.
+type Rule = (&'static str, &'static str, usize);
+
+/// Languages with a light stemmer.
+///
+/// The stemmers only strip the common inflectional suffixes, so they don't
+/// reach the quality of a full stemming algorithm, but are simple enough to
+/// be reimplemented exactly in `searcher.js`.
+pub(super) struct Stemmed {
+    code: &'static str,
+    name: &'static str,
+    stop_words: &'static [&'static str],
+    /// In each step, the first matching rule is applied.
+    steps: &'static [&'static [Rule]],
+}
+
+static STEMMED: &[Stemmed] = &[
+    Stemmed {
+        code: "de",
+        name: "German",
+        stop_words: &[
+            "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "da", "das",
+            "dass", "dem", "den", "der", "des", "die", "du", "ein", "eine", "einem", "einen",
+            "einer", "eines", "er", "es", "für", "hat", "ich", "im", "in", "ist", "mit", "nach",
+            "nicht", "noch", "nur", "oder", "sich", "sie", "sind", "so", "um", "und", "von", "vor",
+            "war", "wie", "wir", "wird", "zu", "zum", "zur",
+        ],
+        steps: &[
+            &[
+                ("ern", "", 3),
+                ("em", "", 3),
+                ("en", "", 3),
+                ("er", "", 3),
+                ("es", "", 3),
+                ("e", "", 3),
+            ],
+            &[("er", "", 3), ("en", "", 3)],
+        ],
+    },
+    Stemmed {
+        code: "es",
+        name: "Spanish",
+        stop_words: &[
+            "a", "al", "como", "con", "de", "del", "el", "en", "es", "esta", "este", "la", "las",
+            "lo", "los", "más", "no", "o", "para", "pero", "por", "que", "se", "si", "sin",
+            "sobre", "su", "sus", "un", "una", "uno", "y", "ya",
+        ],
+        steps: &[
+            &[("ces", "z", 2), ("es", "", 3), ("s", "", 3)],
+            &[("o", "", 3), ("a", "", 3), ("e", "", 3)],
+        ],
+    },
+    Stemmed {
+        code: "fr",
+        name: "French",
+        stop_words: &[
+            "à", "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "est",
+            "et", "il", "ils", "je", "la", "le", "les", "leur", "mais", "ne", "nous", "on", "ou",
+            "par", "pas", "pour", "qui", "que", "sa", "se", "son", "sont", "sur", "un", "une",
+            "vous",
+        ],
+        steps: &[
+            &[("aux", "al", 2), ("x", "", 3), ("s", "", 3)],
+            &[
+                ("ement", "", 3),
+                ("ation", "", 3),
+                ("euse", "", 3),
+                ("ique", "", 3),
+                ("eur", "", 3),
+                ("ee", "", 3),
+                ("er", "", 3),
+                ("ez", "", 3),
+                ("e", "", 3),
+            ],
+        ],
+    },
+    Stemmed {
+        code: "it",
+        name: "Italian",
+        stop_words: &[
+            "a", "al", "alla", "che", "con", "da", "del", "della", "di", "e", "è", "gli", "i",
+            "il", "in", "la", "le", "lo", "ma", "non", "o", "per", "più", "se", "si", "su", "un",
+            "una", "uno",
+        ],
+        steps: &[&[
+            ("che", "c", 3),
+            ("chi", "c", 3),
+            ("ghe", "g", 3),
+            ("ghi", "g", 3),
+            ("i", "", 3),
+            ("e", "", 3),
+            ("a", "", 3),
+            ("o", "", 3),
+        ]],
+    },
+    Stemmed {
+        code: "pt",
+        name: "Portuguese",
+        stop_words: &[
+            "a", "ao", "as", "com", "como", "da", "das", "de", "do", "dos", "e", "é", "em", "há",
+            "mais", "mas", "na", "nas", "no", "nos", "não", "o", "os", "ou", "para", "pela",
+            "pelo", "por", "que", "se", "sem", "um", "uma",
+        ],
+        steps: &[
+            &[
+                ("oes", "ao", 2),
+                ("aes", "ao", 2),
+                ("ais", "al", 2),
+                ("eis", "el", 2),
+                ("ns", "m", 2),
+                ("s", "", 3),
+            ],
+            &[("a", "", 3), ("o", "", 3), ("e", "", 3)],
+        ],
+    },
+];
+
+/// Characters replaced before stemming, so that a search matches whether or
+/// not the accents are typed.
+static FOLD: &[(char, &str)] = &[
+    ('à', "a"),
+    ('á', "a"),
+    ('â', "a"),
+    ('ã', "a"),
+    ('ä', "a"),
+    ('å', "a"),
+    ('æ', "ae"),
+    ('ç', "c"),
+    ('è', "e"),
+    ('é', "e"),
+    ('ê', "e"),
+    ('ë', "e"),
+    ('ì', "i"),
+    ('í', "i"),
+    ('î', "i"),
+    ('ï', "i"),
+    ('ñ', "n"),
+    ('ò', "o"),
+    ('ó', "o"),
+    ('ô', "o"),
+    ('õ', "o"),
+    ('ö', "o"),
+    ('œ', "oe"),
+    ('ß', "ss"),
+    ('ù', "u"),
+    ('ú', "u"),
+    ('û', "u"),
+    ('ü', "u"),
+    ('ý', "y"),
+    ('ÿ', "y"),
+];
+
+/// Primary language subtags of the languages written without spaces between
+/// words, which are split into overlapping pairs of characters.
+static CJK: &[(&str, &str)] = &[("zh", "Chinese"), ("ja", "Japanese"), ("ko", "Korean")];
+
+/// The tokenization and normalization of the search index for the language of
+/// the book.
+pub(super) enum SearchLanguage {
+    /// English, or no language.
+    English,
+    /// A language with a light stemmer and stop words.
+    Stemmed(&'static Stemmed),
+    /// Chinese, Japanese or Korean.
+    Cjk(&'static str, &'static str),
+    /// Any other language, which is only split into words.
+    Other(String),
+}
+
+impl SearchLanguage {
+    /// Returns the search language for the `book.language` setting.
+    pub(super) fn new(language: Option<&str>) -> SearchLanguage {
+        let Some(language) = language else {
+            return SearchLanguage::English;
+        };
+        let primary = language.split(['-', '_']).next().unwrap().to_lowercase();
+        if primary.is_empty() || primary == "en" {
+            SearchLanguage::English
+        } else if let Some(stemmed) = STEMMED.iter().find(|s| s.code == primary) {
+            SearchLanguage::Stemmed(stemmed)
+        } else if let Some((code, name)) = CJK.iter().find(|(code, _)| *code == primary) {
+            SearchLanguage::Cjk(code, name)
+        } else {
+            SearchLanguage::Other(primary)
+        }
+    }
+
+    /// Whether the text is split into pairs of characters rather than words.
+    pub(super) fn is_cjk(&self) -> bool {
+        matches!(self, SearchLanguage::Cjk(..))
+    }
+
+    /// The data `searcher.js` needs to normalize search terms like the index,
+    /// or `None` for English, which is built into elasticlunr.js.
+    pub(super) fn to_json(&self) -> Option<LanguageJson> {
+        let (code, tokenizer, stop_words, stemmer) = match self {
+            SearchLanguage::English => return None,
+            SearchLanguage::Stemmed(stemmed) => (
+                stemmed.code,
+                "words",
+                stemmed.stop_words,
+                Some(StemmerJson::Rules {
+                    fold: FOLD,
+                    steps: stemmed.steps,
+                }),
+            ),
+            SearchLanguage::Cjk(code, _) => (*code, "cjk", &[][..], Some(StemmerJson::English)),
+            SearchLanguage::Other(code) => (code.as_str(), "words", &[][..], None),
+        };
+        Some(LanguageJson {
+            code: code.to_string(),
+            tokenizer,
+            stop_words,
+            stemmer,
+        })
+    }
+}
+
+/// The language settings written to the search index.
+#[derive(Serialize)]
+pub(super) struct LanguageJson {
+    code: String,
+    /// Either `words` or `cjk`.
+    tokenizer: &'static str,
+    /// The words dropped by `mdbook-stop-words`.
+    stop_words: &'static [&'static str],
+    /// How `mdbook-stemmer` normalizes words, if it is in the pipeline.
+    stemmer: Option<StemmerJson>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum StemmerJson {
+    /// The English stemmer of elasticlunr, applied to ASCII words only.
+    English,
+    /// Characters to replace, then the steps of rules.
+    Rules {
+        fold: &'static [(char, &'static str)],
+        steps: &'static [&'static [Rule]],
+    },
+}
+
+impl Language for SearchLanguage {
+    fn name(&self) -> String {
+        match self {
+            SearchLanguage::English => "English".into(),
+            SearchLanguage::Stemmed(stemmed) => stemmed.name.into(),
+            SearchLanguage::Cjk(_, name) => (*name).into(),
+            SearchLanguage::Other(code) => code.clone(),
+        }
+    }
+
+    fn code(&self) -> String {
+        match self {
+            SearchLanguage::English => "en".into(),
+            SearchLanguage::Stemmed(stemmed) => stemmed.code.into(),
+            SearchLanguage::Cjk(code, _) => (*code).into(),
+            SearchLanguage::Other(code) => code.clone(),
+        }
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        match self {
+            SearchLanguage::Cjk(..) => tokenize_cjk(text),
+            _ => tokenize(text),
+        }
+    }
+
+    fn make_pipeline(&self) -> Pipeline {
+        let trimmer =
+            || -> Box<dyn PipelineFn> { Box::new(FnWrapper("mdbook-trimmer".into(), trim)) };
+        let queue = match self {
+            SearchLanguage::English => return English::new().make_pipeline(),
+            SearchLanguage::Stemmed(stemmed) => vec![
+                trimmer(),
+                Box::new(StopWordFilter::new("mdbook-stop-words", stemmed.stop_words)),
+                Box::new(Stemmer::Rules(stemmed.steps)),
+            ],
+            SearchLanguage::Cjk(..) => {
+                // Technical writing in these languages is often mixed with
+                // English words, so those are still normalized.
+                let mut english = English::new().make_pipeline().queue;
+                let stemmer = english.pop().unwrap();
+                let stop_word_filter = english.pop().unwrap();
+                vec![
+                    trimmer(),
+                    stop_word_filter,
+                    Box::new(Stemmer::English(stemmer)),
+                ]
+            }
+            SearchLanguage::Other(_) => vec![trimmer()],
+        };
+        Pipeline { queue }
+    }
+}
+
+/// Removes the non-word characters at the start and end of a token.
+///
+/// Unlike the English trimmer, this keeps all letters, not just ASCII ones.
+fn trim(token: String) -> Option<String> {
+    let trimmed = token.trim_matches(|c: char| !c.is_alphanumeric() && c != '_');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// Tokenizes like [`tokenize`], but also splits runs of Chinese, Japanese and
+/// Korean characters into overlapping pairs of characters.
+///
+/// Text in these languages has no spaces between words, and pairs of
+/// characters match most words without needing a dictionary.
+pub(super) fn tokenize_cjk(text: &str) -> Vec<String> {
+    static_regex!(
+        RUNS,
+        r"(?<cjk>[\p{sc=Han}\p{sc=Hiragana}\p{sc=Katakana}\p{sc=Hangul}ー]+)|[^\p{sc=Han}\p{sc=Hiragana}\p{sc=Katakana}\p{sc=Hangul}ー]+"
+    );
+    let mut tokens = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == '-') {
+        let word = word.to_lowercase();
+        for caps in RUNS.captures_iter(&word) {
+            let run = &caps[0];
+            let chars: Vec<char> = run.chars().collect();
+            if caps.name("cjk").is_none() || chars.len() == 1 {
+                tokens.push(run.to_string());
+            } else {
+                tokens.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+            }
+        }
+    }
+    tokens.retain(|token| token.len() <= MAX_WORD_LENGTH_TO_INDEX);
+    tokens
+}
+
+/// The `mdbook-stemmer` pipeline function.
+enum Stemmer {
+    English(Box<dyn PipelineFn>),
+    Rules(&'static [&'static [Rule]]),
+}
+
+impl PipelineFn for Stemmer {
+    fn name(&self) -> String {
+        "mdbook-stemmer".into()
+    }
+
+    fn filter(&self, token: String) -> Option<String> {
+        match self {
+            Stemmer::English(stemmer) if token.is_ascii() => stemmer.filter(token),
+            Stemmer::English(_) => Some(token),
+            Stemmer::Rules(steps) => Some(stem(&token, steps)),
+        }
+    }
+}
+
+/// Folds the accents of `word`, then applies the first matching rule of each
+/// step.
+fn stem(word: &str, steps: &[&[Rule]]) -> String {
+    let mut word: String = word
+        .chars()
+        .map(|c| match FOLD.iter().find(|(from, _)| *from == c) {
+            Some((_, to)) => to.to_string(),
+            None => c.to_string(),
+        })
+        .collect();
+    for rules in steps {
+        for (suffix, replacement, min) in *rules {
+            if let Some(stem) = word.strip_suffix(suffix)
+                && stem.chars().count() >= *min
+            {
+                word = format!("{stem}{replacement}");
+                break;
+            }
+        }
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(language: &str, text: &str) -> Vec<String> {
+        let language = SearchLanguage::new(Some(language));
+        language.make_pipeline().run(language.tokenize(text))
+    }
+
+    #[test]
+    fn language_lookup() {
+        assert_eq!(SearchLanguage::new(None).code(), "en");
+        assert_eq!(SearchLanguage::new(Some("en-GB")).name(), "English");
+        assert_eq!(SearchLanguage::new(Some("pt-BR")).name(), "Portuguese");
+        assert_eq!(SearchLanguage::new(Some("zh-Hans")).name(), "Chinese");
+        assert_eq!(SearchLanguage::new(Some("ru")).name(), "ru");
+        assert!(SearchLanguage::new(Some("en")).to_json().is_none());
+    }
+
+    #[test]
+    fn stemming() {
+        assert_eq!(
+            normalize("de", "Die Einstellungen der Bücher"),
+            ["einstellung", "buch"]
+        );
+        assert_eq!(
+            normalize("de", "Buch Häuser Haus"),
+            ["buch", "haus", "haus"]
+        );
+        assert_eq!(
+            normalize("fr", "configuration configurations, configurer configurée"),
+            ["configur", "configur", "configur", "configur"]
+        );
+        assert_eq!(
+            normalize("es", "libros libro las luces"),
+            ["libr", "libr", "luz"]
+        );
+        assert_eq!(
+            normalize("pt", "configurações configuração"),
+            ["configuraca", "configuraca"]
+        );
+        assert_eq!(
+            normalize("it", "libri libro amiche amica"),
+            ["libr", "libr", "amic", "amic"]
+        );
+    }
+
+    #[test]
+    fn cjk_tokenize() {
+        assert_eq!(tokenize_cjk("配置文件"), ["配置", "置文", "文件"]);
+        assert_eq!(tokenize_cjk("书"), ["书"]);
+        // Sentences are longer than the words of other languages.
+        assert_eq!(tokenize_cjk(&"书".repeat(100)).len(), 99);
+        assert_eq!(
+            tokenize_cjk("使用mdBook-CLI構築"),
+            ["使用", "mdbook", "cli", "構築"]
+        );
+        assert_eq!(
+            tokenize_cjk("コンピューター"),
+            ["コン", "ンピ", "ピュ", "ュー", "ータ", "ター"]
+        );
+    }
+
+    #[test]
+    fn cjk_pipeline() {
+        assert_eq!(
+            normalize("ja", "「設定」を configuring the book。"),
+            ["設定", "を", "configur", "book"]
+        );
+    }
+
+    #[test]
+    fn other_languages_keep_letters() {
+        assert_eq!(normalize("ru", "«Книга» и книги."), ["книга", "и", "книги"]);
+    }
+}
@@ -1,19 +1,26 @@
+use self::language::SearchLanguage;
+use self::synonyms::{Synonyms, WithSynonyms};
 use super::static_files::StaticFiles;
-use crate::html::{ChapterTree, Node};
+use crate::html::{ChapterTree, Element, Node, build_trees};
 use crate::theme::searcher;
 use crate::utils::ToUrlPath;
 use anyhow::{Result, bail};
 use ego_tree::iter::Edge;
-use elasticlunr::{Index, IndexBuilder};
-use mdbook_core::book::Chapter;
-use mdbook_core::config::{Search, SearchChapterSettings};
+use elasticlunr::{Index, IndexBuilder, Language};
+use mdbook_core::book::{Book, Chapter};
+use mdbook_core::config::{Config, HtmlConfig, Search, SearchChapterSettings};
 use mdbook_core::static_regex;
 use serde::Serialize;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
+mod language;
+mod query;
+mod shards;
+mod synonyms;
+
 const MAX_WORD_LENGTH_TO_INDEX: usize = 80;
 
 /// Tokenizes in the same way as elasticlunr-rs (for English), but also drops long tokens.
@@ -26,15 +33,151 @@ fn tokenize(text: &str) -> Vec<String> {
 }
 
 /// Creates all files required for search.
+///
+/// `language` is the `book.language` setting, which selects how the text is
+/// split into words and normalized.
 pub(super) fn create_files(
     search_config: &Search,
+    language: Option<&str>,
     static_files: &mut StaticFiles,
     chapter_trees: &[ChapterTree<'_>],
 ) -> Result<()> {
-    let mut index = IndexBuilder::new()
-        .add_field_with_tokenizer("title", Box::new(&tokenize))
-        .add_field_with_tokenizer("body", Box::new(&tokenize))
-        .add_field_with_tokenizer("breadcrumbs", Box::new(&tokenize))
+    let mut index = build_index(search_config, language, chapter_trees)?;
+    let mut json = serde_json::to_string(&index)?;
+    let mut shards = Vec::new();
+    if json.len() as u64 > search_config.shard_threshold {
+        shards = shards::split(&mut index)?;
+        json = serde_json::to_string(&index)?;
+        debug!("Splitting search index into {} shards ✓", shards.len());
+    } else if json.len() > 10_000_000 {
+        warn!("search index is very large ({} bytes)", json.len());
+    }
+    debug!("Writing search index ✓");
+
+    if search_config.copy_js {
+        // To reduce the size of the generated JSON by preventing all `"` characters to be
+        // escaped, we instead surround the string with much less common `'` character.
+        let mut searchindex = format!(
+            "window.search = Object.assign(window.search, JSON.parse('{}'));",
+            json.replace("\\", "\\\\").replace("'", "\\'")
+        );
+        if !shards.is_empty() {
+            // The shards are named with `resource` directives, so that they
+            // are fingerprinted along with the other static files.
+            let urls: Vec<_> = shards
+                .iter()
+                .map(|shard| {
+                    let name = &shard.name;
+                    format!("{name}: \"{{{{ resource \"searchindex/{name}.js\" }}}}\"")
+                })
+                .collect();
+            searchindex.push_str(&format!(
+                "\nwindow.search.shard_urls = {{{}}};",
+                urls.join(", ")
+            ));
+        }
+        static_files.add_builtin("searchindex.js", searchindex.as_bytes());
+        for shard in &shards {
+            static_files.add_builtin(
+                &format!("searchindex/{}.js", shard.name),
+                shard.js.as_bytes(),
+            );
+        }
+        static_files.add_builtin("searcher.js", searcher::JS);
+        static_files.add_builtin("mark.min.js", searcher::MARK_JS);
+        static_files.add_builtin("elasticlunr.min.js", searcher::ELASTICLUNR_JS);
+        debug!("Copying search files ✓");
+    }
+
+    Ok(())
+}
+
+/// A result of [`search_book`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SearchResult {
+    /// The chapter and headings of the result, separated by ` » `.
+    pub breadcrumbs: String,
+    /// The URL of the result, relative to the root of the book.
+    pub url: String,
+    /// The part of the text of the result with the most search terms, on one
+    /// line.
+    pub teaser: String,
+    /// The score the results are ranked by.
+    pub score: f64,
+}
+
+/// Searches the book in the same way as the search of the HTML output.
+///
+/// The search index is built with the `output.html.search` settings of
+/// `config`, and at most `limit-results` results are returned, best first.
+pub fn search_book(book: &Book, config: &Config, query: &str) -> Result<Vec<SearchResult>> {
+    let html_config = config.get::<HtmlConfig>("output.html")?.unwrap_or_default();
+    let search_config = html_config.search.clone().unwrap_or_default();
+    if !search_config.enable {
+        bail!("search is disabled by `output.html.search.enable`");
+    }
+    let chapter_trees = build_trees(book, &html_config, config.rust.edition);
+    let language = config.book.language.as_deref();
+    let index = build_index(&search_config, language, &chapter_trees)?;
+
+    let language = SearchLanguage::new(language);
+    let synonyms = Synonyms::new(&search_config.synonyms, &language)?;
+    let language = WithSynonyms::new(language, synonyms);
+    let pipeline = language.make_pipeline();
+    let normalize = |word: String| pipeline.run(vec![word.clone()]).pop().unwrap_or(word);
+    let tokens = pipeline.run(language.tokenize(query));
+    let terms: Vec<_> = query
+        .split_whitespace()
+        .map(|term| normalize(term.to_lowercase()))
+        .collect();
+    let is_term = |word: &str| {
+        let word = normalize(word.to_string());
+        terms.iter().any(|term| {
+            if language.language.is_cjk() {
+                // Words are not separated by spaces, so the term can be anywhere.
+                word.contains(term.as_str())
+            } else {
+                word.starts_with(term.as_str())
+            }
+        })
+    };
+
+    let docs = &index["index"]["documentStore"]["docs"];
+    let results = query::search(&index, &search_config, &tokens)
+        .into_iter()
+        .take(search_config.limit_results as usize)
+        .map(|(doc_ref, score)| {
+            let doc = &docs[doc_ref.to_string()];
+            let field = |name: &str| doc[name].as_str().unwrap_or_default().to_string();
+            let body = field("body");
+            let teaser = query::teaser(&body, search_config.teaser_word_count as usize, &is_term);
+            SearchResult {
+                breadcrumbs: field("breadcrumbs"),
+                url: index["doc_urls"][doc_ref]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                teaser: teaser.split_whitespace().collect::<Vec<_>>().join(" "),
+                score,
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Builds the JSON of the search index for `searcher.js`.
+fn build_index(
+    search_config: &Search,
+    language: Option<&str>,
+    chapter_trees: &[ChapterTree<'_>],
+) -> Result<serde_json::Value> {
+    let language = SearchLanguage::new(language);
+    let language_json = language.to_json();
+    let synonyms = Synonyms::new(&search_config.synonyms, &language)?;
+    let synonyms_json = synonyms.to_json();
+    let mut index = IndexBuilder::with_language(Box::new(WithSynonyms::new(language, synonyms)))
+        .add_fields(["title", "body", "breadcrumbs"])
         .build();
 
     // These are links to all of the headings in all of the chapters.
@@ -42,6 +185,9 @@ pub(super) fn create_files(
 
     let chapter_configs = sort_search_config(&search_config.chapter);
     validate_chapter_config(&chapter_configs, chapter_trees)?;
+    let exclusions = Exclusions::new(search_config)?;
+    // The boosts of the documents of the chapters with a `boost`.
+    let mut doc_boosts = BTreeMap::new();
 
     for ct in chapter_trees {
         let path = settings_path(ct.chapter);
@@ -49,33 +195,21 @@ pub(super) fn create_files(
         if !chapter_settings.enable.unwrap_or(true) {
             continue;
         }
-        index_chapter(&mut index, search_config, &mut doc_urls, ct)?;
-    }
-
-    let index = write_to_json(index, search_config, doc_urls)?;
-    debug!("Writing search index ✓");
-    if index.len() > 10_000_000 {
-        warn!("search index is very large ({} bytes)", index.len());
-    }
-
-    if search_config.copy_js {
-        static_files.add_builtin(
-            "searchindex.js",
-            // To reduce the size of the generated JSON by preventing all `"` characters to be
-            // escaped, we instead surround the string with much less common `'` character.
-            format!(
-                "window.search = Object.assign(window.search, JSON.parse('{}'));",
-                index.replace("\\", "\\\\").replace("'", "\\'")
-            )
-            .as_bytes(),
-        );
-        static_files.add_builtin("searcher.js", searcher::JS);
-        static_files.add_builtin("mark.min.js", searcher::MARK_JS);
-        static_files.add_builtin("elasticlunr.min.js", searcher::ELASTICLUNR_JS);
-        debug!("Copying search files ✓");
+        let first_doc = doc_urls.len();
+        index_chapter(&mut index, search_config, &exclusions, &mut doc_urls, ct)?;
+        if let Some(boost) = chapter_settings.boost.filter(|boost| *boost != 1.0) {
+            doc_boosts.extend((first_doc..doc_urls.len()).map(|doc_ref| (doc_ref, boost)));
+        }
     }
 
-    Ok(())
+    write_to_json(
+        index,
+        search_config,
+        language_json,
+        synonyms_json,
+        doc_boosts,
+        doc_urls,
+    )
 }
 
 /// Uses the given arguments to construct a search document, then inserts it to the given index.
@@ -103,6 +237,7 @@ fn add_doc(
 fn index_chapter(
     index: &mut Index,
     search_config: &Search,
+    exclusions: &Exclusions,
     doc_urls: &mut Vec<String>,
     chapter_tree: &ChapterTree<'_>,
 ) -> Result<()> {
@@ -118,10 +253,18 @@ fn index_chapter(
     breadcrumbs.push(chapter_tree.chapter.name.clone());
 
     let mut traverse = chapter_tree.tree.root().traverse();
+    // Whether the text is between `<!-- search:off -->` and `<!-- search:on -->`.
+    let mut search_off = false;
 
     while let Some(edge) = traverse.next() {
         match edge {
             Edge::Open(node) => match node.value() {
+                Node::Comment(comment) => match comment.trim() {
+                    "search:off" => search_off = true,
+                    "search:on" => search_off = false,
+                    _ => {}
+                },
+                _ if search_off => {}
                 Node::Element(el) => {
                     if let Some(level) = el.heading_level()
                         && level <= max_section_depth
@@ -143,7 +286,7 @@ fn index_chapter(
                         }
                         section_id = Some(heading_id);
                         in_heading = true;
-                    } else if matches!(el.name(), "script" | "style") {
+                    } else if matches!(el.name(), "script" | "style") || exclusions.excludes(el) {
                         // Skip this node.
                         while let Some(edge) = traverse.next() {
                             if let Edge::Close(close) = edge
@@ -167,10 +310,10 @@ fn index_chapter(
                         body.push_str(text);
                     }
                 }
-                Node::Comment(_) => {}
                 Node::Fragment => {}
                 Node::RawData(_) => {}
             },
+            Edge::Close(_) if search_off => {}
             Edge::Close(node) => match node.value() {
                 Node::Element(el) => {
                     if let Some(level) = el.heading_level()
@@ -208,9 +351,15 @@ fn index_chapter(
     Ok(())
 }
 
-fn write_to_json(index: Index, search_config: &Search, doc_urls: Vec<String>) -> Result<String> {
+fn write_to_json(
+    index: Index,
+    search_config: &Search,
+    language: Option<language::LanguageJson>,
+    synonyms: Option<BTreeMap<String, String>>,
+    doc_boosts: BTreeMap<usize, f64>,
+    doc_urls: Vec<String>,
+) -> Result<serde_json::Value> {
     use elasticlunr::config::{SearchBool, SearchOptions, SearchOptionsField};
-    use std::collections::BTreeMap;
 
     #[derive(Serialize)]
     struct ResultsOptions {
@@ -224,6 +373,15 @@ fn write_to_json(index: Index, search_config: &Search, doc_urls: Vec<String>) ->
         results_options: ResultsOptions,
         /// The searchoptions for elasticlunr.js
         search_options: SearchOptions,
+        /// How searcher.js normalizes search terms, if not English
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<language::LanguageJson>,
+        /// The first word of the group of each synonym, for `mdbook-synonyms`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        synonyms: Option<BTreeMap<String, String>>,
+        /// The boosts of the documents in chapters with a `boost`, by their ref
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        doc_boosts: BTreeMap<usize, f64>,
         /// Used to lookup a document's URL from an integer document ref.
         doc_urls: Vec<String>,
         /// The index for elasticlunr.js
@@ -258,6 +416,9 @@ fn write_to_json(index: Index, search_config: &Search, doc_urls: Vec<String>) ->
     let json_contents = SearchindexJson {
         results_options,
         search_options,
+        language,
+        synonyms,
+        doc_boosts,
         doc_urls,
         index,
     };
@@ -265,11 +426,50 @@ fn write_to_json(index: Index, search_config: &Search, doc_urls: Vec<String>) ->
     // By converting to serde_json::Value as an intermediary, we use a
     // BTreeMap internally and can force a stable ordering of map keys.
     let json_contents = serde_json::to_value(&json_contents)?;
-    let json_contents = serde_json::to_string(&json_contents)?;
 
     Ok(json_contents)
 }
 
+/// The elements that are left out of the search index.
+struct Exclusions {
+    code: bool,
+    classes: Vec<String>,
+}
+
+impl Exclusions {
+    fn new(search_config: &Search) -> Result<Exclusions> {
+        let mut classes = search_config.exclude_classes.clone();
+        for kind in &search_config.exclude_admonitions {
+            if !matches!(
+                kind.as_str(),
+                "note" | "tip" | "important" | "warning" | "caution"
+            ) {
+                bail!(
+                    "[output.html.search] unknown admonition kind `{kind}` in `exclude-admonitions`, \
+                     expected one of `note`, `tip`, `important`, `warning`, or `caution`"
+                );
+            }
+            classes.push(format!("blockquote-tag-{kind}"));
+        }
+        Ok(Exclusions {
+            code: search_config.exclude_code,
+            classes,
+        })
+    }
+
+    /// Whether the element and its contents are left out of the index.
+    fn excludes(&self, el: &Element) -> bool {
+        if self.code && el.name() == "pre" {
+            return true;
+        }
+        el.attr("class").is_some_and(|class| {
+            class
+                .split_ascii_whitespace()
+                .any(|class| self.classes.iter().any(|excluded| excluded == class))
+        })
+    }
+}
+
 fn settings_path(ch: &Chapter) -> &Path {
     ch.source_path
         .as_deref()
@@ -280,7 +480,17 @@ fn validate_chapter_config(
     chapter_configs: &[(PathBuf, SearchChapterSettings)],
     chapter_trees: &[ChapterTree<'_>],
 ) -> Result<()> {
-    for (path, _) in chapter_configs {
+    for (path, settings) in chapter_configs {
+        if settings
+            .boost
+            .is_some_and(|boost| !boost.is_finite() || boost < 0.0)
+        {
+            bail!(
+                "[output.html.search.chapter] key `{}` has an invalid `boost`, \
+                 expected a number of 0 or more",
+                path.display()
+            );
+        }
         let found = chapter_trees
             .iter()
             .any(|ct| settings_path(ct.chapter).starts_with(path));
@@ -315,6 +525,7 @@ fn get_chapter_settings(
     for (path, config) in chapter_configs {
         if source_path.starts_with(path) {
             result.enable = config.enable.or(result.enable);
+            result.boost = config.boost.or(result.boost);
         }
     }
     result
@@ -359,6 +570,28 @@ fn chapter_settings_priority() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn chapter_boost() {
+        let cfg = r#"
+            [output.html.search.chapter]
+            "guide" = { boost = 2 }
+            "guide/installation.md" = { boost = 0.5 }
+            "guide/reading.md" = { enable = false }
+        "#;
+        let cfg: mdbook_core::config::Config = toml::from_str(cfg).unwrap();
+        let html = cfg.html_config().unwrap();
+        let chapter_configs = sort_search_config(&html.search.unwrap().chapter);
+        for (path, boost) in [
+            ("intro.md", None),
+            ("guide/index.md", Some(2.0)),
+            ("guide/installation.md", Some(0.5)),
+            ("guide/reading.md", Some(2.0)),
+        ] {
+            let settings = get_chapter_settings(&chapter_configs, Path::new(path));
+            assert_eq!(settings.boost, boost, "{path}");
+        }
+    }
+
     #[test]
     fn test_tokenize_basic() {
         assert_eq!(tokenize("hello world"), vec!["hello", "world"]);
@@ -0,0 +1,286 @@
+//! Searching the JSON of the search index, for `mdbook search`.
+//!
+//! This follows the search of elasticlunr.js and the teasers of
+//! `searcher.js`, so that the results are ranked and summarized in the same
+//! way as in the search of the book.
+
+use mdbook_core::config::Search;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Searches the index for the normalized tokens of a query.
+///
+/// Returns the refs of the matching documents with their scores, best first.
+pub(super) fn search(
+    index: &Value,
+    search_config: &Search,
+    tokens: &[String],
+) -> Vec<(usize, f64)> {
+    // elasticlunr.js goes through the fields in the order of the search
+    // options, which are sorted by name.
+    let fields = [
+        ("body", search_config.boost_paragraph),
+        ("breadcrumbs", search_config.boost_hierarchy),
+        ("title", search_config.boost_title),
+    ];
+    let mut scores: BTreeMap<usize, f64> = BTreeMap::new();
+    for (field, boost) in fields {
+        if boost == 0 {
+            continue;
+        }
+        for (doc_ref, score) in field_search(index, field, search_config, tokens) {
+            *scores.entry(doc_ref).or_default() += score * f64::from(boost);
+        }
+    }
+    // The chapters with a `boost` multiply the scores of their documents.
+    for (doc_ref, score) in &mut scores {
+        if let Some(boost) = index["doc_boosts"][doc_ref.to_string()].as_f64() {
+            *score *= boost;
+        }
+    }
+    let mut results: Vec<_> = scores.into_iter().collect();
+    // The sort is stable, so equal scores stay in the order of the refs.
+    results.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    results
+}
+
+/// Scores the documents that match the tokens in one field.
+fn field_search(
+    index: &Value,
+    field: &str,
+    search_config: &Search,
+    tokens: &[String],
+) -> BTreeMap<usize, f64> {
+    let root = &index["index"]["index"][field]["root"];
+    let store = &index["index"]["documentStore"];
+    let doc_count = store["length"].as_f64().unwrap_or_default();
+
+    let mut scores: Option<BTreeMap<usize, f64>> = None;
+    // The number of tokens each document contains, without expansion.
+    let mut doc_tokens: BTreeMap<usize, usize> = BTreeMap::new();
+    for token in tokens {
+        let keys = if search_config.expand {
+            expand_token(root, token)
+        } else {
+            vec![token.clone()]
+        };
+        let mut token_scores: BTreeMap<usize, f64> = BTreeMap::new();
+        for key in &keys {
+            let Some(node) = get_node(root, key) else {
+                continue;
+            };
+            let Some(docs) = node["docs"].as_object() else {
+                continue;
+            };
+            let df = node["df"].as_f64().unwrap_or_default();
+            let idf = 1.0 + (doc_count / (df + 1.0)).ln();
+            for (doc_ref, doc) in docs {
+                let Ok(doc_ref) = doc_ref.parse() else {
+                    continue;
+                };
+                if search_config.use_boolean_and
+                    && scores
+                        .as_ref()
+                        .is_some_and(|scores| !scores.contains_key(&doc_ref))
+                {
+                    continue;
+                }
+                if key == token {
+                    *doc_tokens.entry(doc_ref).or_default() += 1;
+                }
+                let tf = doc["tf"].as_f64().unwrap_or_default();
+                let field_length = store["docInfo"][doc_ref.to_string()][field]
+                    .as_f64()
+                    .unwrap_or_default();
+                let field_length_norm = if field_length == 0.0 {
+                    1.0
+                } else {
+                    1.0 / field_length.sqrt()
+                };
+                // Longer words found by expanding the token score less.
+                let penalty = if key == token {
+                    1.0
+                } else {
+                    let key_len = key.chars().count() as f64;
+                    let token_len = token.chars().count() as f64;
+                    (1.0 - (key_len - token_len) / key_len) * 0.15
+                };
+                *token_scores.entry(doc_ref).or_default() += tf * idf * field_length_norm * penalty;
+            }
+        }
+        scores = Some(match scores {
+            None => token_scores,
+            Some(scores) if search_config.use_boolean_and => token_scores
+                .into_iter()
+                .filter_map(|(doc_ref, score)| Some((doc_ref, scores.get(&doc_ref)? + score)))
+                .collect(),
+            Some(mut scores) => {
+                for (doc_ref, score) in token_scores {
+                    *scores.entry(doc_ref).or_default() += score;
+                }
+                scores
+            }
+        });
+    }
+
+    let mut scores = scores.unwrap_or_default();
+    for (doc_ref, score) in &mut scores {
+        if let Some(count) = doc_tokens.get(doc_ref) {
+            *score = *score * *count as f64 / tokens.len() as f64;
+        }
+    }
+    scores
+}
+
+/// Returns the node of the token in the inverted index of a field.
+fn get_node<'a>(root: &'a Value, token: &str) -> Option<&'a Value> {
+    token
+        .chars()
+        .try_fold(root, |node, c| node.get(c.encode_utf8(&mut [0; 4]) as &str))
+}
+
+/// Returns the token and the longer words starting with it in the index.
+fn expand_token(root: &Value, token: &str) -> Vec<String> {
+    fn expand(node: &Value, word: String, words: &mut Vec<String>) {
+        if node["df"].as_u64().unwrap_or_default() > 0 {
+            words.push(word.clone());
+        }
+        for (key, child) in node.as_object().into_iter().flatten() {
+            if key != "docs" && key != "df" {
+                expand(child, format!("{word}{key}"), words);
+            }
+        }
+    }
+
+    let mut words = Vec::new();
+    if let Some(node) = get_node(root, token).filter(|_| !token.is_empty()) {
+        expand(node, token.to_string(), &mut words);
+    }
+    words
+}
+
+/// Returns the part of the body with the most search terms, in the same way as
+/// `makeTeaser` of `searcher.js`.
+///
+/// `is_term` tells whether a lowercase word of the body matches a search term.
+pub(super) fn teaser(body: &str, word_count: usize, is_term: impl Fn(&str) -> bool) -> &str {
+    const TERM_WEIGHT: u32 = 40;
+    const FIRST_WORD_WEIGHT: u32 = 8;
+    const WORD_WEIGHT: u32 = 2;
+
+    // The start, end, and weight of each word.
+    let mut words = Vec::new();
+    let mut term_found = false;
+    let mut start = 0;
+    for sentence in body.split(". ") {
+        let mut weight = FIRST_WORD_WEIGHT;
+        for word in sentence.split(' ') {
+            if !word.is_empty() {
+                if is_term(&word.to_lowercase()) {
+                    weight = TERM_WEIGHT;
+                    term_found = true;
+                }
+                words.push((start, start + word.len(), weight));
+                weight = WORD_WEIGHT;
+            }
+            start += word.len() + 1;
+        }
+        start += 1;
+    }
+    if words.is_empty() {
+        return body;
+    }
+
+    let window_size = words.len().min(word_count.max(1));
+    let mut first = 0;
+    if term_found {
+        let mut sum: u32 = words[..window_size].iter().map(|word| word.2).sum();
+        let mut max_sum = sum;
+        for i in 1..=words.len() - window_size {
+            sum = sum - words[i - 1].2 + words[i + window_size - 1].2;
+            // Like `searcher.js`, this takes the last of the best windows.
+            if sum >= max_sum {
+                max_sum = sum;
+                first = i;
+            }
+        }
+    }
+    &body[words[first].0..words[first + window_size - 1].1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elasticlunr::IndexBuilder;
+    use serde_json::json;
+
+    fn index(docs: &[(&str, &str)]) -> Value {
+        let mut index = IndexBuilder::new().add_fields(["title", "body"]).build();
+        for (i, (title, body)) in docs.iter().enumerate() {
+            index.add_doc(&i.to_string(), [*title, *body]);
+        }
+        json!({ "index": index })
+    }
+
+    fn tokens(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn ranks_by_fields_and_terms() {
+        let index = index(&[
+            ("Installation", "Download the binary."),
+            ("Configuration", "The book is configured in book.toml."),
+            ("Themes", "A theme changes the book and its configuration."),
+        ]);
+        let mut config = Search::default();
+        config.boost_hierarchy = 0;
+
+        let results = search(&index, &config, &tokens(&["configur"]));
+        let refs: Vec<_> = results.iter().map(|(doc_ref, _)| *doc_ref).collect();
+        assert_eq!(refs, [1, 2]);
+        assert!(results[0].1 > results[1].1);
+
+        // Without expansion, `configur` does not match `configured`.
+        config.expand = false;
+        let results = search(&index, &config, &tokens(&["configur"]));
+        let refs: Vec<_> = results.iter().map(|(doc_ref, _)| *doc_ref).collect();
+        assert_eq!(refs, [1, 2]);
+        let results = search(&index, &config, &tokens(&["config"]));
+        assert!(results.is_empty());
+
+        let results = search(&index, &config, &tokens(&["book", "theme"]));
+        let refs: Vec<_> = results.iter().map(|(doc_ref, _)| *doc_ref).collect();
+        assert_eq!(refs, [2, 1]);
+
+        // The boosts of the chapters multiply the scores.
+        let mut boosted = index.clone();
+        boosted["doc_boosts"] = json!({"1": 0.5, "2": 4.0});
+        let results = search(&boosted, &config, &tokens(&["book", "theme"]));
+        let refs: Vec<_> = results.iter().map(|(doc_ref, _)| *doc_ref).collect();
+        assert_eq!(refs, [2, 1]);
+        let unboosted = search(&index, &config, &tokens(&["book", "theme"]));
+        assert_eq!(results[0].1, unboosted[0].1 * 4.0);
+        assert_eq!(results[1].1, unboosted[1].1 * 0.5);
+
+        config.use_boolean_and = true;
+        let results = search(&index, &config, &tokens(&["book", "download"]));
+        assert!(results.is_empty());
+        let results = search(&index, &config, &tokens(&["book", "theme"]));
+        let refs: Vec<_> = results.iter().map(|(doc_ref, _)| *doc_ref).collect();
+        assert_eq!(refs, [2]);
+    }
+
+    #[test]
+    fn teaser_window() {
+        let body = "One two three. Four five six. Seven eight nine.";
+        assert_eq!(teaser(body, 3, |word| word == "five"), "five six. Seven");
+        assert_eq!(
+            teaser(body, 4, |word| word == "nine."),
+            "six. Seven eight nine."
+        );
+        assert_eq!(teaser(body, 2, |_| false), "One two");
+        assert_eq!(teaser(body, 30, |_| false), body);
+        assert_eq!(teaser("", 30, |_| false), "");
+    }
+}
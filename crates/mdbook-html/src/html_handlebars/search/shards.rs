@@ -0,0 +1,223 @@
+//! Splitting of large search indexes into shards that are loaded on demand.
+//!
+//! The terms of the index are grouped by their first character, so that a
+//! search only needs the shards for the first characters of its words. This
+//! includes the longer words found with `expand`, since they start with the
+//! same character. The characters are packed into shards of about the same
+//! size, and `shards.terms` in the index maps each character to its shard.
+//! The documents are only needed to display the results, so they are loaded
+//! once the results are known.
+//!
+//! Each shard is a script which passes the gzip-compressed JSON of the shard
+//! in base64 to `window.search.addShard` of `searcher.js`.
+
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde_json::{Map, Value, json};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// The approximate size in bytes of the JSON of a shard.
+const SHARD_SIZE: usize = 500_000;
+
+/// A shard of the search index.
+pub(super) struct Shard {
+    /// The name `searcher.js` uses for the shard, such as `t3` for the
+    /// fourth shard of terms and `d0` for the first shard of documents.
+    pub(super) name: String,
+    /// The contents of the script of the shard.
+    pub(super) js: String,
+}
+
+/// The children of the roots of the fields for one character.
+#[derive(Default)]
+struct CharNodes<'a> {
+    /// The node of each field.
+    nodes: Vec<(&'a str, Value)>,
+    /// The total size in bytes of the JSON of the nodes.
+    size: usize,
+}
+
+/// Moves the terms and documents out of the JSON of the search index into
+/// shards.
+///
+/// The documents are left in the document store as `null`, since
+/// elasticlunr.js needs them to score the results.
+pub(super) fn split(index: &mut Value) -> Result<Vec<Shard>> {
+    let fields: Vec<String> = serde_json::from_value(index["index"]["fields"].clone())?;
+
+    let mut chars: BTreeMap<String, CharNodes<'_>> = BTreeMap::new();
+    for field in &fields {
+        let root = index["index"]["index"][field]["root"]
+            .as_object_mut()
+            .expect("root of the inverted index");
+        let keys: Vec<String> = root
+            .keys()
+            .filter(|key| *key != "docs" && *key != "df")
+            .cloned()
+            .collect();
+        for c in keys {
+            let node = root.remove(&c).unwrap();
+            let entry = chars.entry(c).or_default();
+            entry.size += json_size(&node);
+            entry.nodes.push((field, node));
+        }
+    }
+    let sizes: Vec<usize> = chars.values().map(|entry| entry.size).collect();
+    let size: usize = sizes.iter().sum();
+    let term_shards = size.div_ceil(SHARD_SIZE).clamp(1, sizes.len().max(1));
+    let mut term_values = vec![Map::new(); term_shards];
+    let mut char_shards = Map::new();
+    for ((c, entry), i) in chars.into_iter().zip(pack(&sizes, term_shards)) {
+        for (field, node) in entry.nodes {
+            term_values[i]
+                .entry(field)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .unwrap()
+                .insert(c.clone(), node);
+        }
+        char_shards.insert(c, json!(i));
+    }
+
+    let docs = index["index"]["documentStore"]["docs"]
+        .as_object_mut()
+        .expect("documents of the document store");
+    let size: usize = docs.values().map(json_size).sum();
+    let docs_per_shard = docs.len().div_ceil(size.div_ceil(SHARD_SIZE).max(1)).max(1);
+    let mut doc_values = vec![Map::new(); docs.len().div_ceil(docs_per_shard)];
+    for (doc_ref, doc) in docs.iter_mut() {
+        let i: usize = doc_ref.parse()?;
+        doc_values[i / docs_per_shard].insert(doc_ref.clone(), doc.take());
+    }
+
+    index["shards"] = json!({
+        "terms": char_shards,
+        "docs": doc_values.len(),
+        "docs_per_shard": docs_per_shard,
+    });
+
+    let term_shards = term_values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (format!("t{i}"), value));
+    let doc_shards = doc_values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (format!("d{i}"), value));
+    term_shards
+        .chain(doc_shards)
+        .map(|(name, value)| {
+            let json = serde_json::to_vec(&value)?;
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(&json)?;
+            let data = STANDARD.encode(encoder.finish()?);
+            let js = format!("window.search.addShard('{name}', '{data}');");
+            Ok(Shard { name, js })
+        })
+        .collect()
+}
+
+/// Packs items with the given sizes into `bins` bins of about the same size,
+/// returning the bin of each item.
+///
+/// The largest items are placed first, each into the bin that is the smallest
+/// so far.
+fn pack(sizes: &[usize], bins: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| Reverse(sizes[i]));
+    let mut totals = vec![0; bins];
+    let mut packed = vec![0; sizes.len()];
+    for i in order {
+        let bin = (0..bins).min_by_key(|&bin| totals[bin]).unwrap();
+        totals[bin] += sizes[i];
+        packed[i] = bin;
+    }
+    packed
+}
+
+fn json_size(value: &Value) -> usize {
+    serde_json::to_string(value).map_or(0, |json| json.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn decode(shard: &Shard) -> Value {
+        let data = shard
+            .js
+            .rsplit_once(", '")
+            .and_then(|(_, rest)| rest.strip_suffix("');"))
+            .unwrap();
+        let mut json = String::new();
+        GzDecoder::new(&STANDARD.decode(data).unwrap()[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn split_terms_and_docs() {
+        let node = |df: u32| json!({"docs": {"0": {"tf": 1.0}}, "df": df});
+        let mut index = json!({
+            "doc_urls": ["a.html", "b.html"],
+            "index": {
+                "fields": ["title", "body"],
+                "index": {
+                    "title": {"root": {"docs": {}, "df": 0, "a": node(1)}},
+                    "body": {"root": {"docs": {}, "df": 0, "a": node(2), "b": node(3)}},
+                },
+                "documentStore": {
+                    "docs": {"0": {"title": "A"}, "1": {"title": "B"}},
+                    "docInfo": {"0": {"title": 1}, "1": {"title": 1}},
+                    "length": 2,
+                    "save": true,
+                },
+            },
+        });
+        let shards = split(&mut index).unwrap();
+
+        assert_eq!(
+            index["shards"],
+            json!({"terms": {"a": 0, "b": 0}, "docs": 1, "docs_per_shard": 2})
+        );
+        assert_eq!(
+            index["index"]["index"]["body"]["root"],
+            json!({"docs": {}, "df": 0})
+        );
+        assert_eq!(
+            index["index"]["documentStore"]["docs"],
+            json!({"0": null, "1": null})
+        );
+
+        let names: Vec<_> = shards.iter().map(|shard| shard.name.as_str()).collect();
+        assert_eq!(names, ["t0", "d0"]);
+        assert!(
+            shards[0]
+                .js
+                .starts_with("window.search.addShard('t0', 'H4sI")
+        );
+        assert_eq!(
+            decode(&shards[0]),
+            json!({"title": {"a": node(1)}, "body": {"a": node(2), "b": node(3)}})
+        );
+        assert_eq!(
+            decode(&shards[1]),
+            json!({"0": {"title": "A"}, "1": {"title": "B"}})
+        );
+    }
+
+    #[test]
+    fn pack_by_size() {
+        assert_eq!(pack(&[5, 4, 3, 3, 1], 2), [0, 1, 1, 0, 1]);
+        assert_eq!(pack(&[1, 9, 2], 3), [2, 0, 1]);
+        assert_eq!(pack(&[1, 2], 1), [0, 0]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Synonyms of `output.html.search.synonyms`.
+//!
+//! Each group of synonyms is the key of the table and the words in its value.
+//! The `mdbook-synonyms` function at the end of the pipeline replaces every
+//! word of a group with the first one, so that the index and the search terms
+//! only contain that word. `searcher.js` registers the same function from the
+//! `synonyms` of the search index.
+
+use super::language::SearchLanguage;
+use anyhow::{Result, bail};
+use elasticlunr::Language;
+use elasticlunr::pipeline::{Pipeline, PipelineFn};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The normalized synonyms, mapped to the normalized first word of their
+/// group.
+#[derive(Clone)]
+pub(super) struct Synonyms(Arc<BTreeMap<String, String>>);
+
+impl Synonyms {
+    /// Normalizes the synonyms of the config with the pipeline of the
+    /// language.
+    pub(super) fn new(
+        config: &BTreeMap<String, Vec<String>>,
+        language: &SearchLanguage,
+    ) -> Result<Synonyms> {
+        let pipeline = language.make_pipeline();
+        let normalize = |word: &str| {
+            let mut tokens = pipeline.run(language.tokenize(word));
+            if tokens.len() != 1 {
+                bail!(
+                    "[output.html.search.synonyms] `{word}` is not a single word \
+                     in the search index"
+                );
+            }
+            Ok(tokens.pop().unwrap())
+        };
+
+        // The first word of the group of each synonym, and the key of the group.
+        let mut synonyms: BTreeMap<String, (String, &String)> = BTreeMap::new();
+        for (word, others) in config {
+            let first = normalize(word)?;
+            for synonym in std::iter::once(word).chain(others) {
+                let token = normalize(synonym)?;
+                match synonyms.get(&token) {
+                    Some((existing, key)) if *existing != first => bail!(
+                        "[output.html.search.synonyms] `{synonym}` is in the synonyms of \
+                         both `{key}` and `{word}`"
+                    ),
+                    _ => {
+                        synonyms.insert(token, (first.clone(), word));
+                    }
+                }
+            }
+        }
+        let synonyms = synonyms
+            .into_iter()
+            .filter(|(token, (first, _))| token != first)
+            .map(|(token, (first, _))| (token, first))
+            .collect();
+        Ok(Synonyms(Arc::new(synonyms)))
+    }
+
+    /// The normalized synonyms for `searcher.js`, or `None` if there are none.
+    pub(super) fn to_json(&self) -> Option<BTreeMap<String, String>> {
+        (!self.0.is_empty()).then(|| (*self.0).clone())
+    }
+}
+
+impl PipelineFn for Synonyms {
+    fn name(&self) -> String {
+        "mdbook-synonyms".into()
+    }
+
+    fn filter(&self, token: String) -> Option<String> {
+        Some(self.0.get(&token).cloned().unwrap_or(token))
+    }
+}
+
+/// The search language of the book, with the synonyms at the end of its
+/// pipeline.
+pub(super) struct WithSynonyms {
+    pub(super) language: SearchLanguage,
+    synonyms: Synonyms,
+}
+
+impl WithSynonyms {
+    pub(super) fn new(language: SearchLanguage, synonyms: Synonyms) -> WithSynonyms {
+        WithSynonyms { language, synonyms }
+    }
+}
+
+impl Language for WithSynonyms {
+    fn name(&self) -> String {
+        self.language.name()
+    }
+
+    fn code(&self) -> String {
+        self.language.code()
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self.language.tokenize(text)
+    }
+
+    fn make_pipeline(&self) -> Pipeline {
+        let mut pipeline = self.language.make_pipeline();
+        if !self.synonyms.0.is_empty() {
+            pipeline.queue.push(Box::new(self.synonyms.clone()));
+        }
+        pipeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(groups: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        groups
+            .iter()
+            .map(|(word, others)| {
+                let others = others.iter().map(|other| other.to_string()).collect();
+                (word.to_string(), others)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalized_synonyms() {
+        let config = config(&[("async", &["Futures", "awaiting"]), ("crate", &["package"])]);
+        let synonyms = Synonyms::new(&config, &SearchLanguage::new(None)).unwrap();
+        let expected: BTreeMap<_, _> =
+            [("await", "async"), ("futur", "async"), ("packag", "crate")]
+                .into_iter()
+                .map(|(token, first)| (token.to_string(), first.to_string()))
+                .collect();
+        assert_eq!(synonyms.to_json(), Some(expected));
+
+        let language = WithSynonyms::new(SearchLanguage::new(None), synonyms);
+        let pipeline = language.make_pipeline();
+        assert_eq!(
+            pipeline.run(language.tokenize("Await the futures of packages")),
+            ["async", "async", "crate"]
+        );
+    }
+
+    #[test]
+    fn invalid_synonyms() {
+        let err = |config| {
+            Synonyms::new(&config, &SearchLanguage::new(None))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            err(config(&[("hash map", &["hashmap"])])),
+            "[output.html.search.synonyms] `hash map` is not a single word in the search index"
+        );
+        assert_eq!(
+            err(config(&[("async", &["the"])])),
+            "[output.html.search.synonyms] `the` is not a single word in the search index"
+        );
+        assert_eq!(
+            err(config(&[("async", &["futures"]), ("future", &["promise"])])),
+            "[output.html.search.synonyms] `future` is in the synonyms of both `async` and `future`"
+        );
+    }
+}
@@ -133,6 +133,11 @@ impl StaticFiles {
         });
     }
 
+    /// The mapping from resource names to their hashed filenames.
+    pub(super) fn hash_map(&self) -> &HashMap<String, String> {
+        &self.hash_map
+    }
+
     /// Updates this [`StaticFiles`] to hash the contents for determining the
     /// filename for each resource.
     pub(super) fn hash_files(&mut self) -> Result<()> {
@@ -223,7 +228,7 @@ impl StaticFiles {
                         Cow::Borrowed(&data[..])
                     };
                     let path = destination.join(filename);
-                    fs::write(path, &data)?;
+                    write_if_changed(&path, &data)?;
                 }
                 StaticFile::Additional {
                     input_location,
@@ -243,8 +248,8 @@ impl StaticFiles {
                         let data = fs::read_to_string(input_location)?;
                         let data = replace_all(&self.hash_map, data.as_bytes(), filename);
                         let path = destination.join(filename);
-                        fs::write(path, &data)?;
-                    } else {
+                        write_if_changed(&path, &data)?;
+                    } else if !same_contents(input_location, &output_location) {
                         std::fs::copy(input_location, &output_location).with_context(|| {
                             format!(
                                 "Unable to copy {} to {}",
@@ -261,6 +266,26 @@ impl StaticFiles {
     }
 }
 
+/// Writes `data` to `path`, unless the file already has the same contents.
+///
+/// This avoids touching files that have not changed during incremental
+/// builds.
+fn write_if_changed(path: &Path, data: &[u8]) -> Result<()> {
+    if std::fs::read(path).is_ok_and(|existing| existing == data) {
+        debug!("Skipping unchanged {}", path.display());
+        return Ok(());
+    }
+    fs::write(path, data)
+}
+
+/// Returns `true` if both files exist and have the same contents.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    match (std::fs::read(a), std::fs::read(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
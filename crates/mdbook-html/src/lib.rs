@@ -5,4 +5,7 @@ mod html_handlebars;
 pub mod theme;
 pub(crate) mod utils;
 
+pub use html::{BrokenLink, XhtmlChapter, check_book_links, render_xhtml_chapters};
 pub use html_handlebars::HtmlHandlebars;
+#[cfg(feature = "search")]
+pub use html_handlebars::{SearchResult, search_book};
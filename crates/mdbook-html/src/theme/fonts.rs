@@ -1,6 +1,9 @@
-pub(crate) static CSS: &[u8] = include_bytes!("../../front-end/fonts/fonts.css");
-// An array of (file_name, file_contents) pairs
-pub(crate) static LICENSES: [(&str, &[u8]); 2] = [
+//! The fonts included in the default theme.
+
+/// The `fonts.css` stylesheet.
+pub static CSS: &[u8] = include_bytes!("../../front-end/fonts/fonts.css");
+/// The font licenses, as an array of (file_name, file_contents) pairs.
+pub static LICENSES: [(&str, &[u8]); 2] = [
     (
         "fonts/OPEN-SANS-LICENSE.txt",
         include_bytes!("../../front-end/fonts/OPEN-SANS-LICENSE.txt"),
@@ -10,8 +13,8 @@ pub(crate) static LICENSES: [(&str, &[u8]); 2] = [
         include_bytes!("../../front-end/fonts/SOURCE-CODE-PRO-LICENSE.txt"),
     ),
 ];
-// An array of (file_name, file_contents) pairs
-pub(crate) static OPEN_SANS: [(&str, &[u8]); 10] = [
+/// The Open Sans font files, as an array of (file_name, file_contents) pairs.
+pub static OPEN_SANS: [(&str, &[u8]); 10] = [
     (
         "fonts/open-sans-v17-all-charsets-300.woff2",
         include_bytes!("../../front-end/fonts/open-sans-v17-all-charsets-300.woff2"),
@@ -54,8 +57,8 @@ pub(crate) static OPEN_SANS: [(&str, &[u8]); 10] = [
     ),
 ];
 
-// A (file_name, file_contents) pair
-pub(crate) static SOURCE_CODE_PRO: (&str, &[u8]) = (
+/// The Source Code Pro font file, as a (file_name, file_contents) pair.
+pub static SOURCE_CODE_PRO: (&str, &[u8]) = (
     "fonts/source-code-pro-v11-all-charsets-500.woff2",
     include_bytes!("../../front-end/fonts/source-code-pro-v11-all-charsets-500.woff2"),
 );
@@ -6,7 +6,7 @@ use mdbook_core::utils::fs;
 use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
-pub(crate) mod fonts;
+pub mod fonts;
 pub(crate) mod playground_editor;
 #[cfg(feature = "search")]
 pub(crate) mod searcher;
@@ -6,7 +6,7 @@
 //! for more information on how to implement a preprocessor.
 
 use anyhow::Context;
-use mdbook_core::book::Book;
+use mdbook_core::book::{Book, ChapterMetadata};
 use mdbook_core::config::Config;
 use mdbook_core::errors::Result;
 use serde::{Deserialize, Serialize};
@@ -63,6 +63,14 @@ pub struct PreprocessorContext {
     /// This should not be used outside of mdbook's internals.
     #[serde(skip)]
     pub chapter_titles: RefCell<HashMap<PathBuf, String>>,
+    /// The metadata from the front matter of the chapters, by their
+    /// [`Chapter::source_path`].
+    ///
+    /// Chapters without front matter are not included.
+    ///
+    /// [`Chapter::source_path`]: book::Chapter::source_path
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub chapter_metadata: HashMap<PathBuf, ChapterMetadata>,
 }
 
 impl PreprocessorContext {
@@ -74,6 +82,7 @@ impl PreprocessorContext {
             renderer,
             mdbook_version: crate::MDBOOK_VERSION.to_string(),
             chapter_titles: RefCell::new(HashMap::new()),
+            chapter_metadata: HashMap::new(),
         }
     }
 }
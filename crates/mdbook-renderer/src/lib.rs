@@ -6,7 +6,7 @@
 //! for more information on how to implement a renderer.
 
 use anyhow::Context;
-use mdbook_core::book::Book;
+use mdbook_core::book::{Book, ChapterMetadata};
 use mdbook_core::config::Config;
 use mdbook_core::errors::Result;
 use serde::{Deserialize, Serialize};
@@ -58,6 +58,14 @@ pub struct RenderContext {
     /// This should not be used outside of mdbook's internals.
     #[serde(skip)]
     pub chapter_titles: HashMap<PathBuf, String>,
+    /// The metadata from the front matter of the chapters, by their
+    /// [`Chapter::source_path`].
+    ///
+    /// Chapters without front matter are not included.
+    ///
+    /// [`Chapter::source_path`]: book::Chapter::source_path
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub chapter_metadata: HashMap<PathBuf, ChapterMetadata>,
 }
 
 impl RenderContext {
@@ -74,6 +82,7 @@ impl RenderContext {
             root: root.into(),
             destination: destination.into(),
             chapter_titles: HashMap::new(),
+            chapter_metadata: HashMap::new(),
         }
     }
 
@@ -6,11 +6,12 @@
 
 use anyhow::{Context, Error, Result, bail};
 pub use mdbook_core::book::SectionNumber;
-use memchr::Memchr;
+use mdbook_core::diagnostic::{Diagnostic, Severity};
 use pulldown_cmark::{DefaultBrokenLinkCallback, Event, HeadingLevel, Tag, TagEnd};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::fmt::Display;
+use std::fmt::{self, Display};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use tracing::{debug, trace, warn};
 
@@ -50,6 +51,15 @@ use tracing::{debug, trace, warn};
 /// You can either use - or * to indicate a numbered chapter, the parser doesn't
 /// care but you'll probably want to stay consistent.
 ///
+/// **Glob Entry:** A link whose location contains `*`, `?`, or `[` is a
+/// glob pattern. When the book is loaded, the files that match it become the
+/// nested chapters of the entry, titled from their first heading. A glob
+/// entry cannot have nested chapters of its own.
+///
+/// ```markdown
+/// - [Reference](reference/*.md)
+/// ```
+///
 /// **Suffix Chapter:** After the numbered chapters you can add a couple of
 /// non-numbered chapters. They are the same as prefix chapters but come after
 /// the numbered chapters instead of before.
@@ -61,6 +71,71 @@ pub fn parse_summary(summary: &str) -> Result<Summary> {
     parser.parse()
 }
 
+/// Formats the text of a `SUMMARY.md` file in the canonical form of the
+/// [`Display`] implementation of [`Summary`].
+///
+/// Unlike formatting a parsed [`Summary`], this fails instead of removing
+/// anything from the text that the canonical form can't keep, such as HTML
+/// comments, text outside of the chapter links, or formatting in a title.
+pub fn format_summary(summary: &str) -> Result<String> {
+    let parsed = parse_summary(summary)?;
+    check_formattable(summary)?;
+    Ok(parsed.to_string())
+}
+
+/// Checks that the formatted `SUMMARY.md` keeps everything in `src`.
+fn check_formattable(src: &str) -> Result<()> {
+    let mut in_title = false;
+    let mut link_depth = 0;
+    for (event, span) in pulldown_cmark::Parser::new(src).into_offset_iter() {
+        let problem = match event {
+            Event::Text(_) if in_title => None,
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) if in_title => {
+                in_title = false;
+                None
+            }
+            _ if in_title => Some("Formatting in a title"),
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) => {
+                in_title = true;
+                None
+            }
+            Event::Start(Tag::Link { .. }) => {
+                link_depth += 1;
+                None
+            }
+            Event::End(TagEnd::Link) => {
+                link_depth -= 1;
+                None
+            }
+            // The markdown of the chapter names is kept as it is.
+            _ if link_depth > 0 => None,
+            Event::Html(_) | Event::InlineHtml(_) => Some("HTML"),
+            Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_)
+            | Event::FootnoteReference(_)
+            | Event::TaskListMarker(_) => Some("Text outside of a chapter link"),
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            return Err(Diagnostic::new(
+                Severity::Error,
+                "summary-fmt",
+                format!("{problem} can't be kept when formatting SUMMARY.md"),
+                "SUMMARY.md",
+                src,
+                span,
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
 /// The parsed `SUMMARY.md`, specifying how the book should be laid out.
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -91,6 +166,14 @@ pub struct Link {
     pub number: Option<SectionNumber>,
     /// Any nested items this chapter may contain.
     pub nested_items: Vec<SummaryItem>,
+    /// A glob pattern, taking the book's `src` directory as the root. The
+    /// files that match it are added as the nested items of this chapter
+    /// when the book is loaded.
+    pub glob: Option<PathBuf>,
+    /// The markdown of the name, if it has formatting that `name` doesn't
+    /// keep, such as emphasis or code. The formatted summary uses it instead
+    /// of `name`, as long as it still has the same text as `name`.
+    pub markdown_name: Option<String>,
 }
 
 impl Link {
@@ -101,6 +184,8 @@ impl Link {
             location: Some(location.as_ref().to_path_buf()),
             number: None,
             nested_items: Vec::new(),
+            glob: None,
+            markdown_name: None,
         }
     }
 }
@@ -112,10 +197,142 @@ impl Default for Link {
             location: Some(PathBuf::new()),
             number: None,
             nested_items: Vec::new(),
+            glob: None,
+            markdown_name: None,
         }
     }
 }
 
+impl Summary {
+    /// Checks that no file is used by more than one chapter.
+    ///
+    /// This is done when parsing `SUMMARY.md`, and should be done again after
+    /// the glob entries have been expanded.
+    pub fn check_for_duplicates(&self) -> Result<()> {
+        let mut files = HashSet::new();
+        for part in [
+            &self.prefix_chapters,
+            &self.numbered_chapters,
+            &self.suffix_chapters,
+        ] {
+            check_for_duplicates(part, &mut files)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the summary as `SUMMARY.md` markdown.
+///
+/// The output is in a canonical form: the title (`Summary` if there is none),
+/// then the prefix chapters, the numbered chapters with their part titles and
+/// separators, and the suffix chapters, each separated by a blank line.
+/// Nested chapters are indented with four spaces per level. Parsing the output
+/// with [`parse_summary`] gives back the same summary, except that prefix and
+/// suffix chapters can't be told apart if there are no numbered chapters.
+impl Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let title = self.title.as_deref().unwrap_or("Summary");
+        writeln!(f, "# {}", escape_markdown(title))?;
+
+        for item in &self.prefix_chapters {
+            writeln!(f)?;
+            write_affix_item(f, item)?;
+        }
+
+        let mut in_list = false;
+        for item in &self.numbered_chapters {
+            match item {
+                SummaryItem::Link(link) => {
+                    if !in_list {
+                        writeln!(f)?;
+                        in_list = true;
+                    }
+                    write_numbered_link(f, link, 0)?;
+                }
+                SummaryItem::Separator => {
+                    writeln!(f, "\n---")?;
+                    in_list = false;
+                }
+                SummaryItem::PartTitle(title) => {
+                    writeln!(f, "\n# {}", escape_markdown(title))?;
+                    in_list = false;
+                }
+            }
+        }
+
+        for item in &self.suffix_chapters {
+            writeln!(f)?;
+            write_affix_item(f, item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a prefix or suffix chapter.
+fn write_affix_item(f: &mut fmt::Formatter<'_>, item: &SummaryItem) -> fmt::Result {
+    match item {
+        SummaryItem::Link(link) => writeln!(f, "{}", LinkMarkdown(link)),
+        SummaryItem::Separator => writeln!(f, "---"),
+        // Part titles only appear in the numbered chapters.
+        SummaryItem::PartTitle(_) => Ok(()),
+    }
+}
+
+/// Writes a numbered chapter and its nested chapters.
+fn write_numbered_link(f: &mut fmt::Formatter<'_>, link: &Link, level: usize) -> fmt::Result {
+    writeln!(f, "{}- {}", "    ".repeat(level), LinkMarkdown(link))?;
+    for item in &link.nested_items {
+        // Nested separators and part titles can't be written in `SUMMARY.md`.
+        if let SummaryItem::Link(nested) = item {
+            write_numbered_link(f, nested, level + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// Displays a [`Link`] as `[name](location)`.
+struct LinkMarkdown<'a>(&'a Link);
+
+impl Display for LinkMarkdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let link = self.0;
+        let dest = link
+            .location
+            .as_ref()
+            .or(link.glob.as_ref())
+            .map(|path| path.to_string_lossy())
+            .unwrap_or_default();
+        let name = match &link.markdown_name {
+            Some(markdown) if markdown_text(markdown) == link.name => markdown.clone(),
+            _ => escape_markdown(&link.name),
+        };
+        if dest.contains([' ', '(', ')']) {
+            write!(f, "[{name}](<{dest}>)")
+        } else {
+            write!(f, "[{name}]({dest})")
+        }
+    }
+}
+
+/// Escapes the characters in `text` that markdown would treat as formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The text of the markdown name of a link, in the same way as the parser
+/// gets the `name` of a [`Link`].
+fn markdown_text(markdown: &str) -> String {
+    let src = format!("[{markdown}]()");
+    stringify_events(pulldown_cmark::Parser::new(&src).collect())
+}
+
 /// An item in `SUMMARY.md`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -173,7 +390,8 @@ impl From<Link> for SummaryItem {
 struct SummaryParser<'a> {
     src: &'a str,
     stream: pulldown_cmark::OffsetIter<'a, DefaultBrokenLinkCallback>,
-    offset: usize,
+    /// The byte range of the last event, for error messages.
+    span: Range<usize>,
 
     /// We can't actually put an event back into the `OffsetIter` stream, so instead we store it
     /// here until somebody calls `next_event` again.
@@ -225,22 +443,11 @@ impl<'a> SummaryParser<'a> {
         SummaryParser {
             src: text,
             stream: pulldown_parser,
-            offset: 0,
+            span: 0..0,
             back: None,
         }
     }
 
-    /// Get the current line and column to give the user more useful error
-    /// messages.
-    fn current_location(&self) -> (usize, usize) {
-        let previous_text = self.src[..self.offset].as_bytes();
-        let line = Memchr::new(b'\n', previous_text).count() + 1;
-        let start_of_line = memchr::memrchr(b'\n', previous_text).unwrap_or(0);
-        let col = self.src[start_of_line..self.offset].chars().count();
-
-        (line, col)
-    }
-
     /// Parse the text the `SummaryParser` was created with.
     fn parse(mut self) -> Result<Summary> {
         let title = self.parse_title();
@@ -255,39 +462,15 @@ impl<'a> SummaryParser<'a> {
             .parse_affix(false)
             .with_context(|| "There was an error parsing the suffix chapters")?;
 
-        let mut files = HashSet::new();
-        for part in [&prefix_chapters, &numbered_chapters, &suffix_chapters] {
-            Self::check_for_duplicates(&part, &mut files)?;
-        }
-
-        Ok(Summary {
+        let summary = Summary {
             title,
             prefix_chapters,
             numbered_chapters,
             suffix_chapters,
-        })
-    }
+        };
+        summary.check_for_duplicates()?;
 
-    /// Recursively check for duplicate files in the summary items.
-    fn check_for_duplicates<'b>(
-        items: &'b [SummaryItem],
-        files: &mut HashSet<&'b PathBuf>,
-    ) -> Result<()> {
-        for item in items {
-            if let SummaryItem::Link(link) = item {
-                if let Some(location) = &link.location {
-                    if !files.insert(location) {
-                        bail!(anyhow::anyhow!(
-                            "Duplicate file in SUMMARY.md: {:?}",
-                            location
-                        ));
-                    }
-                }
-                // Recursively check nested items
-                Self::check_for_duplicates(&link.nested_items, files)?;
-            }
-        }
-        Ok(())
+        Ok(summary)
     }
 
     /// Parse the affix chapters.
@@ -379,20 +562,61 @@ impl<'a> SummaryParser<'a> {
 
     /// Finishes parsing a link once the `Event::Start(Tag::Link(..))` has been opened.
     fn parse_link(&mut self, href: String) -> Link {
-        let link_content = collect_events!(self.stream, end TagEnd::Link);
+        let span = self.span.clone();
+        let mut link_content = Vec::new();
+        // The end of the last event of the name, which may be an escaped `]`.
+        let mut content_end = span.start + 1;
+        loop {
+            match self.stream.next() {
+                Some((Event::End(TagEnd::Link), _)) | None => break,
+                Some((event, range)) => {
+                    content_end = content_end.max(range.end);
+                    link_content.push(event);
+                }
+            }
+        }
         let name = stringify_events(link_content);
+        let markdown_name = self
+            .link_markdown(span, content_end)
+            .filter(|markdown| *markdown != escape_markdown(&name));
 
         let path = if href.is_empty() {
             None
         } else {
             Some(PathBuf::from(href.replace("%20", " ")))
         };
+        let (location, glob) = match path {
+            Some(path) if href.contains(['*', '?', '[']) => (None, Some(path)),
+            path => (path, None),
+        };
 
         Link {
             name,
-            location: path,
+            location,
             number: None,
             nested_items: Vec::new(),
+            glob,
+            markdown_name,
+        }
+    }
+
+    /// Returns the markdown between the brackets of the link at `span`, with
+    /// its lines joined.
+    fn link_markdown(&self, span: Range<usize>, content_end: usize) -> Option<String> {
+        let inner = self.src.get(span.start..span.end)?.strip_prefix('[')?;
+        let content_end = content_end - span.start - 1;
+        let end = content_end + inner.get(content_end..)?.find(']')?;
+        let markdown = &inner[..end];
+        if markdown.contains('\n') {
+            Some(
+                markdown
+                    .lines()
+                    .map(str::trim)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+        } else {
+            Some(markdown.to_string())
         }
     }
 
@@ -481,7 +705,7 @@ impl<'a> SummaryParser<'a> {
     fn next_event(&mut self) -> Option<Event<'a>> {
         let next = self.back.take().or_else(|| {
             self.stream.next().map(|(ev, range)| {
-                self.offset = range.start;
+                self.span = range;
                 ev
             })
         });
@@ -508,6 +732,9 @@ impl<'a> SummaryParser<'a> {
                     }
                     // recurse to parse the nested list
                     let (_, last_item) = get_last_link(&mut items)?;
+                    if last_item.glob.is_some() {
+                        bail!(self.parse_error("A glob entry cannot have nested chapters"));
+                    }
                     let last_item_number = last_item
                         .number
                         .as_ref()
@@ -545,6 +772,7 @@ impl<'a> SummaryParser<'a> {
                         link.name,
                         link.location
                             .as_ref()
+                            .or(link.glob.as_ref())
                             .map(|p| p.to_str().unwrap_or(""))
                             .unwrap_or("[draft]")
                     );
@@ -563,14 +791,20 @@ impl<'a> SummaryParser<'a> {
         }
     }
 
+    /// Creates an error at the location of the last event.
+    ///
+    /// The path of the error is `SUMMARY.md`, relative to the book's `src`
+    /// directory.
     fn parse_error<D: Display>(&self, msg: D) -> Error {
-        let (line, col) = self.current_location();
-        anyhow::anyhow!(
-            "failed to parse SUMMARY.md line {}, column {}: {}",
-            line,
-            col,
-            msg
+        Diagnostic::new(
+            Severity::Error,
+            "summary-parse",
+            msg.to_string(),
+            "SUMMARY.md",
+            self.src,
+            self.span.clone(),
         )
+        .into()
     }
 
     /// Try to parse the title line.
@@ -600,6 +834,28 @@ impl<'a> SummaryParser<'a> {
     }
 }
 
+/// Recursively check for duplicate files in the summary items.
+fn check_for_duplicates<'b>(
+    items: &'b [SummaryItem],
+    files: &mut HashSet<&'b PathBuf>,
+) -> Result<()> {
+    for item in items {
+        if let SummaryItem::Link(link) = item {
+            if let Some(location) = &link.location {
+                if !files.insert(location) {
+                    bail!(anyhow::anyhow!(
+                        "Duplicate file in SUMMARY.md: {:?}",
+                        location
+                    ));
+                }
+            }
+            // Recursively check nested items
+            check_for_duplicates(&link.nested_items, files)?;
+        }
+    }
+    Ok(())
+}
+
 fn update_section_numbers(items: &mut [SummaryItem], level: usize, by: u32) {
     for item in items {
         if let SummaryItem::Link(ref mut link) = *item {
@@ -735,7 +991,7 @@ mod tests {
         let error_message = got.err().unwrap().to_string();
         assert_eq!(
             error_message,
-            "failed to parse SUMMARY.md line 2, column 1: Suffix chapters cannot be followed by a list"
+            "SUMMARY.md:2:1: Suffix chapters cannot be followed by a list"
         );
     }
 
@@ -750,7 +1006,7 @@ mod tests {
         let error_message = got.err().unwrap().to_string();
         assert_eq!(
             error_message,
-            "failed to parse SUMMARY.md line 1, column 0: Suffix chapters cannot be followed by a list"
+            "SUMMARY.md:1:1: Suffix chapters cannot be followed by a list"
         );
     }
 
@@ -808,13 +1064,19 @@ mod tests {
                     location: Some(PathBuf::from("./nested.md")),
                     number: Some(SectionNumber::new([1, 1])),
                     nested_items: Vec::new(),
+                    glob: None,
+                    markdown_name: None,
                 })],
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Link(Link {
                 name: String::from("Second"),
                 location: Some(PathBuf::from("./second.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
         ];
 
@@ -836,12 +1098,16 @@ mod tests {
                 location: Some(PathBuf::from("./first.md")),
                 number: Some(SectionNumber::new([1])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Link(Link {
                 name: String::from("Second"),
                 location: Some(PathBuf::from("./second.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
         ];
 
@@ -864,12 +1130,16 @@ mod tests {
                 location: Some(PathBuf::from("./first.md")),
                 number: Some(SectionNumber::new([1])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Link(Link {
                 name: String::from("Second"),
                 location: Some(PathBuf::from("./second.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::PartTitle(String::from("Title 2")),
             SummaryItem::Link(Link {
@@ -881,7 +1151,11 @@ mod tests {
                     location: Some(PathBuf::from("./fourth.md")),
                     number: Some(SectionNumber::new([3, 1])),
                     nested_items: Vec::new(),
+                    glob: None,
+                    markdown_name: None,
                 })],
+                glob: None,
+                markdown_name: None,
             }),
         ];
 
@@ -904,12 +1178,16 @@ mod tests {
                 location: Some(PathBuf::from("./first.md")),
                 number: Some(SectionNumber::new([1])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Link(Link {
                 name: String::from("Second"),
                 location: Some(PathBuf::from("./second.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
         ];
 
@@ -932,6 +1210,8 @@ mod tests {
             location: None,
             number: Some(SectionNumber::new([1])),
             nested_items: Vec::new(),
+            glob: None,
+            markdown_name: None,
         })];
 
         assert!(got.is_ok());
@@ -950,6 +1230,8 @@ mod tests {
                 location: Some(PathBuf::from("./first.md")),
                 number: Some(SectionNumber::new([1])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Separator,
             SummaryItem::Link(Link {
@@ -957,6 +1239,8 @@ mod tests {
                 location: Some(PathBuf::from("./second.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Separator,
             SummaryItem::Link(Link {
@@ -964,6 +1248,8 @@ mod tests {
                 location: Some(PathBuf::from("./third.md")),
                 number: Some(SectionNumber::new([3])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
         ];
 
@@ -985,6 +1271,8 @@ mod tests {
             location: Some(PathBuf::from("./chapter.md")),
             number: Some(SectionNumber::new([1])),
             nested_items: Vec::new(),
+            glob: None,
+            markdown_name: None,
         })];
 
         let mut parser = SummaryParser::new(src);
@@ -1004,12 +1292,16 @@ mod tests {
                 location: Some(PathBuf::from("./test link1.md")),
                 number: Some(SectionNumber::new([1])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
             SummaryItem::Link(Link {
                 name: String::from("test2"),
                 location: Some(PathBuf::from("./test link2.md")),
                 number: Some(SectionNumber::new([2])),
                 nested_items: Vec::new(),
+                glob: None,
+                markdown_name: None,
             }),
         ];
         let mut parser = SummaryParser::new(src);
@@ -1093,6 +1385,8 @@ mod tests {
                 location: Some(PathBuf::from(location)),
                 number: Some(SectionNumber::new(numbers)),
                 nested_items,
+                glob: None,
+                markdown_name: None,
             })
         };
 
@@ -1197,4 +1491,135 @@ mod tests {
         let error_message = res.err().unwrap().to_string();
         assert_eq!(error_message, r#"Duplicate file in SUMMARY.md: "./a.md""#);
     }
+
+    #[test]
+    fn parse_glob_entry() {
+        let src = "- [Intro](intro.md)\n- [Reference](reference/*.md)\n";
+        let got = parse_summary(src).unwrap();
+
+        let should_be = SummaryItem::Link(Link {
+            name: String::from("Reference"),
+            location: None,
+            number: Some(SectionNumber::new([2])),
+            nested_items: Vec::new(),
+            glob: Some(PathBuf::from("reference/*.md")),
+            markdown_name: None,
+        });
+        assert_eq!(got.numbered_chapters[1], should_be);
+    }
+
+    #[test]
+    fn glob_entry_cannot_have_nested_chapters() {
+        let src = "- [Reference](reference/*.md)\n  - [Nested](nested.md)\n";
+
+        let res = parse_summary(src);
+        let error_message = format!("{:#}", res.err().unwrap());
+        assert!(
+            error_message.ends_with("A glob entry cannot have nested chapters"),
+            "{error_message}"
+        );
+    }
+
+    #[test]
+    fn format_summary() {
+        let src = r#"# Summary
+[Intro](intro.md)
+
+---
+
+[Preface](<pre face.md>)
+* [First](first.md)
+  * [Nested *emphasis*](nested.md)
+     * [Deep \[brackets\]](deep.md)
+* [Draft]()
+
+---
+* [Reference](reference/*.md)
+# Part Two
+- [Second](second.md)
+
+[Appendix](appendix.md)
+"#;
+        let summary = parse_summary(src).unwrap();
+        let formatted = summary.to_string();
+        assert_eq!(
+            formatted,
+            "# Summary\n\
+             \n\
+             [Intro](intro.md)\n\
+             \n\
+             ---\n\
+             \n\
+             [Preface](<pre face.md>)\n\
+             \n\
+             - [First](first.md)\n\
+             \x20   - [Nested *emphasis*](nested.md)\n\
+             \x20       - [Deep \\[brackets\\]](deep.md)\n\
+             - [Draft]()\n\
+             \n\
+             ---\n\
+             \n\
+             - [Reference](reference/*.md)\n\
+             \n\
+             # Part Two\n\
+             \n\
+             - [Second](second.md)\n\
+             \n\
+             [Appendix](appendix.md)\n"
+        );
+        assert_eq!(parse_summary(&formatted).unwrap(), summary);
+    }
+
+    #[test]
+    fn format_summary_without_title() {
+        let summary = parse_summary("- [A \\_b\\_](a.md)\n").unwrap();
+        assert_eq!(summary.title, None);
+        assert_eq!(summary.to_string(), "# Summary\n\n- [A \\_b\\_](a.md)\n");
+    }
+
+    #[test]
+    fn format_summary_keeps_markdown_names() {
+        let src = "- [*Italic* `code`\n  title](a.md)\n- [Plain](b.md)\n";
+        let mut summary = parse_summary(src).unwrap();
+        let SummaryItem::Link(link) = &mut summary.numbered_chapters[0] else {
+            panic!("expected a link");
+        };
+        assert_eq!(link.name, "Italic code title");
+        assert_eq!(link.markdown_name.as_deref(), Some("*Italic* `code` title"));
+        assert_eq!(
+            summary.to_string(),
+            "# Summary\n\n- [*Italic* `code` title](a.md)\n- [Plain](b.md)\n"
+        );
+
+        // A renamed link doesn't use the markdown of its old name.
+        let SummaryItem::Link(link) = &mut summary.numbered_chapters[0] else {
+            panic!("expected a link");
+        };
+        link.name = String::from("Renamed *title*");
+        assert_eq!(
+            summary.to_string(),
+            "# Summary\n\n- [Renamed \\*title\\*](a.md)\n- [Plain](b.md)\n"
+        );
+    }
+
+    #[test]
+    fn format_summary_refuses_to_remove_content() {
+        let err = |src| format!("{:#}", super::format_summary(src).unwrap_err());
+        assert_eq!(
+            err("- [A](a.md)\n<!-- keep me -->\n- [B](b.md)\n"),
+            "SUMMARY.md:2:1: HTML can't be kept when formatting SUMMARY.md"
+        );
+        assert_eq!(
+            err("# Summary\n\nSome notes.\n\n- [A](a.md)\n"),
+            "SUMMARY.md:3:1: Text outside of a chapter link can't be kept when formatting SUMMARY.md"
+        );
+        assert_eq!(
+            err("# Part *one*\n\n- [A](a.md)\n"),
+            "SUMMARY.md:1:8: Formatting in a title can't be kept when formatting SUMMARY.md"
+        );
+        assert_eq!(
+            super::format_summary("- [A <kbd>B</kbd>](a.md)\n").unwrap(),
+            "# Summary\n\n- [A <kbd>B</kbd>](a.md)\n"
+        );
+    }
 }
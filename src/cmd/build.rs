@@ -10,13 +10,17 @@ pub fn make_subcommand() -> Command {
         .about("Builds a book from its markdown files")
         .arg_dest_dir()
         .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
+        .arg_message_format()
         .arg_open()
 }
 
 // Build command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
+    set_message_format(args);
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(book_dir)?;
+    let mut book = MDBook::load_with_options(book_dir, &load_options(args))?;
 
     set_dest_dir(args, &mut book);
 
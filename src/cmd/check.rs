@@ -0,0 +1,46 @@
+use super::command_prelude::*;
+use crate::get_book_dir;
+use anyhow::{Result, bail};
+use mdbook_core::diagnostic::Severity;
+use mdbook_driver::MDBook;
+use mdbook_html::HtmlHandlebars;
+use tracing::info;
+
+// Create clap subcommand arguments
+pub fn make_subcommand() -> Command {
+    Command::new("check")
+        .about("Checks a book for broken links to chapters and headings")
+        .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
+        .arg_message_format()
+}
+
+// Check command implementation
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    set_message_format(args);
+    let book_dir = get_book_dir(args);
+    let book = MDBook::load_with_options(book_dir, &load_options(args))?;
+
+    // Each language of a multilingual book is checked with its own config,
+    // like it is built.
+    let renderer = HtmlHandlebars::new();
+    let (preprocessed_book, ctx) = book.preprocess_book(&renderer)?;
+    let mut broken = mdbook_html::check_book_links(&book.root, &preprocessed_book, &ctx.config)?;
+    for (_, preprocessed_book, ctx) in book.preprocess_translations(&renderer)? {
+        broken.extend(mdbook_html::check_book_links(
+            &book.root,
+            &preprocessed_book,
+            &ctx.config,
+        )?);
+    }
+    for link in &broken {
+        link.to_diagnostic(&book.root, Severity::Error).emit();
+    }
+    if !broken.is_empty() {
+        bail!("found {} broken link(s)", broken.len());
+    }
+    info!("No broken links found");
+
+    Ok(())
+}
@@ -13,12 +13,14 @@ pub fn make_subcommand() -> Command {
         .about("Deletes a built book")
         .arg_dest_dir()
         .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
 }
 
 // Clean command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
-    let book = MDBook::load(book_dir)?;
+    let book = MDBook::load_with_options(book_dir, &load_options(args))?;
 
     let dir_to_remove = match args.get_one::<PathBuf>("dest-dir") {
         Some(dest_dir) => std::env::current_dir()
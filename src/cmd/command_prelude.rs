@@ -1,7 +1,8 @@
 //! Helpers for building the command-line arguments for commands.
 
 pub use clap::{Arg, ArgMatches, Command, arg};
-use mdbook_driver::MDBook;
+use mdbook_core::diagnostic::{self, MessageFormat};
+use mdbook_driver::{LoadOptions, MDBook};
 use std::path::PathBuf;
 
 pub trait CommandExt: Sized {
@@ -34,6 +35,31 @@ pub trait CommandExt: Sized {
         )
     }
 
+    fn arg_profile(self) -> Self {
+        self._arg(Arg::new("profile").long("profile").value_name("name").help(
+            "Configuration profile from book.toml to apply\n\
+                    If omitted, mdBook uses build.profile from book.toml, if set.",
+        ))
+    }
+
+    fn arg_strict(self) -> Self {
+        self._arg(arg!(--strict "Fail if book.toml contains unknown keys"))
+    }
+
+    fn arg_message_format(self) -> Self {
+        self._arg(
+            Arg::new("message-format")
+                .long("message-format")
+                .value_name("fmt")
+                .value_parser(["human", "json"])
+                .default_value("human")
+                .help(
+                    "How problems in the book's source files are reported\n\
+                    `json` prints them to stdout, one JSON object per line.",
+                ),
+        )
+    }
+
     fn arg_open(self) -> Self {
         self._arg(arg!(-o --open "Opens the compiled book in a web browser"))
     }
@@ -67,3 +93,18 @@ pub fn set_dest_dir(args: &ArgMatches, book: &mut MDBook) {
         book.config.build.build_dir = build_dir;
     }
 }
+
+pub fn set_message_format(args: &ArgMatches) {
+    let format = match args.get_one::<String>("message-format").map(String::as_str) {
+        Some("json") => MessageFormat::Json,
+        _ => MessageFormat::Human,
+    };
+    diagnostic::set_message_format(format);
+}
+
+pub fn load_options(args: &ArgMatches) -> LoadOptions {
+    let mut options = LoadOptions::default();
+    options.profile = args.get_one::<String>("profile").cloned();
+    options.strict = args.get_flag("strict");
+    options
+}
@@ -0,0 +1,178 @@
+use super::command_prelude::*;
+use crate::get_book_dir;
+use anyhow::{Context, Result, bail};
+use mdbook_core::utils::fs;
+use mdbook_driver::MDBook;
+use mdbook_driver::config::{Config, HtmlConfig, SchemaRegistry};
+use std::path::Path;
+use toml::Value;
+use toml_edit::{DocumentMut, InlineTable, Item, Table};
+use tracing::info;
+
+/// The top-level tables, in the order they are listed.
+const TABLES: &[&str] = &["book", "build", "rust", "output", "preprocessor", "profile"];
+
+// Create clap subcommand arguments
+pub fn make_subcommand() -> Command {
+    Command::new("config")
+        .about("Inspects or changes the configuration of a book")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("get")
+                .about("Prints the value of a config key")
+                .arg(arg!(<key> "The dotted key to print, such as `output.html.site-url`"))
+                .arg_root_dir()
+                .arg_profile()
+                .arg_strict(),
+        )
+        .subcommand(
+            Command::new("set")
+                .about("Sets the value of a config key in book.toml")
+                .arg(arg!(<key> "The dotted key to set, such as `output.html.site-url`"))
+                .arg(arg!(<value> "The value, as TOML. Other text is set as a string."))
+                .arg_root_dir(),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Prints all config keys and their values")
+                .arg_root_dir()
+                .arg_profile()
+                .arg_strict(),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Prints a JSON Schema of book.toml for editors and other tools"),
+        )
+}
+
+// Config command implementation
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        Some(("get", args)) => {
+            let config = MDBook::load_config(&get_book_dir(args), &load_options(args))?;
+            let key = args.get_one::<String>("key").unwrap();
+            let Some(value) = config.get::<Value>(key)? else {
+                bail!("`{key}` is not set");
+            };
+            match value {
+                Value::String(s) => println!("{s}"),
+                Value::Table(table) => print!("{}", toml::to_string(&table)?),
+                value => println!("{value}"),
+            }
+        }
+        Some(("set", args)) => {
+            let key = args.get_one::<String>("key").unwrap();
+            let value = args.get_one::<String>("value").unwrap();
+            set(&get_book_dir(args), key, value)?;
+        }
+        Some(("list", args)) => {
+            let config = MDBook::load_config(&get_book_dir(args), &load_options(args))?;
+            for table in TABLES {
+                let value: Value = config.get(table)?.unwrap();
+                if value.as_table().is_some_and(|t| !t.is_empty()) {
+                    list(table, &value);
+                }
+            }
+        }
+        Some(("schema", _)) => {
+            let schema = serde_json::to_string_pretty(&Config::json_schema())?;
+            println!("{schema}");
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Prints every value in `value` as a `key = value` line.
+fn list(key: &str, value: &Value) {
+    match value {
+        Value::Table(table) if !table.is_empty() => {
+            for (name, value) in table {
+                list(&format!("{key}.{name}"), value);
+            }
+        }
+        value => println!("{key} = {value}"),
+    }
+}
+
+/// Sets `key` to `value` in `book.toml`, keeping its comments and formatting.
+///
+/// The value is parsed as TOML, and used as a string if that fails, or if
+/// the parsed value doesn't have the right type.
+fn set(book_dir: &Path, key: &str, value: &str) -> Result<()> {
+    let path = book_dir.join("book.toml");
+    let src = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let doc: DocumentMut = src.parse().with_context(|| "Invalid configuration file")?;
+
+    let string = toml_edit::Value::from(value);
+    let doc = match value.parse::<toml_edit::Value>() {
+        Ok(mut parsed) => {
+            parsed.decor_mut().clear();
+            let result = with_value(&doc, key, parsed);
+            match result {
+                Ok(doc) => doc,
+                Err(e) => with_value(&doc, key, string).map_err(|_| e)?,
+            }
+        }
+        Err(_) => with_value(&doc, key, string)?,
+    };
+
+    fs::write(&path, doc.to_string())?;
+    info!("Set `{key}` in {}", path.display());
+    Ok(())
+}
+
+/// Returns a copy of `doc` with `key` set to `value`, if the result is a
+/// valid config.
+fn with_value(doc: &DocumentMut, key: &str, mut value: toml_edit::Value) -> Result<DocumentMut> {
+    let mut doc = doc.clone();
+    let (last, tables) = key
+        .rsplit_once('.')
+        .map_or((key, None), |(t, l)| (l, Some(t)));
+    if !TABLES.contains(&key.split('.').next().unwrap()) || tables.is_none() {
+        bail!("unable to set `{key}`, expected a key in a table such as `book.title`");
+    }
+
+    let mut item = doc.as_item_mut();
+    for part in tables.into_iter().flat_map(|t| t.split('.')) {
+        let in_inline = item.is_value();
+        let Some(table) = item.as_table_like_mut() else {
+            bail!("unable to set `{key}`, `{part}` is not in a table");
+        };
+        item = table.entry(part).or_insert_with(|| {
+            if in_inline {
+                Item::Value(InlineTable::new().into())
+            } else {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            }
+        });
+    }
+    let Some(table) = item.as_table_like_mut() else {
+        bail!("unable to set `{key}`, its parent is not a table");
+    };
+    // Keep the comments around a value that is replaced.
+    if let Some(old) = table.get(last).and_then(Item::as_value) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    table.insert(last, Item::Value(value));
+
+    let src = doc.to_string();
+    let (config, unknown) = Config::from_str_checked(&src, &SchemaRegistry::new())?;
+    let nested = format!("{key}.");
+    if let Some(unknown) = unknown
+        .iter()
+        .find(|unknown| unknown.key == key || unknown.key.starts_with(&nested))
+    {
+        bail!("{unknown}");
+    }
+    if key.starts_with("output.html.") {
+        config.get::<HtmlConfig>("output.html")?;
+    }
+    Ok(doc)
+}
@@ -1,11 +1,16 @@
 //! Subcommand modules for the `mdbook` binary.
 
 pub mod build;
+pub mod check;
 pub mod clean;
 pub mod command_prelude;
+pub mod config;
 pub mod init;
+#[cfg(feature = "search")]
+pub mod search;
 #[cfg(feature = "serve")]
 pub mod serve;
+pub mod summary;
 pub mod test;
 #[cfg(feature = "watch")]
 pub mod watch;
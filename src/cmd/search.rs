@@ -0,0 +1,39 @@
+use super::command_prelude::*;
+use crate::get_book_dir;
+use anyhow::Result;
+use mdbook_driver::MDBook;
+use mdbook_html::HtmlHandlebars;
+use tracing::info;
+
+// Create clap subcommand arguments
+pub fn make_subcommand() -> Command {
+    Command::new("search")
+        .about("Searches a book like the search of the HTML output")
+        .arg(arg!(<query> "The words to search for"))
+        .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
+}
+
+// Search command implementation
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    let book_dir = get_book_dir(args);
+    let book = MDBook::load_with_options(book_dir, &load_options(args))?;
+    let query = args.get_one::<String>("query").unwrap();
+
+    let (preprocessed_book, ctx) = book.preprocess_book(&HtmlHandlebars::new())?;
+    let results = mdbook_html::search_book(&preprocessed_book, &ctx.config, query)?;
+    if results.is_empty() {
+        info!("No search results for `{query}`");
+    }
+    for result in &results {
+        println!("{}", result.breadcrumbs);
+        println!("  {}", result.url);
+        if !result.teaser.is_empty() {
+            println!("  {}", result.teaser);
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -25,6 +25,8 @@ pub fn make_subcommand() -> Command {
         .about("Serves a book at http://localhost:3000, and rebuilds it on changes")
         .arg_dest_dir()
         .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
         .arg(
             Arg::new("hostname")
                 .short('n')
@@ -50,7 +52,7 @@ pub fn make_subcommand() -> Command {
 // Serve command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(&book_dir)?;
+    let mut book = MDBook::load_with_options(&book_dir, &load_options(args))?;
 
     let port = args.get_one::<String>("port").unwrap();
     let hostname = args.get_one::<String>("hostname").unwrap();
@@ -65,6 +67,15 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
         set_dest_dir(args, book);
         // Override site-url for local serving of the 404 file
         book.config.set("output.html.site-url", "/").unwrap();
+        // Only render the chapters that changed when rebuilding, unless
+        // incremental builds are configured. Hashed file names would change
+        // every page along with the search index, so they are turned off too.
+        if let Ok(None) = book.config.get::<bool>("output.html.incremental") {
+            book.config.set("output.html.incremental", true).unwrap();
+            if let Ok(None) = book.config.get::<bool>("output.html.hash-files") {
+                book.config.set("output.html.hash-files", false).unwrap();
+            }
+        }
     };
     update_config(&mut book);
     book.build()?;
@@ -95,9 +106,15 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     #[cfg(feature = "watch")]
     {
         let watcher = watch::WatcherKind::from_str(args.get_one::<String>("watcher").unwrap());
-        watch::rebuild_on_change(watcher, &book_dir, &update_config, &move || {
-            let _ = tx.send(Message::text("reload"));
-        });
+        watch::rebuild_on_change(
+            watcher,
+            &book_dir,
+            &load_options(args),
+            &update_config,
+            &move || {
+                let _ = tx.send(Message::text("reload"));
+            },
+        );
     }
 
     let _ = thread_handle.join();
@@ -0,0 +1,57 @@
+use super::command_prelude::*;
+use crate::get_book_dir;
+use anyhow::{Result, bail};
+use mdbook_core::diagnostic::Diagnostic;
+use mdbook_core::utils::fs;
+use mdbook_driver::{LoadOptions, MDBook};
+use mdbook_summary::format_summary;
+use std::path::Path;
+use tracing::info;
+
+// Create clap subcommand arguments
+pub fn make_subcommand() -> Command {
+    Command::new("summary")
+        .about("Works with the SUMMARY.md of a book")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("fmt")
+                .about("Formats SUMMARY.md")
+                .arg(arg!(--check "Fails if SUMMARY.md is not formatted, without changing it"))
+                .arg_root_dir(),
+        )
+}
+
+// Summary command implementation
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        Some(("fmt", args)) => fmt(&get_book_dir(args), args.get_flag("check")),
+        _ => unreachable!(),
+    }
+}
+
+/// Rewrites `SUMMARY.md` in its canonical form.
+fn fmt(book_dir: &Path, check: bool) -> Result<()> {
+    let config = MDBook::load_config(book_dir, &LoadOptions::default())?;
+    let path = book_dir.join(&config.book.src).join("SUMMARY.md");
+    let src = fs::read_to_string(&path)?;
+    let formatted = format_summary(&src).map_err(|mut e| {
+        // Errors in `SUMMARY.md` are relative to the `src` directory.
+        if let Some(diagnostic) = e.downcast_mut::<Diagnostic>() {
+            diagnostic.path = config.book.src.join(&diagnostic.path);
+        }
+        e
+    })?;
+    if formatted == src {
+        info!("{} is already formatted", path.display());
+        return Ok(());
+    }
+    if check {
+        bail!(
+            "{} is not formatted, run `mdbook summary fmt` to format it",
+            path.display()
+        );
+    }
+    fs::write(&path, formatted)?;
+    info!("Formatted {}", path.display());
+    Ok(())
+}
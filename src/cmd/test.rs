@@ -10,6 +10,9 @@ pub fn make_subcommand() -> Command {
     Command::new("test")
         .about("Tests that a book's Rust code samples compile")
         .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
+        .arg_message_format()
         .arg(
             Arg::new("chapter")
                 .short('c')
@@ -33,6 +36,7 @@ pub fn make_subcommand() -> Command {
 
 // test command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
+    set_message_format(args);
     let library_paths: Vec<&str> = args
         .get_many("library-path")
         .map(|it| it.map(String::as_str).collect())
@@ -41,7 +45,7 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     let chapter: Option<&str> = args.get_one::<String>("chapter").map(|s| s.as_str());
 
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(book_dir)?;
+    let mut book = MDBook::load_with_options(book_dir, &load_options(args))?;
 
     match chapter {
         Some(_) => book.test_chapter(library_paths, chapter),
@@ -1,7 +1,7 @@
 use super::command_prelude::*;
 use crate::{get_book_dir, open};
 use anyhow::Result;
-use mdbook_driver::MDBook;
+use mdbook_driver::{LoadOptions, MDBook};
 use std::path::{Path, PathBuf};
 use tracing::error;
 
@@ -14,6 +14,8 @@ pub fn make_subcommand() -> Command {
         .about("Watches a book's files and rebuilds it on changes")
         .arg_dest_dir()
         .arg_root_dir()
+        .arg_profile()
+        .arg_strict()
         .arg_open()
         .arg_watcher()
 }
@@ -36,7 +38,7 @@ impl WatcherKind {
 // Watch command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(&book_dir)?;
+    let mut book = MDBook::load_with_options(&book_dir, &load_options(args))?;
 
     let update_config = |book: &mut MDBook| {
         set_dest_dir(args, book);
@@ -54,7 +56,13 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     }
 
     let watcher = WatcherKind::from_str(args.get_one::<String>("watcher").unwrap());
-    rebuild_on_change(watcher, &book_dir, &update_config, &|| {});
+    rebuild_on_change(
+        watcher,
+        &book_dir,
+        &load_options(args),
+        &update_config,
+        &|| {},
+    );
 
     Ok(())
 }
@@ -62,12 +70,17 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
 pub fn rebuild_on_change(
     kind: WatcherKind,
     book_dir: &Path,
+    options: &LoadOptions,
     update_config: &dyn Fn(&mut MDBook),
     post_build: &dyn Fn(),
 ) {
     match kind {
-        WatcherKind::Poll => self::poller::rebuild_on_change(book_dir, update_config, post_build),
-        WatcherKind::Native => self::native::rebuild_on_change(book_dir, update_config, post_build),
+        WatcherKind::Poll => {
+            self::poller::rebuild_on_change(book_dir, options, update_config, post_build)
+        }
+        WatcherKind::Native => {
+            self::native::rebuild_on_change(book_dir, options, update_config, post_build)
+        }
     }
 }
 
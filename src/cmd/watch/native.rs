@@ -1,7 +1,7 @@
 //! A filesystem watcher using native operating system facilities.
 
 use ignore::gitignore::Gitignore;
-use mdbook_driver::MDBook;
+use mdbook_driver::{LoadOptions, MDBook};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
 use std::thread::sleep;
@@ -10,12 +10,13 @@ use tracing::{error, info, warn};
 
 pub fn rebuild_on_change(
     book_dir: &Path,
+    options: &LoadOptions,
     update_config: &dyn Fn(&mut MDBook),
     post_build: &dyn Fn(),
 ) {
     use notify::RecursiveMode::*;
 
-    let mut book = MDBook::load(book_dir).unwrap_or_else(|e| {
+    let mut book = MDBook::load_with_options(book_dir, options).unwrap_or_else(|e| {
         error!("failed to load book: {e}");
         std::process::exit(1);
     });
@@ -88,7 +89,7 @@ pub fn rebuild_on_change(
 
         if !paths.is_empty() {
             info!("Files changed: {paths:?}");
-            match MDBook::load(book_dir) {
+            match MDBook::load_with_options(book_dir, options) {
                 Ok(mut b) => {
                     update_config(&mut b);
                     if let Err(e) = b.build() {
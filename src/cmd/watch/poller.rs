@@ -5,7 +5,7 @@
 //! had problems correctly reporting changes.
 
 use ignore::gitignore::Gitignore;
-use mdbook_driver::MDBook;
+use mdbook_driver::{LoadOptions, MDBook};
 use pathdiff::diff_paths;
 use std::collections::HashMap;
 use std::fs::FileType;
@@ -17,10 +17,11 @@ use walkdir::WalkDir;
 /// Calls the closure when a book source file is changed, blocking indefinitely.
 pub fn rebuild_on_change(
     book_dir: &Path,
+    options: &LoadOptions,
     update_config: &dyn Fn(&mut MDBook),
     post_build: &dyn Fn(),
 ) {
-    let mut book = MDBook::load(book_dir).unwrap_or_else(|e| {
+    let mut book = MDBook::load_with_options(book_dir, options).unwrap_or_else(|e| {
         error!("failed to load book: {e}");
         std::process::exit(1);
     });
@@ -57,7 +58,7 @@ pub fn rebuild_on_change(
 
         if !paths.is_empty() {
             info!("Files changed: {paths:?}");
-            match MDBook::load(book_dir) {
+            match MDBook::load_with_options(book_dir, options) {
                 Ok(mut b) => {
                     update_config(&mut b);
                     if let Err(e) = b.build() {
@@ -24,7 +24,12 @@ fn main() {
     let res = match command.get_matches().subcommand() {
         Some(("init", sub_matches)) => cmd::init::execute(sub_matches),
         Some(("build", sub_matches)) => cmd::build::execute(sub_matches),
+        Some(("check", sub_matches)) => cmd::check::execute(sub_matches),
         Some(("clean", sub_matches)) => cmd::clean::execute(sub_matches),
+        Some(("config", sub_matches)) => cmd::config::execute(sub_matches),
+        Some(("summary", sub_matches)) => cmd::summary::execute(sub_matches),
+        #[cfg(feature = "search")]
+        Some(("search", sub_matches)) => cmd::search::execute(sub_matches),
         #[cfg(feature = "watch")]
         Some(("watch", sub_matches)) => cmd::watch::execute(sub_matches),
         #[cfg(feature = "serve")]
@@ -68,8 +73,11 @@ fn create_clap_command() -> Command {
         )
         .subcommand(cmd::init::make_subcommand())
         .subcommand(cmd::build::make_subcommand())
+        .subcommand(cmd::check::make_subcommand())
         .subcommand(cmd::test::make_subcommand())
         .subcommand(cmd::clean::make_subcommand())
+        .subcommand(cmd::config::make_subcommand())
+        .subcommand(cmd::summary::make_subcommand())
         .subcommand(
             Command::new("completions")
                 .about("Generate shell completions for your shell to stdout")
@@ -82,6 +90,8 @@ fn create_clap_command() -> Command {
                 ),
         );
 
+    #[cfg(feature = "search")]
+    let app = app.subcommand(cmd::search::make_subcommand());
     #[cfg(feature = "watch")]
     let app = app.subcommand(cmd::watch::make_subcommand());
     #[cfg(feature = "serve")]
@@ -85,3 +85,60 @@ fn dest_dir_relative_path() {
     });
     assert!(current_dir.join("foo/index.html").exists());
 }
+
+// Incremental builds only render the chapters that changed.
+#[test]
+fn incremental() {
+    let mut test = BookTest::from_dir("build/incremental");
+    test.build();
+    assert!(test.dir.join("book/.mdbook-cache.json").exists());
+    // Tamper with the output so we can see whether it gets rendered again.
+    test.change_file("book/chapter_1.html", "not rendered again")
+        .change_file("book/chapter_2.html", "not rendered again")
+        .change_file("src/chapter_2.md", "# Changed chapter 2\n")
+        .build()
+        .check_file("book/chapter_1.html", str!["not rendered again"])
+        .check_file_contains("book/chapter_2.html", "Changed chapter 2");
+
+    // Changing the config does a full build.
+    test.change_file(
+        "book.toml",
+        "[book]\ntitle = \"new title\"\n\n[output.html]\nincremental = true\nhash-files = false\n",
+    )
+    .build()
+    .check_file_contains("book/chapter_1.html", "new title");
+
+    // So does changing the structure of the book.
+    test.change_file("book/chapter_1.html", "not rendered again")
+        .change_file("src/SUMMARY.md", "- [Chapter 1](chapter_1.md)\n")
+        .build()
+        .check_file_contains("book/chapter_1.html", "Chapter 1");
+    assert!(!test.dir.join("book/chapter_2.html").exists());
+}
+
+// With hashed file names, `toc.js` and the search index are hashed too, and a
+// change to the search index rebuilds every page that refers to it.
+#[test]
+fn incremental_hash_files() {
+    let mut test = BookTest::from_dir("build/incremental");
+    test.change_file(
+        "book.toml",
+        "[book]\ntitle = \"incremental\"\n\n[output.html]\nincremental = true\n",
+    )
+    .build();
+    let toc_js = glob_one(&test.dir, "book/toc-*.js");
+    let searchindex_js = glob_one(&test.dir, "book/searchindex-*.js");
+    assert!(!test.dir.join("book/toc.js").exists());
+    assert!(!test.dir.join("book/searchindex.js").exists());
+
+    test.change_file("book/chapter_1.html", "not rendered again")
+        .change_file("src/chapter_2.md", "# Changed chapter 2\n")
+        .build()
+        .check_file_contains("book/chapter_1.html", "Chapter 1");
+    // The old search index is removed, and `toc.js` is unchanged.
+    assert!(!searchindex_js.exists());
+    assert_eq!(glob_one(&test.dir, "book/toc-*.js"), toc_js);
+    let searchindex_js = glob_one(&test.dir, "book/searchindex-*.js");
+    let name = searchindex_js.file_name().unwrap().to_str().unwrap();
+    test.check_file_contains("book/chapter_1.html", name);
+}
@@ -0,0 +1,93 @@
+//! Tests for the conditional preprocessor.
+
+use crate::prelude::*;
+
+const BOOK_TOML: &str = "\
+[book]
+title = \"Conditional\"
+
+[preprocessor.conditional]
+flags = [\"beta\"]
+
+[output.html]
+
+[output.markdown]
+
+[profile.public.preprocessor.conditional]
+flags = []
+";
+
+const CHAPTER: &str = "\
+# Chapter 1
+
+{{#if renderer == \"html\"}}
+Only in HTML.
+{{#else}}
+Not in HTML.
+{{/if}}
+
+{{#if beta && profile != \"public\"}}
+Beta feature.
+{{/if}}
+";
+
+// Blocks are kept or removed based on the renderer, profile, and flags.
+#[test]
+fn conditional() {
+    BookTest::init(|_| {})
+        .change_file("book.toml", BOOK_TOML)
+        .change_file("src/chapter_1.md", CHAPTER)
+        .run("build", |_| {})
+        .check_main_file(
+            "book/html/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<p>Only in HTML.</p>
+<p>Beta feature.</p>
+"##]],
+        )
+        .check_file(
+            "book/markdown/chapter_1.md",
+            str![[r#"
+# Chapter 1
+
+Not in HTML.
+
+Beta feature.
+
+"#]],
+        )
+        .run("build --profile public", |_| {})
+        .check_file(
+            "book/markdown/chapter_1.md",
+            str![[r#"
+# Chapter 1
+
+Not in HTML.
+
+
+"#]],
+        );
+}
+
+// Blocks that aren't closed are an error.
+#[test]
+fn unclosed_block() {
+    BookTest::init(|_| {})
+        .change_file("book.toml", BOOK_TOML)
+        .change_file("src/chapter_1.md", "# Chapter 1\n\n{{#if beta}}\nBeta.\n")
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [conditional] `{{#if}}` is not closed with `{{/if}}`
+ --> src/chapter_1.md:3:1
+  |
+3 | {{#if beta}}
+  | ^^^^^^^^^^^^
+ERROR 1 chapter has invalid conditional blocks
+
+"#]]);
+        });
+}
@@ -1,6 +1,7 @@
 //! Tests for book configuration loading.
 
 use crate::prelude::*;
+use snapbox::file;
 
 // Test that config can load from environment variable.
 #[test]
@@ -125,7 +126,7 @@ ERROR Invalid configuration file
   |
 1 | foo = 123
   | ^^^
-unknown field `foo`, expected one of `book`, `build`, `rust`, `output`, `preprocessor`
+unknown field `foo`, expected one of `book`, `build`, `rust`, `output`, `preprocessor`, `profile`, `language`
 
 
 "#]]);
@@ -150,7 +151,7 @@ ERROR Invalid configuration file
   |
 1 | [other]
   |  ^^^^^
-unknown field `other`, expected one of `book`, `build`, `rust`, `output`, `preprocessor`
+unknown field `other`, expected one of `book`, `build`, `rust`, `output`, `preprocessor`, `profile`, `language`
 
 
 "#]]);
@@ -165,19 +166,26 @@ fn bad_config_in_book_table() {
             "book.toml",
             "[book]\n\
              title = \"bad-config\"\n\
-             foo = 123"
+             src-dir = \"src\"\n\
+             foo = 123",
         )
         .run("build", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ WARN unknown key `book.foo` in book.toml
+ WARN unknown key `book.src-dir` in book.toml, did you mean `src`?
+ INFO Book building has started
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        .run("build --strict", |cmd| {
             cmd.expect_failure()
                 .expect_stdout(str![[""]])
                 .expect_stderr(str![[r#"
-ERROR Invalid configuration file
-[TAB]Caused by: TOML parse error at line 3, column 1
-  |
-3 | foo = 123
-  | ^^^
-unknown field `foo`, expected one of `title`, `authors`, `description`, `src`, `language`, `text-direction`
-
+ERROR unknown key `book.foo` in book.toml
+ERROR unknown key `book.src-dir` in book.toml, did you mean `src`?
+ERROR found 2 unknown key(s) in book.toml
 
 "#]]);
         });
@@ -193,16 +201,79 @@ fn bad_config_in_rust_table() {
              title = \"bad-config\"\n",
         )
         .run("build", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ WARN unknown key `rust.title` in book.toml
+ INFO Book building has started
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        .run("build --strict", |cmd| {
             cmd.expect_failure()
                 .expect_stdout(str![[""]])
                 .expect_stderr(str![[r#"
-ERROR Invalid configuration file
-[TAB]Caused by: TOML parse error at line 2, column 1
-  |
-2 | title = "bad-config"
-  | ^^^^^
-unknown field `title`, expected `edition`
+ERROR unknown key `rust.title` in book.toml
+ERROR found 1 unknown key(s) in book.toml
+
+"#]]);
+        });
+}
+
+// An unknown key in the html renderer table, including one in a profile.
+#[test]
+fn bad_config_in_html_table() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "[output.html]\n\
+             additonal-css = [\"custom.css\"]\n\
+             git-repository-url = \"https://example.com/\"\n\
+             [profile.public.output.html.search]\n\
+             enable = false\n\
+             limit = 10\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ WARN unknown key `output.html.additonal-css` in book.toml, did you mean `additional-css`?
+ WARN unknown key `profile.public.output.html.search.limit` in book.toml, did you mean `limit-results`?
+ INFO Book building has started
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        // The rest of the table is still used.
+        .check_file_contains("book/index.html", "https://example.com/");
+}
+
+// A third-party preprocessor table is checked against its `known-keys`.
+#[test]
+fn bad_config_in_known_keys_table() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "[preprocessor.example]\n\
+             command = \"mdbook-example\"\n\
+             renderers = [\"other\"]\n\
+             known-keys = [\"level\"]\n\
+             levle = 2\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ WARN unknown key `preprocessor.example.levle` in book.toml, did you mean `level`?
+ INFO Book building has started
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
 
+"#]]);
+        })
+        .run("build --strict", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR unknown key `preprocessor.example.levle` in book.toml, did you mean `level`?
+ERROR found 1 unknown key(s) in book.toml
 
 "#]]);
         });
@@ -342,3 +413,212 @@ preprocessor saw custom config
         // No HTML output
         .check_file_list("book", str![[""]]);
 }
+
+// Applying a profile with `--profile` and `build.profile`.
+#[test]
+fn profile() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Internal\"\n\
+             [output.html]\n\
+             git-repository-url = \"https://example.com/\"\n\
+             [profile.public.book]\n\
+             title = \"Public\"\n\
+             [profile.public.build]\n\
+             build-dir = \"public\"\n",
+        )
+        .run("build", |_| {})
+        .check_file_contains("book/index.html", "<title>Chapter 1 - Internal</title>")
+        .run("build --profile public", |_| {})
+        .check_file_contains("public/index.html", "<title>Chapter 1 - Public</title>")
+        .check_file_contains("public/index.html", "https://example.com/")
+        // Environment variables take precedence over the profile.
+        .run("build --profile public", |cmd| {
+            cmd.env("MDBOOK_BOOK__TITLE", "From env");
+        })
+        .check_file_contains("public/index.html", "<title>Chapter 1 - From env</title>")
+        .change_file(
+            "book.toml",
+            "[build]\n\
+             profile = \"public\"\n\
+             [profile.public.build]\n\
+             build-dir = \"default-profile\"\n",
+        )
+        .run("build", |_| {})
+        .check_file_contains("default-profile/index.html", "<title>Chapter 1</title>");
+}
+
+// Selecting a profile that doesn't exist.
+#[test]
+fn missing_profile() {
+    BookTest::from_dir("config/empty")
+        .change_file("book.toml", "[profile.public]\n[profile.staff]\n")
+        .run("build --profile other", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR profile `other` not found, available profiles are: `public`, `staff`
+
+"#]]);
+        });
+}
+
+// The JSON Schema of book.toml.
+#[test]
+fn config_schema() {
+    BookTest::from_dir("config/empty").run("config schema", |cmd| {
+        cmd.expect_stdout(file!["config/schema.json"])
+            .expect_stderr(str![[""]]);
+    });
+}
+
+// Reading resolved values with `mdbook config get`.
+#[test]
+fn config_get() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Original\"\n\
+             [profile.public.output.html]\n\
+             site-url = \"/public/\"\n",
+        )
+        .run("config get book.title", |cmd| {
+            cmd.expect_stdout(str![[r#"
+Original
+
+"#]])
+                .expect_stderr(str![[""]]);
+        })
+        .run("config get book.title", |cmd| {
+            cmd.env("MDBOOK_BOOK__TITLE", "From env")
+                .expect_stdout(str![[r#"
+From env
+
+"#]]);
+        })
+        .run("config get build.build-dir", |cmd| {
+            cmd.expect_stdout(str![[r#"
+book
+
+"#]]);
+        })
+        .run("config get output.html --profile public", |cmd| {
+            cmd.expect_stdout(str![[r#"
+site-url = "/public/"
+
+"#]]);
+        })
+        .run("config get output.html.site-url", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR `output.html.site-url` is not set
+
+"#]]);
+        });
+}
+
+// Listing all values with `mdbook config list`.
+#[test]
+fn config_list() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Listed\"\n\
+             [output.html.print]\n\
+             enable = false\n",
+        )
+        .run("config list", |cmd| {
+            cmd.expect_stdout(str![[r#"
+book.authors = []
+book.language = "en"
+book.src = "src"
+book.title = "Listed"
+build.build-dir = "book"
+build.create-missing = true
+build.extra-watch-dirs = []
+build.summary-glob-order = "name"
+build.use-default-preprocessors = true
+output.html.print.enable = false
+
+"#]])
+                .expect_stderr(str![[""]]);
+        });
+}
+
+// Changing book.toml with `mdbook config set`.
+#[test]
+fn config_set() {
+    BookTest::from_dir("config/empty")
+        .change_file(
+            "book.toml",
+            "# Settings for the book.\n\
+             [book]\n\
+             title = \"Example\" # shown in the sidebar\n",
+        )
+        .run("config set book.title Changed", |cmd| {
+            cmd.expect_stderr(str![[r#"
+ INFO Set `book.title` in [ROOT]/book.toml
+
+"#]]);
+        })
+        .run("config set output.html.print.enable false", |_| {})
+        .run("config set book.authors", |cmd| {
+            cmd.args(&[r#"["Ferris"]"#]);
+        })
+        .run("config set build.build-dir out", |_| {})
+        .check_file(
+            "book.toml",
+            str![[r##"
+# Settings for the book.
+[book]
+title = "Changed" # shown in the sidebar
+authors = ["Ferris"]
+
+[output.html.print]
+enable = false
+
+[build]
+build-dir = "out"
+
+"##]],
+        );
+}
+
+// Setting keys that don't exist or values of the wrong type.
+#[test]
+fn config_set_invalid() {
+    BookTest::from_dir("config/empty")
+        .run("config set book.titel Example", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR unknown key `book.titel` in book.toml, did you mean `title`?
+
+"#]]);
+        })
+        .run("config set output.html.site-ulr /", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR unknown key `output.html.site-ulr` in book.toml, did you mean `site-url`?
+
+"#]]);
+        })
+        .run("config set output.html.mathjax-support maybe", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR Failed to deserialize `output.html`
+[TAB]Caused by: invalid type: string "maybe", expected a boolean
+in `mathjax-support`
+
+
+"#]]);
+        })
+        .check_file("book.toml", str![[""]]);
+}
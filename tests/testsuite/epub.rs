@@ -0,0 +1,193 @@
+//! Tests for the EPUB renderer.
+
+use crate::prelude::*;
+use snapbox::Data;
+use std::io::Read;
+
+fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> String {
+    let mut contents = String::new();
+    archive
+        .by_name(name)
+        .unwrap_or_else(|e| panic!("expected `{name}` in the archive: {e}"))
+        .read_to_string(&mut contents)
+        .unwrap();
+    contents
+}
+
+// Builds a basic EPUB and checks its structure.
+#[test]
+fn basic() {
+    let mut test = BookTest::from_dir("epub/basic");
+    test.run("build", |cmd| {
+        cmd.env("SOURCE_DATE_EPOCH", "1700000000")
+            .expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the epub backend
+ INFO EPUB book written to `[ROOT]/book/Basic EPUB.epub`
+
+"#]]);
+    });
+    let file = std::fs::File::open(test.dir.join("book/Basic EPUB.epub")).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+
+    let names: Vec<_> = archive.file_names().collect();
+    assert_eq!(names[0], "mimetype");
+    let mut names: Vec<_> = names.into_iter().map(str::to_string).collect();
+    names.sort();
+    test.assert.eq(
+        names.join("\n"),
+        str![[r#"
+META-INF/container.xml
+OEBPS/appendix.html
+OEBPS/chapter_1.html
+OEBPS/content.opf
+OEBPS/css/epub.css
+OEBPS/fonts/open-sans-v17-all-charsets-300.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-300italic.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-600.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-600italic.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-700.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-700italic.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-800.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-800italic.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-italic.woff2
+OEBPS/fonts/open-sans-v17-all-charsets-regular.woff2
+OEBPS/fonts/source-code-pro-v11-all-charsets-500.woff2
+OEBPS/images/pixel.png
+OEBPS/intro.html
+OEBPS/nav.xhtml
+OEBPS/part/nested.html
+mimetype
+"#]],
+    );
+    let mimetype = archive.by_name("mimetype").unwrap();
+    assert_eq!(mimetype.compression(), zip::CompressionMethod::Stored);
+    drop(mimetype);
+    assert_eq!(read_entry(&mut archive, "mimetype"), "application/epub+zip");
+
+    test.assert.eq(
+        read_entry(&mut archive, "OEBPS/nav.xhtml"),
+        Data::from(str![[r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="en" lang="en" dir="ltr">
+<head>
+    <meta charset="UTF-8" />
+    <title>Table of Contents</title>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>Table of Contents</h1>
+    <ol>
+      <li><a href="intro.html">Introduction</a></li>
+      <li><span>User Guide</span>
+        <ol>
+          <li><a href="chapter_1.html">1. Chapter 1</a>
+            <ol>
+              <li><a href="part/nested.html">1.1. Nested</a></li>
+            </ol>
+          </li>
+        </ol>
+      </li>
+      <li><a href="appendix.html">Appendix</a></li>
+    </ol>
+  </nav>
+</body>
+</html>
+
+"#]]).raw(),
+    );
+    test.assert.eq(
+        read_entry(&mut archive, "OEBPS/content.opf"),
+        Data::from(str![[r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="en" dir="ltr">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:isbn:9780000000000</dc:identifier>
+    <dc:title>Basic EPUB</dc:title>
+    <dc:language>en</dc:language>
+    <dc:creator>Alice</dc:creator>
+    <dc:description>An example book.</dc:description>
+    <meta property="dcterms:modified">2023-11-14T22:13:20Z</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="chapter-0" href="intro.html" media-type="application/xhtml+xml"/>
+    <item id="chapter-1" href="chapter_1.html" media-type="application/xhtml+xml" properties="svg"/>
+    <item id="chapter-2" href="part/nested.html" media-type="application/xhtml+xml"/>
+    <item id="chapter-3" href="appendix.html" media-type="application/xhtml+xml"/>
+    <item id="resource-0" href="css/epub.css" media-type="text/css"/>
+    <item id="resource-1" href="fonts/open-sans-v17-all-charsets-300.woff2" media-type="font/woff2"/>
+    <item id="resource-2" href="fonts/open-sans-v17-all-charsets-300italic.woff2" media-type="font/woff2"/>
+    <item id="resource-3" href="fonts/open-sans-v17-all-charsets-regular.woff2" media-type="font/woff2"/>
+    <item id="resource-4" href="fonts/open-sans-v17-all-charsets-italic.woff2" media-type="font/woff2"/>
+    <item id="resource-5" href="fonts/open-sans-v17-all-charsets-600.woff2" media-type="font/woff2"/>
+    <item id="resource-6" href="fonts/open-sans-v17-all-charsets-600italic.woff2" media-type="font/woff2"/>
+    <item id="resource-7" href="fonts/open-sans-v17-all-charsets-700.woff2" media-type="font/woff2"/>
+    <item id="resource-8" href="fonts/open-sans-v17-all-charsets-700italic.woff2" media-type="font/woff2"/>
+    <item id="resource-9" href="fonts/open-sans-v17-all-charsets-800.woff2" media-type="font/woff2"/>
+    <item id="resource-10" href="fonts/open-sans-v17-all-charsets-800italic.woff2" media-type="font/woff2"/>
+    <item id="resource-11" href="fonts/source-code-pro-v11-all-charsets-500.woff2" media-type="font/woff2"/>
+    <item id="resource-12" href="images/pixel.png" media-type="image/png"/>
+  </manifest>
+  <spine page-progression-direction="ltr">
+    <itemref idref="chapter-0"/>
+    <itemref idref="chapter-1"/>
+    <itemref idref="chapter-2"/>
+    <itemref idref="chapter-3"/>
+  </spine>
+</package>
+
+"#]]).raw(),
+    );
+    test.assert.eq(
+        read_entry(&mut archive, "OEBPS/intro.html"),
+        Data::from(str![[r##"
+<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="en" lang="en" dir="ltr">
+<head>
+    <meta charset="UTF-8" />
+    <title>Introduction</title>
+    <link rel="stylesheet" type="text/css" href="css/epub.css" />
+</head>
+<body>
+<h1 id="introduction"><a class="header" href="#introduction">Introduction</a></h1>
+<p>A paragraph.<br /></p>
+<hr />
+<p><label class="checkbox-label"><input class="checkbox-img" type="checkbox" /><img src="images/pixel.png" alt="An image" /><span class="img-wrapper"><img src="images/pixel.png" alt="An image" /></span></label></p>
+<p><label class="checkbox-label"><input class="checkbox-img" type="checkbox" /><img src="https://example.com/image.png" alt="Remote" /><span class="img-wrapper"><img src="https://example.com/image.png" alt="Remote" /></span></label></p>
+
+</body>
+</html>
+
+"##]]).raw(),
+    );
+    test.assert.eq(
+        read_entry(&mut archive, "OEBPS/part/nested.html"),
+        Data::from(str![[r##"
+<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="en" lang="en" dir="ltr">
+<head>
+    <meta charset="UTF-8" />
+    <title>Nested</title>
+    <link rel="stylesheet" type="text/css" href="../css/epub.css" />
+</head>
+<body>
+<h1 id="nested"><a class="header" href="#nested">Nested</a></h1>
+<p><label class="checkbox-label"><input class="checkbox-img" type="checkbox" /><img src="../images/pixel.png" alt="An image" /><span class="img-wrapper"><img src="../images/pixel.png" alt="An image" /></span></label></p>
+<p>See <a href="../chapter_1.html">chapter 1</a>.</p>
+
+</body>
+</html>
+
+"##]]).raw(),
+    );
+    let chapter_1 = read_entry(&mut archive, "OEBPS/chapter_1.html");
+    assert!(chapter_1.contains(
+        r#"<svg viewBox="0 0 16 16" width="18" height="18" xmlns="http://www.w3.org/2000/svg">"#
+    ));
+    assert!(chapter_1.contains(r#"<span class="boring">"#));
+    assert!(chapter_1.contains(r#"<ol class="footnote-definition">"#));
+}
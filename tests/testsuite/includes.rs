@@ -62,7 +62,11 @@ fn recursive_include() {
         .run("build", |cmd| {
             cmd.expect_stderr(str![[r#"
  INFO Book building has started
-ERROR Stack depth exceeded in recursive.md. Check for cyclic includes
+ERROR [include-depth] Stack depth exceeded. Check for cyclic includes
+ --> src/recursive.md:2:1
+  |
+2 | {{#include recursive.md}}
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^
  INFO Running the html backend
  INFO HTML book written to `[ROOT]/book`
 
@@ -127,3 +131,180 @@ fn rustdoc_include() {
 }</code></pre>
 "##]]);
 }
+
+// An include of a file that doesn't exist is reported at the location of the
+// include, in the terminal or as JSON.
+#[test]
+fn missing_include() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/chapter_1.md",
+            "---\n\
+             description: Has front matter.\n\
+             ---\n\
+             # Chapter 1\n\
+             \n\
+             Before {{#include missing.rs}} after.\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [include-read] Could not read file for link {{#include missing.rs}} ([ROOT]/src/missing.rs)
+ --> src/chapter_1.md:6:8
+  |
+6 | Before {{#include missing.rs}} after.
+  |        ^^^^^^^^^^^^^^^^^^^^^^^
+  = note: failed to read `[ROOT]/src/missing.rs`
+  = note: [NOT_FOUND]
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        .run("build --message-format=json", |cmd| {
+            cmd.expect_stdout(str![[r#"
+{"severity":"error","code":"include-read","message":"Could not read file for link {{#include missing.rs}} ([ROOT]/src/missing.rs)","path":"src/chapter_1.md","line":6,"column":8,"end_line":6,"end_column":31,"source_line":"Before {{#include missing.rs}} after.","notes":["failed to read `[ROOT]/src/missing.rs`","[NOT_FOUND]"]}
+
+"#]]).expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        });
+}
+
+// Checks the `shift` and `dedent` options of #include.
+#[test]
+fn include_options() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\
+             \n\
+             {{#include part.md shift=1}}\n\
+             \n\
+             ```rust\n\
+             {{#include snippet.rs dedent}}\n\
+             ```\n",
+        )
+        .change_file(
+            "src/part.md",
+            "# Part\n\
+             \n\
+             Setext\n\
+             ------\n",
+        )
+        .change_file(
+            "src/snippet.rs",
+            "    fn main() {\n        println!(\"hi\");\n    }\n",
+        )
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<h2 id="part"><a class="header" href="#part">Part</a></h2>
+<h3 id="setext"><a class="header" href="#setext">Setext</a></h3>
+<pre class="playground"><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+"##]],
+        );
+}
+
+// Checks including a Rust item by name and lines between regexes.
+#[test]
+fn include_selectors() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\
+             \n\
+             ```rust\n\
+             {{#include config.rs:fn=parse dedent}}\n\
+             ```\n\
+             \n\
+             {{#include notes.txt:/^Start/,/^End/}}\n\
+             \n\
+             {{#include config.rs:struct=Missing}}\n",
+        )
+        .change_file(
+            "src/config.rs",
+            "struct Config;\n\
+             \n\
+             impl Config {\n    \
+                 /// Parses it.\n    \
+                 fn parse(s: &str) -> Config {\n        \
+                     let _ = \"}\";\n        \
+                     Config\n    \
+                 }\n\
+             }\n",
+        )
+        .change_file(
+            "src/notes.txt",
+            "Ignored.\nStart\nKept.\nEnd\nIgnored.\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [include-read] Could not find the lines for link {{#include config.rs:struct=Missing}} ([ROOT]/src/config.rs)
+ --> src/chapter_1.md:9:1
+  |
+9 | {{#include config.rs:struct=Missing}}
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+  = note: no `struct Missing` item found
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<pre class="playground"><code class="language-rust"><span class="boring">#![allow(unused)]
+</span><span class="boring">fn main() {
+</span>/// Parses it.
+fn parse(s: &amp;str) -&gt; Config {
+    let _ = "}";
+    Config
+}
+<span class="boring">}</span></code></pre>
+<p>Start
+Kept.
+End</p>
+<p>{{#include config.rs:struct=Missing}}</p>
+"##]],
+        );
+}
+
+// A regex selector with a space is split and has no closing `/`.
+#[test]
+fn include_unterminated_pattern() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\n{{#include notes.txt:/^## Usage/}}\n",
+        )
+        .change_file("src/notes.txt", "## Usage\nKept.\n")
+        .run("build", |cmd| {
+            cmd.expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [include-read] Could not parse link {{#include notes.txt:/^## Usage/}}: the regular expression `/^##` has no closing `/`, use `/s` instead of spaces in it
+ --> src/chapter_1.md:3:1
+  |
+3 | {{#include notes.txt:/^## Usage/}}
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+        })
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<p>{{#include notes.txt:/^## Usage/}}</p>
+"##]],
+        );
+}
@@ -184,6 +184,7 @@ build-dir = "out"
 create-missing = true
 use-default-preprocessors = true
 extra-watch-dirs = []
+summary-glob-order = "name"
 
 "#]],
     )
@@ -0,0 +1,229 @@
+//! Tests for multilingual books.
+
+use crate::prelude::*;
+
+/// A book written in English and Japanese, where one chapter hasn't been
+/// translated.
+fn multilingual_book() -> BookTest {
+    let mut test = BookTest::empty();
+    test.change_file(
+        "book.toml",
+        "[book]\n\
+         title = \"Example\"\n\
+         \n\
+         [language.en]\n\
+         default = true\n\
+         name = \"English\"\n\
+         \n\
+         [language.ja]\n\
+         name = \"日本語\"\n\
+         title = \"例\"\n",
+    )
+    .change_file(
+        "src/en/SUMMARY.md",
+        "# Summary\n\n- [Intro](intro.md)\n- [Guide](guide.md)\n",
+    )
+    .change_file("src/en/intro.md", "# Intro\n\n![logo](logo.png)\n")
+    .change_file("src/en/guide.md", "# Guide\n")
+    .change_file("src/en/logo.png", "")
+    .change_file(
+        "src/ja/SUMMARY.md",
+        "# 目次\n\n- [はじめに](intro.md)\n- [ガイド](guide.md)\n",
+    )
+    .change_file("src/ja/intro.md", "# はじめに\n");
+    test
+}
+
+// Each language is built into its own directory, and chapters that haven't
+// been translated are taken from the default language.
+#[test]
+fn build_languages() {
+    multilingual_book()
+        .run("build", |cmd| {
+            cmd.expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Building the `en` language
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book/en`
+ INFO Building the `ja` language
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book/ja`
+
+"#]]);
+        })
+        .check_file("book/en/logo.png", str![[""]])
+        .check_file("book/ja/logo.png", str![[""]])
+        .check_main_file(
+            "book/ja/intro.html",
+            str![[r##"<h1 id="はじめに"><a class="header" href="#はじめに">はじめに</a></h1>"##]],
+        )
+        .check_main_file(
+            "book/ja/guide.html",
+            str![[r##"<h1 id="guide"><a class="header" href="#guide">Guide</a></h1>"##]],
+        )
+        .check_file_contains("book/ja/intro.html", r#"<html lang="ja" class="light sidebar-visible" dir="ltr">"#)
+        .check_file_contains("book/ja/intro.html", "<title>はじめに - 例</title>")
+        .check_file_contains(
+            "book/ja/intro.html",
+            r#"<link rel="alternate" hreflang="en" href="../en/intro.html">"#,
+        )
+        .check_file_contains(
+            "book/ja/intro.html",
+            r#"<li role="none"><a role="menuitem" class="language language-selected" href="../ja/intro.html" hreflang="ja" lang="ja">日本語</a></li>"#,
+        )
+        .check_file_contains(
+            "book/en/intro.html",
+            r#"<li role="none"><a role="menuitem" class="language" href="../ja/intro.html" hreflang="ja" lang="ja">日本語</a></li>"#,
+        )
+        .check_file_contains(
+            "book/index.html",
+            r#"<meta http-equiv="refresh" content="0; URL=en/index.html">"#,
+        );
+}
+
+// A multilingual book needs exactly one default language.
+#[test]
+fn missing_default_language() {
+    multilingual_book()
+        .change_file("book.toml", "[language.en]\n[language.ja]\n")
+        .run("build", |cmd| {
+            cmd.expect_failure().expect_stderr(str![[r#"
+ERROR a multilingual book needs a default language, set `default = true` in one of the `[language]` tables
+
+"#]]);
+        });
+}
+
+// The includes of a chapter that hasn't been translated are relative to the
+// chapter in the default language, which is where errors are reported.
+#[test]
+fn fallback_chapter_includes() {
+    multilingual_book()
+        .change_file("src/en/guide.md", "# Guide\n\n{{#include snippet.txt}}\n")
+        .change_file("src/en/snippet.txt", "Included text.\n")
+        .build()
+        .check_main_file(
+            "book/ja/guide.html",
+            str![[r##"
+<h1 id="guide"><a class="header" href="#guide">Guide</a></h1>
+<p>Included text.</p>
+"##]],
+        )
+        .change_file("src/en/guide.md", "# Guide\n\n{{#include missing.txt}}\n")
+        .run("build", |cmd| {
+            cmd.expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Building the `en` language
+ERROR [include-read] Could not read file for link {{#include missing.txt}} ([ROOT]/src/en/missing.txt)
+ --> src/en/guide.md:3:1
+  |
+3 | {{#include missing.txt}}
+  | ^^^^^^^^^^^^^^^^^^^^^^^^
+  = note: failed to read `[ROOT]/src/en/missing.txt`
+  = note: [NOT_FOUND]
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book/en`
+ INFO Building the `ja` language
+ERROR [include-read] Could not read file for link {{#include missing.txt}} ([ROOT]/src/en/missing.txt)
+ --> src/en/guide.md:3:1
+  |
+3 | {{#include missing.txt}}
+  | ^^^^^^^^^^^^^^^^^^^^^^^^
+  = note: failed to read `[ROOT]/src/en/missing.txt`
+  = note: [NOT_FOUND]
+ INFO Running the html backend
+ INFO HTML book written to `[ROOT]/book/ja`
+
+"#]]);
+        });
+}
+
+// A chapter that hasn't been translated keeps its path in the translation,
+// for the search settings of chapters, while its edit link points to the file
+// in the default language.
+#[cfg(feature = "search")]
+#[test]
+fn fallback_chapter_paths() {
+    multilingual_book()
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Example\"\n\
+             \n\
+             [output.html]\n\
+             edit-url-template = \"https://example.com/edit/{path}\"\n\
+             \n\
+             [output.html.search.chapter]\n\
+             \"guide.md\" = { enable = false }\n\
+             \n\
+             [language.en]\n\
+             default = true\n\
+             \n\
+             [language.ja]\n",
+        )
+        .build()
+        .check_file_contains(
+            "book/ja/guide.html",
+            r#"<a href="https://example.com/edit/src/en/guide.md""#,
+        )
+        .check_file_contains(
+            "book/ja/intro.html",
+            r#"<a href="https://example.com/edit/src/ja/intro.md""#,
+        );
+}
+
+// `mdbook check` checks the links of every language.
+#[test]
+fn check_languages() {
+    multilingual_book()
+        .change_file("src/en/guide.md", "# Guide\n\n[Missing](missing.md)\n")
+        .change_file("src/ja/intro.md", "# はじめに\n\n[ガイド](guide.md#nope)\n")
+        .run("check", |cmd| {
+            cmd.expect_failure().expect_stderr(str![[r#"
+ERROR [broken-link] broken link `missing.html`: `missing.html` does not exist
+ --> src/en/guide.md:3:1
+  |
+3 | [Missing](missing.md)
+  | ^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `guide.html#nope`: anchor `#nope` not found in `guide.html`
+ --> src/ja/intro.md:3:1
+  |
+3 | [ガイド](guide.md#nope)
+  | ^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `missing.html`: `missing.html` does not exist
+ --> src/en/guide.md:3:1
+  |
+3 | [Missing](missing.md)
+  | ^^^^^^^^^^^^^^^^^^^^^
+ERROR found 3 broken link(s)
+
+"#]]);
+        });
+}
+
+// `mdbook search` searches the default language with its own settings.
+#[cfg(feature = "search")]
+#[test]
+fn search_default_language() {
+    let mut test = BookTest::empty();
+    test.change_file(
+        "book.toml",
+        "[book]\n\
+         title = \"Beispiel\"\n\
+         \n\
+         [language.de]\n\
+         default = true\n\
+         name = \"Deutsch\"\n",
+    )
+    .change_file("src/de/SUMMARY.md", "- [Bücher](buecher.md)\n")
+    .change_file("src/de/buecher.md", "# Bücher\n\nDie Bücher im Regal.\n")
+    .run("search Buch", |cmd| {
+        cmd.expect_stdout(str![[r#"
+Bücher » Bücher
+  buecher.html#bücher
+  Die Bücher im Regal.
+
+
+"#]]);
+    });
+}
@@ -0,0 +1,132 @@
+//! Tests for checking links.
+
+use crate::prelude::*;
+
+// Broken links fail the build with `check-links = "error"`.
+#[test]
+fn broken_links_error() {
+    BookTest::from_dir("link_check/broken_links").run("build", |cmd| {
+        cmd.expect_failure().expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the html backend
+ERROR [broken-link] broken link `missing.html`: `missing.html` does not exist
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `nested/page.html#nope`: anchor `#nope` not found in `nested/page.html`
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `print.html#nowhere`: anchor `#nowhere` not found in `print.html`
+ --> src/intro.md:8:1
+  |
+8 | <a href="print.html#details">Print page</a> and <a href="print.html#nowhere">bad print link</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `../intro.html#missing-section`: anchor `#missing-section` not found in `intro.html`
+ --> src/nested/page.md:8:1
+  |
+8 | Text from the <a href="../intro.md#missing-section">intro</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `nested/missing.html`: redirect from `/gone.html`: `nested/missing.html` does not exist
+ --> book.toml:9:17
+  |
+9 | "/gone.html" = "nested/missing.html"
+  |                 ^^^^^^^^^^^^^^^^^^^
+ERROR Rendering failed
+[TAB]Caused by: found 5 broken link(s)
+
+"#]]);
+    });
+}
+
+// Broken links are only reported with `check-links = "warn"`.
+#[test]
+fn broken_links_warn() {
+    BookTest::from_dir("link_check/broken_links").run("build", |cmd| {
+        cmd.env("MDBOOK_OUTPUT__HTML__CHECK_LINKS", "warn")
+            .expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the html backend
+ WARN [broken-link] broken link `missing.html`: `missing.html` does not exist
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ WARN [broken-link] broken link `nested/page.html#nope`: anchor `#nope` not found in `nested/page.html`
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ WARN [broken-link] broken link `print.html#nowhere`: anchor `#nowhere` not found in `print.html`
+ --> src/intro.md:8:1
+  |
+8 | <a href="print.html#details">Print page</a> and <a href="print.html#nowhere">bad print link</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ WARN [broken-link] broken link `../intro.html#missing-section`: anchor `#missing-section` not found in `intro.html`
+ --> src/nested/page.md:8:1
+  |
+8 | Text from the <a href="../intro.md#missing-section">intro</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ WARN [broken-link] broken link `nested/missing.html`: redirect from `/gone.html`: `nested/missing.html` does not exist
+ --> book.toml:9:17
+  |
+9 | "/gone.html" = "nested/missing.html"
+  |                 ^^^^^^^^^^^^^^^^^^^
+ INFO HTML book written to `[ROOT]/book`
+
+"#]]);
+    });
+}
+
+// The `check` command reports broken links.
+#[test]
+fn check_command() {
+    BookTest::from_dir("link_check/broken_links").run("check", |cmd| {
+        cmd.expect_failure().expect_stderr(str![[r#"
+ERROR [broken-link] broken link `missing.html`: `missing.html` does not exist
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `nested/page.html#nope`: anchor `#nope` not found in `nested/page.html`
+ --> src/intro.md:6:1
+  |
+6 | A [missing page](missing.md) and a [missing heading](nested/page.md#nope).
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `print.html#nowhere`: anchor `#nowhere` not found in `print.html`
+ --> src/intro.md:8:1
+  |
+8 | <a href="print.html#details">Print page</a> and <a href="print.html#nowhere">bad print link</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `../intro.html#missing-section`: anchor `#missing-section` not found in `intro.html`
+ --> src/nested/page.md:8:1
+  |
+8 | Text from the <a href="../intro.md#missing-section">intro</a>.
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR [broken-link] broken link `nested/missing.html`: redirect from `/gone.html`: `nested/missing.html` does not exist
+ --> book.toml:9:17
+  |
+9 | "/gone.html" = "nested/missing.html"
+  |                 ^^^^^^^^^^^^^^^^^^^
+ERROR found 5 broken link(s)
+
+"#]]);
+    })
+    .run("check --message-format=json", |cmd| {
+        cmd.expect_failure()
+            .expect_stdout(str![[r#"
+{"severity":"error","code":"broken-link","message":"broken link `missing.html`: `missing.html` does not exist","path":"src/intro.md","line":6,"column":1,"end_line":6,"end_column":75,"source_line":"A [missing page](missing.md) and a [missing heading](nested/page.md#nope).","notes":[]}
+{"severity":"error","code":"broken-link","message":"broken link `nested/page.html#nope`: anchor `#nope` not found in `nested/page.html`","path":"src/intro.md","line":6,"column":1,"end_line":6,"end_column":75,"source_line":"A [missing page](missing.md) and a [missing heading](nested/page.md#nope).","notes":[]}
+{"severity":"error","code":"broken-link","message":"broken link `print.html#nowhere`: anchor `#nowhere` not found in `print.html`","path":"src/intro.md","line":8,"column":1,"end_line":8,"end_column":97,"source_line":"<a href=/"print.html#details/">Print page</a> and <a href=/"print.html#nowhere/">bad print link</a>.","notes":[]}
+{"severity":"error","code":"broken-link","message":"broken link `../intro.html#missing-section`: anchor `#missing-section` not found in `intro.html`","path":"src/nested/page.md","line":8,"column":1,"end_line":8,"end_column":63,"source_line":"Text from the <a href=/"../intro.md#missing-section/">intro</a>.","notes":[]}
+{"severity":"error","code":"broken-link","message":"broken link `nested/missing.html`: redirect from `/gone.html`: `nested/missing.html` does not exist","path":"book.toml","line":9,"column":17,"end_line":9,"end_column":36,"source_line":"/"/gone.html/" = /"nested/missing.html/"","notes":[]}
+
+"#]])
+            .expect_stderr(str![[r#"
+ERROR found 5 broken link(s)
+
+"#]]);
+    });
+}
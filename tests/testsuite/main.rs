@@ -7,10 +7,14 @@
 mod book_test;
 mod build;
 mod cli;
+mod conditional;
 mod config;
+mod epub;
 mod includes;
 mod index;
 mod init;
+mod language;
+mod link_check;
 mod markdown;
 mod playground;
 mod preprocessor;
@@ -20,9 +24,12 @@ mod renderer;
 mod rendering;
 #[cfg(feature = "search")]
 mod search;
+mod summary;
 mod test;
 mod theme;
 mod toc;
+mod vars;
+mod xref;
 
 mod prelude {
     pub use crate::book_test::{BookTest, glob_one, read_to_string};
@@ -1,7 +1,9 @@
 //! Tests for special markdown rendering.
 
 use crate::prelude::*;
+use mdbook_driver::builtin_renderers::MarkdownRenderer;
 use snapbox::file;
+use std::path::Path;
 
 // Checks custom header id and classes.
 #[test]
@@ -176,3 +178,59 @@ fn admonitions() {
             file!["markdown/admonitions/expected_disabled/admonitions.html"],
         );
 }
+
+// Front matter is removed from the content and given to preprocessors and
+// renderers as chapter metadata. A thematic break with a setext heading is not
+// front matter.
+#[test]
+fn front_matter() {
+    let mut test = BookTest::from_dir("markdown/front_matter");
+    test.check_main_file(
+        "book/yaml.html",
+        str![[r##"<h1 id="yaml"><a class="header" href="#yaml">YAML</a></h1>"##]],
+    )
+    .check_file_contains(
+        "book/yaml.html",
+        r#"<meta name="description" content="A chapter with YAML front matter.">"#,
+    )
+    .check_main_file(
+        "book/toml.html",
+        str![[r##"<h1 id="toml"><a class="header" href="#toml">TOML</a></h1>"##]],
+    )
+    .check_file_contains(
+        "book/toml.html",
+        r#"<meta name="description" content="A chapter with TOML front matter.">"#,
+    )
+    .check_main_file(
+        "book/thematic_break.html",
+        str![[r##"
+<hr>
+<h1 id="thematic-break"><a class="header" href="#thematic-break">Thematic break</a></h1>
+<hr>
+"##]],
+    )
+    .check_file_contains(
+        "book/thematic_break.html",
+        r#"<meta name="description" content="Book description">"#,
+    )
+    .check_main_file(
+        "book/setext.html",
+        str![[r##"
+<hr>
+<h2 id="a-setext-heading"><a class="header" href="#a-setext-heading">A setext heading</a></h2>
+<p>Not front matter.</p>
+"##]],
+    );
+
+    let (_, ctx) = test
+        .load_book()
+        .preprocess_book(&MarkdownRenderer::new())
+        .unwrap();
+    let yaml = &ctx.chapter_metadata[Path::new("yaml.md")];
+    assert_eq!(yaml.authors, ["Alice", "Bob"]);
+    assert_eq!(yaml.tags, ["intro"]);
+    assert!(!yaml.draft);
+    assert_eq!(yaml.extra["weight"], 10);
+    assert!(ctx.chapter_metadata[Path::new("toml.md")].draft);
+    assert_eq!(ctx.chapter_metadata.len(), 2);
+}
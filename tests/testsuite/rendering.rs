@@ -96,6 +96,15 @@ fn hidelines() {
     BookTest::from_dir("rendering/hidelines").check_all_main_files();
 }
 
+// Tests for highlighting code blocks when the book is built.
+#[cfg(feature = "highlight")]
+#[test]
+fn builtin_highlighting() {
+    BookTest::from_dir("rendering/builtin_highlighting")
+        .check_all_main_files()
+        .check_file_doesnt_contain("book/highlighting.html", "highlight.js");
+}
+
 // Tests for code blocks of basic rust code.
 #[test]
 fn language_rust_playground() {
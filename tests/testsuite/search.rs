@@ -134,3 +134,289 @@ ERROR Rendering failed
 "#]]);
     });
 }
+
+// The index is tokenized and stemmed according to `book.language`.
+#[test]
+fn language_specific_index() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file("book.toml", "[book]\nlanguage = \"fr\"\n")
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapitre\n\nLes configurations du livre.\n",
+        )
+        .build();
+    let index = read_book_index(&test.dir);
+    assert_eq!(index["language"]["code"], "fr");
+    assert_eq!(index["index"]["lang"], "French");
+    assert_eq!(
+        index["index"]["pipeline"],
+        serde_json::json!(["mdbook-trimmer", "mdbook-stop-words", "mdbook-stemmer"])
+    );
+    let bodyidx = &index["index"]["index"]["body"]["root"];
+    assert_eq!(bodyidx["c"]["o"]["n"]["f"]["i"]["g"]["u"]["r"]["df"], 1);
+    assert!(bodyidx["l"]["e"]["s"].is_null());
+
+    test.change_file("book.toml", "[book]\nlanguage = \"zh-CN\"\n")
+        .change_file("src/chapter_1.md", "# 第一章\n\n配置文件。\n")
+        .build();
+    let index = read_book_index(&test.dir);
+    assert_eq!(index["language"]["tokenizer"], "cjk");
+    let bodyidx = &index["index"]["index"]["body"]["root"];
+    for token in ["配置", "置文", "文件"] {
+        let node = token.chars().fold(bodyidx, |node, c| &node[c.to_string()]);
+        assert_eq!(node["df"], 1, "{token}");
+    }
+}
+
+// A search index larger than `shard-threshold` is split into shards.
+#[test]
+fn sharded_search_index() {
+    let mut test = BookTest::from_dir("search/reasonable_search_index");
+    test.change_file(
+        "book.toml",
+        "[book]\n\
+         title = \"search_test\"\n\
+         \n\
+         [output.html.search]\n\
+         shard-threshold = 0\n",
+    )
+    .build()
+    .check_file_list(
+        "book/searchindex",
+        str![[r#"
+book/searchindex/d0-[..].js
+book/searchindex/t0-[..].js
+"#]],
+    );
+    snapbox::assert_data_eq!(
+        read_to_string(glob_one(&test.dir, "book/searchindex/t0*.js")),
+        str!["window.search.addShard('t0', 'H4sI[..]');"]
+    );
+
+    // The URLs of the shards follow the index.
+    let index_path = glob_one(&test.dir, "book/searchindex*.js");
+    let index = read_to_string(&index_path);
+    let (index, shard_urls) = index.split_once('\n').unwrap();
+    snapbox::assert_data_eq!(
+        shard_urls,
+        str![[
+            r#"window.search.shard_urls = {t0: "searchindex/t0-[..].js", d0: "searchindex/d0-[..].js"};"#
+        ]]
+    );
+    std::fs::write(&index_path, index).unwrap();
+    let index = read_book_index(&test.dir);
+    assert_eq!(index["shards"]["docs"], 1);
+    assert_eq!(index["shards"]["docs_per_shard"], 15);
+    // Every character that starts a term is in the only shard of terms.
+    let term_shards = index["shards"]["terms"].as_object().unwrap();
+    assert!(term_shards.contains_key("s"));
+    assert!(term_shards.values().all(|shard| shard == 0));
+    assert_eq!(
+        index["index"]["documentStore"]["docs"]["0"],
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        index["index"]["index"]["body"]["root"],
+        serde_json::json!({"docs": {}, "df": 0})
+    );
+}
+
+// Code blocks, marked elements, admonitions, and regions can be left out of
+// the index.
+#[test]
+fn excluded_content() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file(
+        "book.toml",
+        "[output.html.search]\n\
+         exclude-code = true\n\
+         exclude-classes = [\"no-search\"]\n\
+         exclude-admonitions = [\"note\"]\n",
+    )
+    .change_file(
+        "src/chapter_1.md",
+        "# Chapter 1\n\
+         \n\
+         Kept paragraph.\n\
+         \n\
+         ```rust\n\
+         fn listing() {}\n\
+         ```\n\
+         \n\
+         <div class=\"generated no-search\">Generated listing.</div>\n\
+         \n\
+         > [!NOTE]\n\
+         > Noted aside.\n\
+         \n\
+         > [!TIP]\n\
+         > Kept tip.\n\
+         \n\
+         <!-- search:off -->\n\
+         \n\
+         Hidden region.\n\
+         \n\
+         <!-- search:on -->\n\
+         \n\
+         Kept ending.\n",
+    )
+    .build();
+    let index = read_book_index(&test.dir);
+    assert_eq!(
+        index["index"]["documentStore"]["docs"]["0"]["body"],
+        "Kept paragraph. Tip Kept tip. Kept ending."
+    );
+}
+
+// Unknown admonition kinds are rejected.
+#[test]
+fn exclude_admonitions_validation_error() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file(
+        "book.toml",
+        "[output.html.search]\n\
+         exclude-admonitions = [\"hint\"]\n",
+    )
+    .run("build", |cmd| {
+        cmd.expect_failure().expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the html backend
+ERROR Rendering failed
+[TAB]Caused by: [output.html.search] unknown admonition kind `hint` in `exclude-admonitions`, expected one of `note`, `tip`, `important`, `warning`, or `caution`
+
+"#]]);
+    });
+}
+
+// `mdbook search` ranks the results like the search of the HTML output.
+#[test]
+fn search_command() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file(
+        "book.toml",
+        "[output.html.search]\n\
+         teaser-word-count = 8\n\
+         \n\
+         [output.html.search.chapter]\n\
+         \"hidden.md\" = { enable = false }\n",
+    )
+    .change_file(
+        "src/SUMMARY.md",
+        "- [Chapter 1](chapter_1.md)\n\
+         - [Themes](themes.md)\n\
+         - [Hidden](hidden.md)\n",
+    )
+    .change_file(
+        "src/chapter_1.md",
+        "# Chapter 1\n\
+         \n\
+         The book starts here. It explains how the pages of a theme are \
+         configured, and which files are used.\n",
+    )
+    .change_file(
+        "src/themes.md",
+        "# Themes\n\
+         \n\
+         ## Configuring a theme\n\
+         \n\
+         A theme changes the look of the book.\n",
+    )
+    .change_file(
+        "src/hidden.md",
+        "# Hidden\n\nThe configuration of the theme.\n",
+    )
+    .run("search configuring", |cmd| {
+        cmd.expect_stdout(str![[r#"
+Themes » Configuring a theme
+  themes.html#configuring-a-theme
+  A theme changes the look of the book.
+
+Chapter 1 » Chapter 1
+  chapter_1.html#chapter-1
+  theme are configured, and which files are used.
+
+
+"#]]);
+    })
+    .run("search nothing", |cmd| {
+        cmd.expect_stdout(str![""]).expect_stderr(str![[r#"
+ INFO No search results for `nothing`
+
+"#]]);
+    });
+}
+
+// Synonyms are searched as the same word, and chapters can boost their results.
+#[test]
+fn synonyms_and_chapter_boosts() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file(
+        "book.toml",
+        "[output.html.search]\n\
+         synonyms = { async = [\"futures\", \"await\"] }\n\
+         \n\
+         [output.html.search.chapter]\n\
+         \"landing.md\" = { boost = 20 }\n",
+    )
+    .change_file(
+        "src/SUMMARY.md",
+        "- [Futures](futures.md)\n\
+         - [Landing](landing.md)\n",
+    )
+    .change_file(
+        "src/futures.md",
+        "# Futures\n\nA future is ready once it is awaited.\n",
+    )
+    .change_file(
+        "src/landing.md",
+        "# Landing\n\nStart here for async code.\n",
+    )
+    .build();
+    let index = read_book_index(&test.dir);
+    assert_eq!(
+        index["synonyms"],
+        serde_json::json!({"await": "async", "futur": "async"})
+    );
+    assert_eq!(index["doc_boosts"], serde_json::json!({"1": 20.0}));
+    assert_eq!(
+        index["index"]["pipeline"],
+        serde_json::json!(["trimmer", "stopWordFilter", "stemmer", "mdbook-synonyms"])
+    );
+    // The words of the chapters are indexed as the first word of the group.
+    let bodyidx = &index["index"]["index"]["body"]["root"];
+    assert_eq!(bodyidx["a"]["s"]["y"]["n"]["c"]["df"], 2);
+    assert!(bodyidx["f"]["u"]["t"].is_null());
+
+    test.run("search futures", |cmd| {
+        cmd.expect_stdout(str![[r#"
+Landing » Landing
+  landing.html#landing
+  Start here for async code.
+
+Futures » Futures
+  futures.html#futures
+  A future is ready once it is awaited.
+
+
+"#]]);
+    });
+}
+
+// Synonyms must be words of the search index.
+#[test]
+fn synonyms_validation_error() {
+    let mut test = BookTest::init(|_| {});
+    test.change_file(
+        "book.toml",
+        "[output.html.search]\n\
+         synonyms = { map = [\"hash map\"] }\n",
+    )
+    .run("build", |cmd| {
+        cmd.expect_failure().expect_stderr(str![[r#"
+ INFO Book building has started
+ INFO Running the html backend
+ERROR Rendering failed
+[TAB]Caused by: [output.html.search.synonyms] `hash map` is not a single word in the search index
+
+"#]]);
+    });
+}
@@ -0,0 +1,111 @@
+//! Tests for the `mdbook summary` command.
+
+use crate::prelude::*;
+
+// Formatting SUMMARY.md with `mdbook summary fmt`.
+#[test]
+fn summary_fmt() {
+    BookTest::from_dir("summary/fmt")
+        .run("summary fmt --check", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR [ROOT]/src/SUMMARY.md is not formatted, run `mdbook summary fmt` to format it
+
+"#]]);
+        })
+        .run("summary fmt", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ INFO Formatted [ROOT]/src/SUMMARY.md
+
+"#]]);
+        })
+        .check_file(
+            "src/SUMMARY.md",
+            str![[r#"
+# Summary
+
+[Introduction](intro.md)
+
+# Guide
+
+- [Getting `started`](start.md)
+    - [Installation](install.md)
+- [Draft chapter]()
+
+---
+
+# Reference
+
+- [Options](options.md)
+
+[Contributors](contributors.md)
+
+"#]],
+        )
+        .run("summary fmt --check", |cmd| {
+            cmd.expect_stdout(str![[""]]).expect_stderr(str![[r#"
+ INFO [ROOT]/src/SUMMARY.md is already formatted
+
+"#]]);
+        });
+}
+
+// `mdbook summary fmt` doesn't remove what it can't format.
+#[test]
+fn summary_fmt_keeps_comments() {
+    let summary = "# Summary\n\
+                   - [Chapter 1](chapter_1.md)\n\
+                   <!-- keep me -->\n";
+    BookTest::init(|_| {})
+        .change_file("src/SUMMARY.md", summary)
+        .run("summary fmt", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR [summary-fmt] HTML can't be kept when formatting SUMMARY.md
+ --> src/SUMMARY.md:3:1
+  |
+3 | <!-- keep me -->
+  | ^^^^^^^^^^^^^^^^
+
+"#]]);
+        })
+        .check_file("src/SUMMARY.md", summary);
+}
+
+// Errors in SUMMARY.md point to the line with the problem.
+#[test]
+fn summary_parse_error() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/SUMMARY.md",
+            "# Summary\n\
+             \n\
+             - [Chapter 1](chapter_1.md)\n\
+             \n\
+             [Appendix](appendix.md)\n\
+             \n\
+             - [Chapter 2](chapter_2.md)\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ERROR [summary-parse] Suffix chapters cannot be followed by a list
+ --> src/SUMMARY.md:7:1
+  |
+7 | - [Chapter 2](chapter_2.md)
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+"#]]);
+        })
+        .run("build --message-format=json", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[r#"
+{"severity":"error","code":"summary-parse","message":"Suffix chapters cannot be followed by a list","path":"src/SUMMARY.md","line":7,"column":1,"end_line":8,"end_column":1,"source_line":"- [Chapter 2](chapter_2.md)","notes":[]}
+
+"#]])
+                .expect_stderr(str![[""]]);
+        });
+}
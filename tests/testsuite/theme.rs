@@ -176,3 +176,44 @@ book/fonts/myfont-[..].woff
 "#]],
         );
 }
+
+// The strings of the user interface are translated to the book's language,
+// and can be overridden in book.toml.
+#[test]
+fn translated_ui_strings() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             language = \"fr\"\n\
+             \n\
+             [output.html.ui-strings.fr]\n\
+             next-chapter = \"Suite\"\n",
+        )
+        .change_file(
+            "src/SUMMARY.md",
+            "- [Chapter 1](chapter_1.md)\n- [Chapter 2](chapter_2.md)\n",
+        )
+        .change_file("src/chapter_2.md", "# Chapter 2\n")
+        .check_file_contains(
+            "book/chapter_1.html",
+            r#"<a href="print.html" title="Imprimer ce livre" aria-label="Imprimer ce livre">"#,
+        )
+        .check_file_contains(
+            "book/chapter_1.html",
+            "<p>Appuyez sur <kbd>?</kbd> pour afficher cette aide</p>",
+        )
+        .check_file_contains(
+            "book/chapter_1.html",
+            r#"<a rel="next prefetch" href="chapter_2.html" class="nav-chapters next" title="Suite" aria-label="Suite" aria-keyshortcuts="Right">"#,
+        )
+        // The strings of the JavaScript keep their placeholders.
+        .check_file_contains(
+            "book/chapter_1.html",
+            r#"data-copy-to-clipboard="Copier dans le presse-papiers""#,
+        )
+        .check_file_contains(
+            "book/chapter_1.html",
+            r#"data-search-results="{count} résultats pour « {term} » :""#,
+        );
+}
@@ -189,3 +189,129 @@ fn summary_with_markdown_formatting() {
 "#]],
         );
 }
+
+// The "On this page" outline of the headings in a chapter.
+#[test]
+fn page_toc() {
+    let mut test = BookTest::from_dir("toc/page_toc");
+    test.build();
+    let html = read_to_string(test.dir.join("book/chapter_1.html"));
+    let start = html.find(r#"<nav class="page-toc""#).unwrap();
+    let end = start + html[start..].find("</nav>").unwrap();
+    let nav: String = html[start..end]
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    test.assert.eq(
+        nav,
+        str![[r##"
+<nav class="page-toc" aria-label="On this page">
+<div class="page-toc-title">On this page</div>
+<ol>
+<li class="page-toc-level-2">
+<a href="#getting-started">Getting started</a>
+<ol>
+<li class="page-toc-level-3">
+<a href="#details">Details</a>
+</li>
+</ol>
+</li>
+<li class="page-toc-level-2">
+<a href="#next">Next</a>
+<ol>
+<li class="page-toc-level-3">
+<a href="#more">More</a>
+</li>
+</ol>
+</li>
+</ol>
+"##]],
+    );
+    test.check_file_doesnt_contain("book/no_headings.html", "page-toc");
+}
+
+// A glob entry in SUMMARY.md expands to the files that match it.
+#[test]
+fn summary_glob() {
+    BookTest::from_dir("toc/summary_glob")
+        .check_toc_js(str![[r#"
+<ol class="chapter">
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="intro.html">
+<strong aria-hidden="true">1.</strong> Intro</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/index.html">
+<strong aria-hidden="true">2.</strong> Reference</a>
+</span>
+<ol class="section">
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/2-apple.html">
+<strong aria-hidden="true">2.1.</strong> apple command</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/3-mango.html">
+<strong aria-hidden="true">2.2.</strong> Mango</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/10-advanced.html">
+<strong aria-hidden="true">2.3.</strong> Advanced options</a>
+</span>
+</li>
+</ol>
+</li>
+</ol>
+"#]])
+        .change_file(
+            "book.toml",
+            "[build]\n\
+             summary-glob-order = \"title\"\n",
+        )
+        .build()
+        .check_toc_js(str![[r#"
+<ol class="chapter">
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="intro.html">
+<strong aria-hidden="true">1.</strong> Intro</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/index.html">
+<strong aria-hidden="true">2.</strong> Reference</a>
+</span>
+<ol class="section">
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/10-advanced.html">
+<strong aria-hidden="true">2.1.</strong> Advanced options</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/2-apple.html">
+<strong aria-hidden="true">2.2.</strong> apple command</a>
+</span>
+</li>
+<li class="chapter-item expanded ">
+<span class="chapter-link-wrapper">
+<a href="reference/3-mango.html">
+<strong aria-hidden="true">2.3.</strong> Mango</a>
+</span>
+</li>
+</ol>
+</li>
+</ol>
+"#]]);
+}
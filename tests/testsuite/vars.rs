@@ -0,0 +1,200 @@
+//! Tests for the vars preprocessor.
+
+use crate::prelude::*;
+
+// Variables are replaced with values from book.toml and the environment.
+#[test]
+fn vars() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Vars\"\n\
+             \n\
+             [preprocessor.vars]\n\
+             version = \"1.2.3\"\n\
+             snippets = \"examples\"\n",
+        )
+        .change_file("src/examples/hello.txt", "Hello from an include.")
+        .change_file(
+            "src/chapter_1.md",
+            "# {{#var book.title}} {{#var version}}\n\
+             \n\
+             {{#include {{#var snippets}}/hello.txt}}\n\
+             \n\
+             Shell is {{#var env.MDBOOK_TEST_VAR}}, not `\\{{#var version}}`.\n",
+        )
+        .run("build", |cmd| {
+            cmd.env("MDBOOK_TEST_VAR", "bash");
+        })
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="vars-123"><a class="header" href="#vars-123">Vars 1.2.3</a></h1>
+<p>Hello from an include.</p>
+<p>Shell is bash, not <code>{{#var version}}</code>.</p>
+"##]],
+        );
+}
+
+// Variables are only replaced if there is a `[preprocessor.vars]` table.
+#[test]
+fn vars_opt_in() {
+    BookTest::init(|_| {})
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\nVersion {{#var version}}.\n",
+        )
+        .run("build", |_| {})
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<p>Version {{#var version}}.</p>
+"##]],
+        );
+}
+
+// Variables in code spans and code blocks are left as they are.
+#[test]
+fn vars_in_code() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Vars\"\n\
+             \n\
+             [preprocessor.vars]\n\
+             version = \"1.2.3\"\n",
+        )
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\
+             \n\
+             Use `{{#var version}}` for {{#var version}}.\n\
+             \n\
+             ```text\n\
+             {{#var version}}\n\
+             ```\n",
+        )
+        .run("build", |_| {})
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<p>Use <code>{{#var version}}</code> for 1.2.3.</p>
+<pre><code class="language-text">{{#var version}}
+</code></pre>
+"##]],
+        );
+}
+
+// Variables in included files are replaced, unless the file is included in
+// code.
+#[test]
+fn vars_in_included_files() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Vars\"\n\
+             \n\
+             [preprocessor.vars]\n\
+             version = \"1.2.3\"\n",
+        )
+        .change_file(
+            "src/part.md",
+            "Version {{#var version}}, not `{{#var version}}`.\n\
+             \n\
+             {{#include nested.md}}\n",
+        )
+        .change_file("src/nested.md", "Nested {{#var version}}.\n")
+        .change_file("src/example.txt", "{{#var version}}\n")
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\
+             \n\
+             {{#include part.md}}\n\
+             \n\
+             ```text\n\
+             {{#include example.txt}}\n\
+             ```\n",
+        )
+        .run("build", |_| {})
+        .check_main_file(
+            "book/chapter_1.html",
+            str![[r##"
+<h1 id="chapter-1"><a class="header" href="#chapter-1">Chapter 1</a></h1>
+<p>Version 1.2.3, not <code>{{#var version}}</code>.</p>
+<p>Nested 1.2.3.</p>
+<pre><code class="language-text">{{#var version}}
+</code></pre>
+"##]],
+        );
+}
+
+// Undefined variables fail the build.
+#[test]
+fn undefined_var() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Vars\"\n\
+             \n\
+             [preprocessor.vars]\n",
+        )
+        .change_file(
+            "src/chapter_1.md",
+            "# Chapter 1\n\nVersion {{#var version}} of {{#var env.MDBOOK_TEST_UNSET}}.\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [var-undefined] variable `version` is not defined
+ --> src/chapter_1.md:3:9
+  |
+3 | Version {{#var version}} of {{#var env.MDBOOK_TEST_UNSET}}.
+  |         ^^^^^^^^^^^^^^^^
+ERROR [var-undefined] environment variable `MDBOOK_TEST_UNSET` is not set
+ --> src/chapter_1.md:3:29
+  |
+3 | Version {{#var version}} of {{#var env.MDBOOK_TEST_UNSET}}.
+  |                             ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+ERROR 2 variables could not be replaced
+
+"#]]);
+        });
+}
+
+// Undefined variables in included files are reported at the include.
+#[test]
+fn undefined_var_in_included_file() {
+    BookTest::init(|_| {})
+        .change_file(
+            "book.toml",
+            "[book]\n\
+             title = \"Vars\"\n\
+             \n\
+             [preprocessor.vars]\n",
+        )
+        .change_file("src/part.md", "Version {{#var version}}.\n")
+        .change_file("src/chapter_1.md", "# Chapter 1\n\n{{#include part.md}}\n")
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [var-undefined] variable `version` is not defined
+ --> src/chapter_1.md:3:1
+  |
+3 | {{#include part.md}}
+  | ^^^^^^^^^^^^^^^^^^^^
+  = note: in the included file `part.md`
+ERROR 1 variable could not be replaced
+
+"#]]);
+        });
+}
@@ -0,0 +1,111 @@
+//! Tests for cross-references to heading labels.
+
+use crate::prelude::*;
+
+// Links to labels are replaced with relative links to the headings.
+#[test]
+fn xref() {
+    BookTest::from_dir("xref/basic")
+        .check_main_file("book/index.html", str![[r##"
+<h1 id="intro"><a class="header" href="#intro">Introduction</a></h1>
+<p>Start with <a href="guide/setup.html#setup">Setting <em>up</em></a>, or read <a href="guide/index.html#guide" title="The guide">the guide</a>.</p>
+<p>See also <a href="#intro">Introduction</a> and <code>{{#ref not-resolved}}</code>.</p>
+"##]])
+        .check_main_file("book/guide/index.html", str![[r##"
+<h1 id="guide"><a class="header" href="#guide">Guide</a></h1>
+<h2 id="shared"><a class="header" href="#shared">Shared <code>section</code></a></h2>
+<p>Back to <a href="../index.html#intro">Introduction</a>.</p>
+"##]])
+        .check_main_file("book/guide/setup.html", str![[r##"
+<h1 id="setup"><a class="header" href="#setup">Setting <em>up</em></a></h1>
+<p>See <a href="index.html#shared">Shared <code>section</code></a> and <a href="index.html#guide">Guide</a>.</p>
+"##]]);
+}
+
+// Unknown labels fail the build.
+#[test]
+fn unknown_label() {
+    BookTest::from_dir("xref/basic")
+        .change_file(
+            "src/guide/setup.md",
+            "# Setup {#setup}\n\nSee [](@shraed) and {{#ref nope}}.\n",
+        )
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [xref-label] unknown label `shraed`
+ --> src/guide/setup.md:3:5
+  |
+3 | See [](@shraed) and {{#ref nope}}.
+  |     ^^^^^^^^^^^
+  = note: did you mean `shared`?
+ERROR [xref-label] unknown label `nope`
+ --> src/guide/setup.md:3:21
+  |
+3 | See [](@shraed) and {{#ref nope}}.
+  |                     ^^^^^^^^^^^^^
+ERROR 2 cross-references could not be resolved
+
+"#]]);
+        });
+}
+
+// A label defined in more than one chapter can't be referred to.
+#[test]
+fn duplicate_label() {
+    BookTest::from_dir("xref/basic")
+        .change_file("src/guide/setup.md", "# Setup {#intro}\n\n[](@intro)\n")
+        .run("build", |cmd| {
+            cmd.expect_failure()
+                .expect_stdout(str![[""]])
+                .expect_stderr(str![[r#"
+ INFO Book building has started
+ERROR [xref-label] unknown label `setup`
+ --> src/README.md:3:12
+  |
+3 | Start with [](@setup), or read [the guide](@guide "The guide").
+  |            ^^^^^^^^^^
+ERROR [xref-label] label `intro` is defined in more than one chapter
+ --> src/README.md:5:10
+  |
+5 | See also {{#ref intro}} and `{{#ref not-resolved}}`.
+  |          ^^^^^^^^^^^^^^
+  = note: defined in `index.md`, `guide/setup.md`
+ERROR [xref-label] label `intro` is defined in more than one chapter
+ --> src/guide/setup.md:3:1
+  |
+3 | [](@intro)
+  | ^^^^^^^^^^
+  = note: defined in `index.md`, `guide/setup.md`
+ERROR [xref-label] label `intro` is defined in more than one chapter
+ --> src/guide/index.md:5:9
+  |
+5 | Back to [](@intro).
+  |         ^^^^^^^^^^
+  = note: defined in `index.md`, `guide/setup.md`
+ERROR 4 cross-references could not be resolved
+
+"#]]);
+        });
+}
+
+// Without a `[preprocessor.xref]` table, links to `@` paths and `{{#ref}}` are
+// left alone.
+#[test]
+fn xref_not_enabled() {
+    BookTest::from_dir("xref/basic")
+        .change_file("book.toml", "[book]\ntitle = \"Cross-references\"\n")
+        .change_file(
+            "src/guide/setup.md",
+            "# Setup {#setup}\n\nInstall [typings](@types/node) with {{#ref npm}}.\n",
+        )
+        .check_main_file(
+            "book/guide/setup.html",
+            str![[r##"
+<h1 id="setup"><a class="header" href="#setup">Setup</a></h1>
+<p>Install <a href="@types/node">typings</a> with {{#ref npm}}.</p>
+"##]],
+        );
+}